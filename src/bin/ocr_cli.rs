@@ -0,0 +1,328 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::thread;
+
+use clap::{Parser, ValueEnum};
+use ocr_service::config::app_config::AppConfig;
+use ocr_service::models::{error::ErrorType, images::ImagesQueryParams, languages::TesseractModel};
+use ocr_service::utils::{
+    languages::get_available_languages_with_models,
+    metrics::model_attributes,
+    ocr::{decode_image, recognize_image},
+    validations::validate_language_params,
+};
+use serde::Serialize;
+
+/// File extensions picked up when a directory is given as input.
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "gif"];
+
+/// Perform OCR on image files offline, with the same engine configuration as the OCR Service.
+///
+/// The Tesseract data path and default language are read from the same environment variables
+/// (and `.env` file) as the server.
+#[derive(Debug, Parser)]
+#[command(name = "ocr_cli", version)]
+struct Args {
+    /// Image files, directories or glob patterns (e.g. "scans/*.png") to process.
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// The language to use for the OCR. Defaults to SERVICE_DEFAULT_LANGUAGE.
+    #[arg(short, long)]
+    language: Option<String>,
+
+    /// The model to use for the OCR. Requires --language.
+    #[arg(short, long)]
+    model: Option<String>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Write the results to this directory instead of next to each input file. Fails before
+    /// processing if two inputs have the same file name.
+    #[arg(short, long)]
+    output_dir: Option<PathBuf>,
+
+    /// The number of files to process in parallel. Defaults to the number of CPUs.
+    #[arg(short, long)]
+    jobs: Option<usize>,
+
+    /// The Tesseract data path. Defaults to TESSDATA_PATH.
+    #[arg(long)]
+    tessdata: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    /// The extracted text, written to `<name>.txt`.
+    Text,
+    /// A JSON document with the file, language, model and text, written to `<name>.json`.
+    Json,
+}
+
+impl OutputFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Text => "txt",
+            OutputFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonOutput<'a> {
+    file: String,
+    language: &'a str,
+    model: Option<&'a str>,
+    text: String,
+}
+
+fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+    let args = Args::parse();
+
+    let mut app_config = match AppConfig::load() {
+        Ok(app_config) => app_config,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(tessdata) = &args.tessdata {
+        app_config.tesseract.data_path = tessdata.clone();
+    }
+
+    let available_languages = match get_available_languages_with_models(&app_config) {
+        Ok(available_languages) => available_languages,
+        Err(error) => {
+            eprintln!(
+                "Failed to read Tesseract languages from '{}': {error}",
+                app_config.tesseract.data_path
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    let params = ImagesQueryParams::new(args.language.clone(), args.model.clone());
+    let tesseract_model = match validate_language_params(
+        &params,
+        &available_languages,
+        &app_config.service.default_language,
+        &app_config.tesseract.model_preferences,
+    ) {
+        Ok(tesseract_model) => tesseract_model,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let files = match expand_inputs(&args.inputs) {
+        Ok(files) if !files.is_empty() => files,
+        Ok(_) => {
+            eprintln!("No image files found");
+            return ExitCode::FAILURE;
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    // Workers write concurrently, so two inputs must never share an output file.
+    if let Err(error) = check_output_paths(&files, args.output_dir.as_deref(), args.format) {
+        eprintln!("{error}");
+        return ExitCode::FAILURE;
+    }
+    if let Some(output_dir) = &args.output_dir {
+        if let Err(error) = fs::create_dir_all(output_dir) {
+            eprintln!(
+                "Failed to create output directory '{}': {error}",
+                output_dir.display()
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let workers = args
+        .jobs
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1)
+        .clamp(1, files.len());
+    let queue = Mutex::new(files.into_iter());
+    let failures = Mutex::new(0_usize);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    let Some(file) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    match process_file(
+                        &file,
+                        &args,
+                        &app_config.tesseract.data_path,
+                        &tesseract_model,
+                    ) {
+                        Ok(output) => println!("{} -> {}", file.display(), output.display()),
+                        Err(error) => {
+                            eprintln!("{}: {error}", file.display());
+                            *failures.lock().unwrap() += 1;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    if failures.into_inner().unwrap() > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Resolve files, directories and glob patterns into a sorted, de-duplicated list of files.
+fn expand_inputs(inputs: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            let entries =
+                fs::read_dir(path).map_err(|error| format!("Failed to read '{input}': {error}"))?;
+            files.extend(
+                entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file() && is_image_file(path)),
+            );
+        } else if path.is_file() {
+            files.push(path.to_path_buf());
+        } else {
+            let paths = glob::glob(input)
+                .map_err(|error| format!("Invalid glob pattern '{input}': {error}"))?;
+            files.extend(
+                paths
+                    .filter_map(Result::ok)
+                    .filter(|path| path.is_file() && is_image_file(path)),
+            );
+        }
+    }
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    files.sort();
+    Ok(files)
+}
+
+fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// The file the result for `file` is written to.
+fn output_path(file: &Path, output_dir: Option<&Path>, format: OutputFormat) -> PathBuf {
+    let output = file.with_extension(format.extension());
+    match (output_dir, output.file_name()) {
+        (Some(output_dir), Some(file_name)) => output_dir.join(file_name),
+        _ => output,
+    }
+}
+
+/// Check that no two files are written to the same output file, e.g. `a/scan.png` and
+/// `b/scan.png` with an output directory, or `scan.png` and `scan.jpg`.
+fn check_output_paths(
+    files: &[PathBuf],
+    output_dir: Option<&Path>,
+    format: OutputFormat,
+) -> Result<(), String> {
+    let mut outputs = HashMap::new();
+    for file in files {
+        let output = output_path(file, output_dir, format);
+        if let Some(other) = outputs.insert(output.clone(), file) {
+            return Err(format!(
+                "'{}' and '{}' would both be written to '{}'",
+                other.display(),
+                file.display(),
+                output.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn process_file(
+    file: &Path,
+    args: &Args,
+    data_path: &str,
+    tesseract_model: &TesseractModel,
+) -> anyhow::Result<PathBuf> {
+    let file_content = fs::read(file)?;
+    let img = decode_image(&file_content, &model_attributes(tesseract_model)).map_err(cli_error)?;
+    let text = recognize_image(data_path, tesseract_model, &img, None).map_err(cli_error)?;
+
+    let contents = match args.format {
+        OutputFormat::Text => text,
+        OutputFormat::Json => serde_json::to_string_pretty(&JsonOutput {
+            file: file.display().to_string(),
+            language: &tesseract_model.language,
+            model: tesseract_model.model.as_deref(),
+            text,
+        })?,
+    };
+
+    let output = output_path(file, args.output_dir.as_deref(), args.format);
+    fs::write(&output, contents)?;
+    Ok(output)
+}
+
+/// Unlike API consumers, CLI users should see the details of internal errors.
+fn cli_error(error: ErrorType) -> anyhow::Error {
+    match error {
+        ErrorType::InternalError(error) => error,
+        error => anyhow::anyhow!(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{OutputFormat, check_output_paths, output_path};
+
+    #[test]
+    fn test_output_path_next_to_input() {
+        let output = output_path(Path::new("scans/page-1.png"), None, OutputFormat::Text);
+        assert_eq!(output, PathBuf::from("scans/page-1.txt"));
+    }
+
+    #[test]
+    fn test_output_path_in_output_dir() {
+        let output = output_path(
+            Path::new("scans/page-1.png"),
+            Some(Path::new("results")),
+            OutputFormat::Json,
+        );
+        assert_eq!(output, PathBuf::from("results/page-1.json"));
+    }
+
+    #[test]
+    fn test_output_path_collisions() {
+        let files = [PathBuf::from("a/scan.png"), PathBuf::from("b/scan.png")];
+        assert!(check_output_paths(&files, None, OutputFormat::Text).is_ok());
+
+        let error =
+            check_output_paths(&files, Some(Path::new("results")), OutputFormat::Text).unwrap_err();
+        assert_eq!(
+            error,
+            "'a/scan.png' and 'b/scan.png' would both be written to 'results/scan.txt'"
+        );
+
+        let files = [PathBuf::from("scan.jpg"), PathBuf::from("scan.png")];
+        assert!(check_output_paths(&files, None, OutputFormat::Json).is_err());
+    }
+}
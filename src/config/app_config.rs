@@ -1,40 +1,96 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::OnceLock;
+use std::thread;
 use std::time::Duration;
 
-use super::error::ServerError;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+
+use super::error::{ConfigError, REDACTED, ServerError, ServerResult};
+use super::loader::ConfigLoader;
 
 const DEFAULT_SERVER_REQUEST_TIMEOUT: u64 = 15;
 const DEFAULT_SERVER_HOST: &str = "0.0.0.0";
 const DEFAULT_SERVER_PORT: u16 = 8080;
 const DEFAULT_SERVER_FILE_UPLOAD_MAX_SIZE: usize = 1024 * 1024 * 10;
 const DEFAULT_SERVER_FILE_UPLOAD_MAX_SIZE_ENABLED: bool = true;
+const DEFAULT_SERVER_MODEL_UPLOAD_MAX_SIZE: usize = 1024 * 1024 * 200;
 const DEFAULT_SERVER_ENVIRONMENT: &str = "development";
+const DEFAULT_SERVER_GRPC_ENABLED: bool = true;
+const DEFAULT_SERVER_GRPC_PORT: u16 = 50051;
 
 const DEFAULT_SERVICE_NAME: &str = "ocr-service";
 const DEFAULT_SERVICE_DEFAULT_LANGUAGE: &str = "eng";
 
 const DEFAULT_MAX_ACCESS_CONTROL_AGE: u64 = 600;
+const DEFAULT_SECURITY_EXPOSE_MODEL_PATHS: bool = false;
+const DEFAULT_SECURITY_JWT_JWKS_REFRESH_INTERVAL: u64 = 300;
+const DEFAULT_SECURITY_JWT_LEEWAY: u64 = 60;
+const DEFAULT_SECURITY_JWT_TENANT_CLAIM: &str = "tenant_id";
+const DEFAULT_SECURITY_JWT_SCOPES_CLAIM: &str = "scope";
+const DEFAULT_SECURITY_CORS_ALLOWED_ORIGINS: &str = "*";
+const DEFAULT_SECURITY_CORS_ALLOWED_METHODS: &str = "*";
+const DEFAULT_SECURITY_CORS_ALLOWED_HEADERS: &str = "*";
+const DEFAULT_SECURITY_CORS_EXPOSED_HEADERS: &str =
+    "x-request-id,retry-after,ratelimit-limit,ratelimit-remaining,ratelimit-reset";
+const DEFAULT_SECURITY_CORS_ALLOW_CREDENTIALS: bool = false;
+
+/// The `SERVER_ENVIRONMENT` with strict defaults, e.g. no cross-origin requests.
+const PRODUCTION_ENVIRONMENT: &str = "production";
+const PRODUCTION_SECURITY_CORS_ALLOWED_ORIGINS: &str = "";
+const PRODUCTION_SECURITY_CORS_ALLOWED_METHODS: &str = "GET,POST";
+const PRODUCTION_SECURITY_CORS_ALLOWED_HEADERS: &str =
+    "accept-language,authorization,content-type,x-api-key,x-request-id";
 
 const DEFAULT_TESSERACT_DATA_PATH: &str = "tesseract";
+const DEFAULT_TESSDATA_WATCH_ENABLED: bool = true;
+const DEFAULT_TESSDATA_WATCH_DEBOUNCE: u64 = 1000;
+
+const DEFAULT_JOBS_RESULT_RETENTION: u64 = 300;
+const DEFAULT_JOBS_MAX_QUEUED: usize = 100;
+
+const DEFAULT_HEALTH_MAX_QUEUE_DEPTH: usize = 100;
+const DEFAULT_HEALTH_SELF_TEST_ENABLED: bool = false;
+
+const DEFAULT_OTEL_TRACES_SAMPLER_ARG: f64 = 1.0;
+
+const DEFAULT_CACHE_ENABLED: bool = false;
+const DEFAULT_CACHE_MAX_ENTRIES: usize = 1000;
+const DEFAULT_CACHE_TTL: u64 = 3600;
+const DEFAULT_CACHE_DIRECTORY: &str = "cache";
+
+const DEFAULT_RATE_LIMIT_ENABLED: bool = false;
+const DEFAULT_RATE_LIMIT_REQUESTS_PER_MINUTE: u32 = 60;
+const DEFAULT_RATE_LIMIT_BURST: u32 = 10;
+const DEFAULT_RATE_LIMIT_MAX_CONCURRENT: usize = 2;
+const DEFAULT_RATE_LIMIT_TRUST_FORWARDED_FOR: bool = false;
 
 pub fn app_config() -> &'static AppConfig {
     static INSTANCE: OnceLock<AppConfig> = OnceLock::new();
 
     INSTANCE.get_or_init(|| {
-        AppConfig::load_from_env()
-            .unwrap_or_else(|ex| panic!("Unable to load application configuration: {ex:?}"))
+        AppConfig::load()
+            .unwrap_or_else(|ex| panic!("Unable to load application configuration: {ex}"))
     })
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AppConfig {
     pub server: ServerConfig,
     pub service: ServiceConfig,
     pub security: SecurityConfig,
     pub otel: OtelConfig,
     pub otel_provider: OtelProviderConfig,
+    pub log: LogConfig,
     pub tesseract: TesseractConfig,
+    pub jobs: JobsConfig,
+    pub cache: CacheConfig,
+    pub health: HealthConfig,
+    pub rate_limit: RateLimitConfig,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -43,13 +99,256 @@ pub struct ServerConfig {
     pub port: u16,
     pub file_upload_max_size: usize,
     pub file_upload_max_size_enabled: bool,
+    /// The maximum size of the Tesseract models uploaded to the admin API, which are larger
+    /// than images.
+    pub model_upload_max_size: usize,
     pub environment: String,
     pub timeout: Duration,
+    pub grpc_enabled: bool,
+    pub grpc_port: u16,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SecurityConfig {
     pub max_access_control_age: Duration,
+    /// The bearer token required by the admin API. The admin API is disabled when unset.
+    pub admin_token: Option<String>,
+    /// Whether the languages API lists the file paths of the models.
+    pub expose_model_paths: bool,
+    /// The API keys accepted by the API, from `SECURITY_API_KEYS` and `SECURITY_API_KEYS_FILE`.
+    /// The API does not require a key when there are none.
+    pub api_keys: Vec<ApiKey>,
+    /// The file the API keys are read from, in addition to `SECURITY_API_KEYS`.
+    pub api_keys_file: Option<String>,
+    pub jwt: JwtConfig,
+    pub cors: CorsConfig,
+}
+
+/// The cross-origin requests browsers are allowed to make. The defaults are strict when
+/// `SERVER_ENVIRONMENT` is `production`, and allow any origin otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// `*`, or origins like `https://app.example.com`, where `https://*.example.com` matches the
+    /// subdomains of `example.com`.
+    pub allowed_origins: Vec<String>,
+    /// `*`, or upper case method names.
+    pub allowed_methods: Vec<String>,
+    /// `*`, or lower case header names.
+    pub allowed_headers: Vec<String>,
+    /// `*`, or lower case header names the responses expose to scripts.
+    pub exposed_headers: Vec<String>,
+    /// Whether requests may include cookies and `Authorization` headers.
+    pub allow_credentials: bool,
+}
+
+/// Validation of JWT bearer tokens, enabled when a JWKS is configured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JwtConfig {
+    /// The path or http(s) URL of the JSON Web Key Set the tokens are signed with.
+    pub jwks: Option<String>,
+    /// How long the JWKS is cached before it is read again.
+    pub jwks_refresh_interval: Duration,
+    /// The required `iss` claim.
+    pub issuer: Option<String>,
+    /// The required `aud` claim.
+    pub audience: Option<String>,
+    /// The clock skew tolerated when checking `exp` and `nbf`.
+    pub leeway: Duration,
+    /// The claim with the tenant id, with `.` separating nested claims.
+    pub tenant_claim: String,
+    /// The claim with the scopes, a space-separated string or an array, with `.` separating
+    /// nested claims.
+    pub scopes_claim: String,
+}
+
+impl JwtConfig {
+    #[must_use]
+    pub fn enabled(&self) -> bool {
+        self.jwks.is_some()
+    }
+}
+
+/// An API key, stored as the SHA-256 checksum of the key rather than the key itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    /// The name of the key in logs, e.g. the client it was issued to.
+    pub name: String,
+    /// The SHA-256 checksum of the key, in lowercase hexadecimal.
+    pub sha256: String,
+    /// The routes the key gives access to.
+    pub scopes: BTreeSet<Scope>,
+}
+
+/// The permissions of an API key, each covering a group of routes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Scope {
+    /// Recognize images, directly or with jobs.
+    Ocr,
+    /// List the available languages and models.
+    LanguagesRead,
+    /// Use the admin API.
+    Admin,
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        match scope {
+            "ocr" => Ok(Scope::Ocr),
+            "languages:read" => Ok(Scope::LanguagesRead),
+            "admin" => Ok(Scope::Admin),
+            _ => Err(format!(
+                "Unknown scope '{scope}', expected ocr, languages:read or admin"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Scope::Ocr => "ocr",
+            Scope::LanguagesRead => "languages:read",
+            Scope::Admin => "admin",
+        })
+    }
+}
+
+/// Parse an API key in the `name:sha256:scope scope` format, e.g.
+/// `ci:9f86d08...:ocr languages:read`.
+///
+/// # Errors
+///
+/// Returns an error if the name is empty, the checksum is not a SHA-256 checksum, or a scope is
+/// unknown or missing.
+pub fn parse_api_key(api_key: &str) -> Result<ApiKey, String> {
+    let mut parts = api_key.trim().splitn(3, ':');
+    let (Some(name), Some(sha256), Some(scopes)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err("API key is not in the name:sha256:scopes format".to_owned());
+    };
+    let (name, sha256) = (name.trim(), sha256.trim());
+    if name.is_empty() {
+        return Err("API key has an empty name".to_owned());
+    }
+    if sha256.len() != 64 || !sha256.chars().all(|char| char.is_ascii_hexdigit()) {
+        return Err(format!(
+            "API key '{name}' must be the SHA-256 checksum of the key, not the key itself"
+        ));
+    }
+    let scopes = scopes
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<BTreeSet<Scope>, _>>()?;
+    if scopes.is_empty() {
+        return Err(format!("API key '{name}' has no scopes"));
+    }
+    Ok(ApiKey {
+        name: name.to_owned(),
+        sha256: sha256.to_lowercase(),
+        scopes,
+    })
+}
+
+/// Parse comma-separated API keys, in the format of `parse_api_key`.
+///
+/// # Errors
+///
+/// Returns an error if a key is invalid.
+pub fn parse_api_keys(api_keys: &str) -> Result<Vec<ApiKey>, String> {
+    api_keys
+        .split(',')
+        .filter(|api_key| !api_key.trim().is_empty())
+        .map(parse_api_key)
+        .collect()
+}
+
+/// Read a file of API keys, one per line in the format of `parse_api_key`. Empty lines and
+/// lines starting with `#` are ignored.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or a key is invalid.
+pub fn read_api_keys_file(path: &str) -> Result<Vec<ApiKey>, String> {
+    let api_keys = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    api_keys
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| {
+            parse_api_key(line).map_err(|error| format!("Line {}: {error}", index + 1))
+        })
+        .collect()
+}
+
+/// Parse comma-separated CORS origins: `*`, or a scheme and host with an optional port, e.g.
+/// `https://app.example.com,https://*.example.com`.
+///
+/// # Errors
+///
+/// Returns an error if an origin has no scheme, has a path, or has a `*` other than a leading
+/// `*.` subdomain.
+pub fn parse_cors_origins(origins: &str) -> Result<Vec<String>, String> {
+    comma_separated(origins)
+        .map(|origin| {
+            if origin == "*" {
+                return Ok(origin.to_owned());
+            }
+            let host = origin
+                .split_once("://")
+                .filter(|(scheme, _)| !scheme.is_empty())
+                .map(|(_, host)| host.strip_prefix("*.").unwrap_or(host));
+            match host {
+                Some(host) if !host.is_empty() && !host.contains(['/', '*']) => {
+                    Ok(origin.to_lowercase())
+                }
+                _ => Err(format!(
+                    "CORS origin '{origin}' must be * or a scheme and host, e.g. \
+                     https://app.example.com or https://*.example.com"
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Parse comma-separated HTTP methods, or `*`.
+///
+/// # Errors
+///
+/// Returns an error if a method is not a valid HTTP method.
+pub fn parse_cors_methods(methods: &str) -> Result<Vec<String>, String> {
+    comma_separated(methods)
+        .map(|method| match method {
+            "*" => Ok(method.to_owned()),
+            _ => http::Method::from_bytes(method.to_uppercase().as_bytes())
+                .map(|method| method.to_string())
+                .map_err(|_| format!("'{method}' is not an HTTP method")),
+        })
+        .collect()
+}
+
+/// Parse comma-separated HTTP header names, or `*`.
+///
+/// # Errors
+///
+/// Returns an error if a header name is invalid.
+pub fn parse_cors_headers(headers: &str) -> Result<Vec<String>, String> {
+    comma_separated(headers)
+        .map(|header| match header {
+            "*" => Ok(header.to_owned()),
+            _ => http::HeaderName::from_bytes(header.as_bytes())
+                .map(|header| header.to_string())
+                .map_err(|_| format!("'{header}' is not an HTTP header name")),
+        })
+        .collect()
+}
+
+fn comma_separated(values: &str) -> impl Iterator<Item = &str> {
+    values
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -60,13 +359,132 @@ pub struct ServiceConfig {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OtelProviderConfig {
-    pub provider: Option<String>,
+    pub provider: Option<OtelProvider>,
     pub organization: Option<String>,
     pub stream_name: Option<String>,
     pub auth_token: Option<String>,
+    /// Headers sent with every OTLP export, from `OTEL_EXPORTER_OTLP_HEADERS`.
+    pub headers: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl OtelProviderConfig {
+    /// The headers sent with every OTLP export.
+    ///
+    /// The headers of the provider preset come first, so `OTEL_EXPORTER_OTLP_HEADERS` can
+    /// override them.
+    #[must_use]
+    pub fn headers(&self) -> BTreeMap<String, String> {
+        let mut headers = BTreeMap::new();
+        let mut insert = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                headers.insert(name.to_owned(), value);
+            }
+        };
+        let auth_token = self.auth_token.clone();
+        match self.provider {
+            Some(OtelProvider::OpenObserve) => {
+                insert("authorization", auth_token);
+                insert("organization", self.organization.clone());
+                insert("stream-name", self.stream_name.clone());
+            }
+            Some(OtelProvider::Honeycomb) => {
+                insert("x-honeycomb-team", auth_token);
+                insert("x-honeycomb-dataset", self.stream_name.clone());
+            }
+            Some(OtelProvider::GrafanaCloud) => {
+                // The organization is the numeric instance id of the Grafana Cloud stack.
+                let credentials =
+                    self.organization
+                        .as_ref()
+                        .zip(auth_token)
+                        .map(|(instance_id, token)| {
+                            format!("Basic {}", BASE64.encode(format!("{instance_id}:{token}")))
+                        });
+                insert("authorization", credentials);
+            }
+            Some(OtelProvider::NewRelic) => insert("api-key", auth_token),
+            Some(OtelProvider::SigNoz) => insert("signoz-ingestion-key", auth_token),
+            None => {}
+        }
+        headers.extend(self.headers.clone());
+        headers
+    }
+}
+
+/// Backends with first-class support, whose headers are derived from the `OTEL_PROVIDER_*`
+/// variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtelProvider {
+    OpenObserve,
+    Honeycomb,
+    GrafanaCloud,
+    NewRelic,
+    SigNoz,
+}
+
+impl FromStr for OtelProvider {
+    type Err = String;
+
+    fn from_str(provider: &str) -> Result<Self, Self::Err> {
+        match provider.to_lowercase().as_str() {
+            "openobserve" => Ok(OtelProvider::OpenObserve),
+            "honeycomb" => Ok(OtelProvider::Honeycomb),
+            "grafana_cloud" => Ok(OtelProvider::GrafanaCloud),
+            "newrelic" => Ok(OtelProvider::NewRelic),
+            "signoz" => Ok(OtelProvider::SigNoz),
+            _ => Err(format!("Unknown OpenTelemetry provider: {provider}")),
+        }
+    }
+}
+
+impl fmt::Display for OtelProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OtelProvider::OpenObserve => "openobserve",
+            OtelProvider::Honeycomb => "honeycomb",
+            OtelProvider::GrafanaCloud => "grafana_cloud",
+            OtelProvider::NewRelic => "newrelic",
+            OtelProvider::SigNoz => "signoz",
+        })
+    }
+}
+
+/// Parse headers in the format of `OTEL_EXPORTER_OTLP_HEADERS`: comma separated `key=value`
+/// pairs with percent-encoded values, e.g. `api-key=secret,x-tenant=a%20b`.
+///
+/// Header names are lowercased and empty entries are ignored.
+///
+/// # Errors
+///
+/// Returns an error if an entry has no `=`, an empty name or a value that is not valid UTF-8
+/// once decoded.
+pub fn parse_otlp_headers(headers: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut parsed = BTreeMap::new();
+    // Entries are named by their position, as they may hold a secret, e.g. a mistyped
+    // `Authorization: Bearer <token>`.
+    for (position, entry) in headers
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .enumerate()
+        .map(|(index, entry)| (index + 1, entry))
+    {
+        let (name, value) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("Header {position} is not in the key=value format"))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("Header {position} has an empty name"));
+        }
+        let value = percent_encoding::percent_decode_str(value.trim())
+            .decode_utf8()
+            .map_err(|error| format!("Header '{name}' has an invalid value: {error}"))?;
+        parsed.insert(name.to_lowercase(), value.into_owned());
+    }
+    Ok(parsed)
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct OtelConfig {
     pub enabled: bool,
     pub service_name: Option<String>,
@@ -74,75 +492,1370 @@ pub struct OtelConfig {
     pub logs_endpoint: Option<String>,
     pub metrics_endpoint: Option<String>,
     pub metric_export_interval: Option<Duration>,
+    pub metrics_exporter: MetricsExporter,
+    /// The transport of the OTLP exporters.
+    pub protocol: OtlpProtocol,
+    pub traces_sampler: TracesSampler,
+    /// The sampling probability of the ratio based samplers, between 0.0 and 1.0.
+    pub traces_sampler_ratio: f64,
+}
+
+impl OtelConfig {
+    /// Check that every OTLP endpoint needed by the enabled exporters is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigInvalid` with the variables of all the missing endpoints.
+    pub fn validate(&self) -> ServerResult<()> {
+        let missing = self.missing_endpoints();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ServerError::ConfigInvalid(missing))
+        }
+    }
+
+    fn missing_endpoints(&self) -> Vec<ConfigError> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let mut missing = Vec::new();
+        if self.traces_endpoint.is_none() {
+            missing.push("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT");
+        }
+        if self.logs_endpoint.is_none() {
+            missing.push("OTEL_EXPORTER_OTLP_LOGS_ENDPOINT");
+        }
+        if self.metrics_exporter == MetricsExporter::Otlp && self.metrics_endpoint.is_none() {
+            missing.push("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT");
+        }
+        missing
+            .into_iter()
+            .map(|key| ConfigError::new(key, "", "Must be set when OTEL_ENABLED is true"))
+            .collect()
+    }
+}
+
+/// The transport used to send telemetry to an OTLP collector.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OtlpProtocol {
+    /// OTLP over gRPC (tonic).
+    #[default]
+    Grpc,
+    /// OTLP over HTTP with protobuf payloads.
+    HttpProtobuf,
+}
+
+impl FromStr for OtlpProtocol {
+    type Err = String;
+
+    fn from_str(protocol: &str) -> Result<Self, Self::Err> {
+        match protocol.to_lowercase().as_str() {
+            "grpc" => Ok(OtlpProtocol::Grpc),
+            "http/protobuf" => Ok(OtlpProtocol::HttpProtobuf),
+            _ => Err(format!("Unknown OTLP protocol: {protocol}")),
+        }
+    }
+}
+
+impl fmt::Display for OtlpProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OtlpProtocol::Grpc => "grpc",
+            OtlpProtocol::HttpProtobuf => "http/protobuf",
+        })
+    }
+}
+
+/// Which traces are sampled, named after the values of the standard `OTEL_TRACES_SAMPLER`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TracesSampler {
+    AlwaysOn,
+    AlwaysOff,
+    /// Sample by ratio, ignoring the sampling decision of the parent span.
+    #[default]
+    TraceIdRatio,
+    ParentBasedAlwaysOn,
+    ParentBasedAlwaysOff,
+    /// Follow the sampling decision of the parent span, otherwise sample by ratio.
+    ParentBasedTraceIdRatio,
+}
+
+impl FromStr for TracesSampler {
+    type Err = String;
+
+    fn from_str(sampler: &str) -> Result<Self, Self::Err> {
+        match sampler.to_lowercase().as_str() {
+            "always_on" => Ok(TracesSampler::AlwaysOn),
+            "always_off" => Ok(TracesSampler::AlwaysOff),
+            "traceidratio" => Ok(TracesSampler::TraceIdRatio),
+            "parentbased_always_on" => Ok(TracesSampler::ParentBasedAlwaysOn),
+            "parentbased_always_off" => Ok(TracesSampler::ParentBasedAlwaysOff),
+            "parentbased_traceidratio" => Ok(TracesSampler::ParentBasedTraceIdRatio),
+            _ => Err(format!("Unknown traces sampler: {sampler}")),
+        }
+    }
+}
+
+impl fmt::Display for TracesSampler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TracesSampler::AlwaysOn => "always_on",
+            TracesSampler::AlwaysOff => "always_off",
+            TracesSampler::TraceIdRatio => "traceidratio",
+            TracesSampler::ParentBasedAlwaysOn => "parentbased_always_on",
+            TracesSampler::ParentBasedAlwaysOff => "parentbased_always_off",
+            TracesSampler::ParentBasedTraceIdRatio => "parentbased_traceidratio",
+        })
+    }
+}
+
+/// How metrics leave the service.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetricsExporter {
+    /// Push metrics to an OTLP collector (gRPC).
+    #[default]
+    Otlp,
+    /// Serve metrics for Prometheus to scrape on `/system/metrics`.
+    Prometheus,
+}
+
+impl FromStr for MetricsExporter {
+    type Err = String;
+
+    fn from_str(exporter: &str) -> Result<Self, Self::Err> {
+        match exporter.to_lowercase().as_str() {
+            "otlp" => Ok(MetricsExporter::Otlp),
+            "prometheus" => Ok(MetricsExporter::Prometheus),
+            _ => Err(format!("Unknown metrics exporter: {exporter}")),
+        }
+    }
+}
+
+impl fmt::Display for MetricsExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MetricsExporter::Otlp => "otlp",
+            MetricsExporter::Prometheus => "prometheus",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogConfig {
+    pub format: LogFormat,
+}
+
+/// How log records are written to stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Multi-line, human readable records with ANSI colors.
+    #[default]
+    Pretty,
+    /// Single-line, human readable records with ANSI colors.
+    Compact,
+    /// One JSON object per record, for log shippers.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "compact" => Ok(LogFormat::Compact),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("Unknown log format: {format}")),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogFormat::Pretty => "pretty",
+            LogFormat::Compact => "compact",
+            LogFormat::Json => "json",
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TesseractConfig {
     pub data_path: String,
+    /// Whether the data path is watched to reload the available languages when models change.
+    pub watch_enabled: bool,
+    /// How long the data path must be quiet before the languages are reloaded.
+    pub watch_debounce: Duration,
+    pub model_preferences: ModelPreferences,
+    /// The sources the admin API may install models from. Other sources can only be installed
+    /// from with the `install-models` command.
+    pub install_sources: Vec<String>,
+}
+
+/// How the model of a language is chosen when it has several models and none is requested.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelPreferences {
+    /// The model used for each language, e.g. `best` for `eng`.
+    pub default_models: BTreeMap<String, String>,
+    /// The models tried in order for every language, e.g. `best`, `default`, `fast`.
+    ///
+    /// A name matches the model of that name or a model ending with `_<name>` (e.g. `eng_best`
+    /// for `best`), and `default` also matches the model without a name (`<language>.traineddata`).
+    pub order: Vec<String>,
+}
+
+/// Parse the default models of `TESSDATA_DEFAULT_MODELS`, as comma separated `language=model`
+/// entries.
+///
+/// # Errors
+///
+/// Returns an error if an entry has no `=`, or an empty language or model.
+pub fn parse_default_models(default_models: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut parsed = BTreeMap::new();
+    for entry in default_models
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let (language, model) = entry.split_once('=').ok_or_else(|| {
+            format!("Default model '{entry}' is not in the language=model format")
+        })?;
+        let (language, model) = (language.trim(), model.trim());
+        if language.is_empty() || model.is_empty() {
+            return Err(format!(
+                "Default model '{entry}' has an empty language or model"
+            ));
+        }
+        parsed.insert(language.to_owned(), model.to_owned());
+    }
+    Ok(parsed)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobsConfig {
+    pub result_retention: Duration,
+    /// New jobs are rejected while this many jobs are queued or running.
+    pub max_queued: usize,
+    /// The number of pages recognized at the same time, across all jobs.
+    pub workers: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthConfig {
+    /// The service is not ready while this many OCR jobs or more are queued or running.
+    pub max_queue_depth: usize,
+    /// Whether readiness also recognizes a built-in test image.
+    pub self_test_enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub backend: CacheBackend,
+    pub max_entries: usize,
+    pub ttl: Duration,
+    /// The directory results are stored in when using the disk backend.
+    pub directory: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheBackend {
+    #[default]
+    Memory,
+    Disk,
+}
+
+impl FromStr for CacheBackend {
+    type Err = String;
+
+    fn from_str(backend: &str) -> Result<Self, Self::Err> {
+        match backend.to_lowercase().as_str() {
+            "memory" => Ok(CacheBackend::Memory),
+            "disk" => Ok(CacheBackend::Disk),
+            _ => Err(format!("Unknown cache backend: {backend}")),
+        }
+    }
+}
+
+impl fmt::Display for CacheBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CacheBackend::Memory => "memory",
+            CacheBackend::Disk => "disk",
+        })
+    }
+}
+
+/// Quotas of the recognitions of each client, so one client cannot saturate the OCR engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// The rate the token bucket of a client is refilled at.
+    pub requests_per_minute: u32,
+    /// The size of the token bucket of a client, i.e. the requests it can send at once.
+    pub burst: u32,
+    /// The recognitions (requests, jobs or streams) a client can run at the same time.
+    pub max_concurrent: usize,
+    pub key: RateLimitKey,
+    /// Whether the client IP is read from the `X-Forwarded-For` header of a reverse proxy.
+    pub trust_forwarded_for: bool,
+}
+
+/// How the clients of the quotas are told apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// By the tenant of the JWT, or else by the API key or the subject of the JWT, or else by
+    /// the client IP.
+    #[default]
+    Client,
+    /// By the client IP only.
+    Ip,
+}
+
+impl FromStr for RateLimitKey {
+    type Err = String;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        match key.to_lowercase().as_str() {
+            "client" => Ok(RateLimitKey::Client),
+            "ip" => Ok(RateLimitKey::Ip),
+            _ => Err(format!("Unknown rate limit key: {key}")),
+        }
+    }
+}
+
+impl fmt::Display for RateLimitKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RateLimitKey::Client => "client",
+            RateLimitKey::Ip => "ip",
+        })
+    }
 }
 
 impl AppConfig {
-    fn load_from_env() -> Result<AppConfig, ServerError> {
-        Ok(AppConfig {
+    /// Load the configuration from the environment, layered over the configuration file set by
+    /// `CONFIG_FILE` if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigFile` if the configuration file cannot be read, or `ConfigInvalid` with
+    /// every invalid value.
+    pub fn load() -> ServerResult<AppConfig> {
+        let config_file = env::var("CONFIG_FILE")
+            .ok()
+            .filter(|config_file| !config_file.is_empty());
+        Self::load_with(config_file.as_deref().map(Path::new), BTreeMap::new())
+    }
+
+    /// Load the configuration from the environment, layered over a configuration file.
+    /// `overrides` (e.g. command-line arguments) take precedence over both, keyed by the names
+    /// of the environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigFile` if the configuration file cannot be read, or `ConfigInvalid` with
+    /// every invalid value.
+    pub fn load_with(
+        config_file: Option<&Path>,
+        overrides: BTreeMap<String, String>,
+    ) -> ServerResult<AppConfig> {
+        let mut loader = ConfigLoader::new(config_file, overrides)?;
+        // Read by `load`, so it is a known key even when set in the configuration file.
+        loader.value("CONFIG_FILE");
+        let app_config = Self::from_loader(&mut loader);
+        app_config.validate(&mut loader);
+        loader.finish()?;
+        Ok(app_config)
+    }
+
+    /// The effective value of every configuration key, in the format of the environment
+    /// variables. Unset optional values are empty and the values of `SECRET_KEYS` are redacted.
+    #[must_use]
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        let secret = |value: &Option<String>| {
+            value
+                .as_ref()
+                .map(|_| REDACTED.to_owned())
+                .unwrap_or_default()
+        };
+        let default_models = self
+            .tesseract
+            .model_preferences
+            .default_models
+            .iter()
+            .map(|(language, model)| format!("{language}={model}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        // The keys of the file are listed too, as this is the effective configuration.
+        let api_keys = self
+            .security
+            .api_keys
+            .iter()
+            .map(|api_key| {
+                let scopes = api_key.scopes.iter().map(Scope::to_string);
+                format!(
+                    "{}:{REDACTED}:{}",
+                    api_key.name,
+                    scopes.collect::<Vec<_>>().join(" ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let headers = self
+            .otel_provider
+            .headers
+            .keys()
+            .map(|name| format!("{name}={REDACTED}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        vec![
+            ("SERVER_HOST", self.server.host.clone()),
+            ("SERVER_PORT", self.server.port.to_string()),
+            (
+                "SERVER_FILE_UPLOAD_MAX_SIZE",
+                self.server.file_upload_max_size.to_string(),
+            ),
+            (
+                "SERVER_FILE_UPLOAD_MAX_SIZE_ENABLED",
+                self.server.file_upload_max_size_enabled.to_string(),
+            ),
+            (
+                "SERVER_MODEL_UPLOAD_MAX_SIZE",
+                self.server.model_upload_max_size.to_string(),
+            ),
+            ("SERVER_ENVIRONMENT", self.server.environment.clone()),
+            (
+                "SERVER_REQUEST_TIMEOUT",
+                self.server.timeout.as_secs().to_string(),
+            ),
+            ("SERVER_GRPC_ENABLED", self.server.grpc_enabled.to_string()),
+            ("SERVER_GRPC_PORT", self.server.grpc_port.to_string()),
+            ("SERVICE_NAME", self.service.name.clone()),
+            (
+                "SERVICE_DEFAULT_LANGUAGE",
+                self.service.default_language.clone(),
+            ),
+            (
+                "SECURITY_MAX_ACCESS_CONTROL_AGE",
+                self.security.max_access_control_age.as_secs().to_string(),
+            ),
+            ("SECURITY_ADMIN_TOKEN", secret(&self.security.admin_token)),
+            (
+                "SECURITY_EXPOSE_MODEL_PATHS",
+                self.security.expose_model_paths.to_string(),
+            ),
+            ("SECURITY_API_KEYS", api_keys),
+            (
+                "SECURITY_API_KEYS_FILE",
+                optional(&self.security.api_keys_file),
+            ),
+            ("SECURITY_JWT_JWKS", optional(&self.security.jwt.jwks)),
+            (
+                "SECURITY_JWT_JWKS_REFRESH_INTERVAL",
+                self.security
+                    .jwt
+                    .jwks_refresh_interval
+                    .as_secs()
+                    .to_string(),
+            ),
+            ("SECURITY_JWT_ISSUER", optional(&self.security.jwt.issuer)),
+            (
+                "SECURITY_JWT_AUDIENCE",
+                optional(&self.security.jwt.audience),
+            ),
+            (
+                "SECURITY_JWT_LEEWAY",
+                self.security.jwt.leeway.as_secs().to_string(),
+            ),
+            (
+                "SECURITY_JWT_TENANT_CLAIM",
+                self.security.jwt.tenant_claim.clone(),
+            ),
+            (
+                "SECURITY_JWT_SCOPES_CLAIM",
+                self.security.jwt.scopes_claim.clone(),
+            ),
+            (
+                "SECURITY_CORS_ALLOWED_ORIGINS",
+                self.security.cors.allowed_origins.join(","),
+            ),
+            (
+                "SECURITY_CORS_ALLOWED_METHODS",
+                self.security.cors.allowed_methods.join(","),
+            ),
+            (
+                "SECURITY_CORS_ALLOWED_HEADERS",
+                self.security.cors.allowed_headers.join(","),
+            ),
+            (
+                "SECURITY_CORS_EXPOSED_HEADERS",
+                self.security.cors.exposed_headers.join(","),
+            ),
+            (
+                "SECURITY_CORS_ALLOW_CREDENTIALS",
+                self.security.cors.allow_credentials.to_string(),
+            ),
+            ("OTEL_ENABLED", self.otel.enabled.to_string()),
+            ("OTEL_SERVICE_NAME", optional(&self.otel.service_name)),
+            (
+                "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+                optional(&self.otel.traces_endpoint),
+            ),
+            (
+                "OTEL_EXPORTER_OTLP_LOGS_ENDPOINT",
+                optional(&self.otel.logs_endpoint),
+            ),
+            (
+                "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT",
+                optional(&self.otel.metrics_endpoint),
+            ),
+            (
+                "OTEL_METRIC_EXPORT_INTERVAL",
+                self.otel
+                    .metric_export_interval
+                    .map(|interval| interval.as_millis().to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "OTEL_METRICS_EXPORTER",
+                self.otel.metrics_exporter.to_string(),
+            ),
+            (
+                "OTEL_EXPORTER_OTLP_PROTOCOL",
+                self.otel.protocol.to_string(),
+            ),
+            ("OTEL_TRACES_SAMPLER", self.otel.traces_sampler.to_string()),
+            (
+                "OTEL_TRACES_SAMPLER_ARG",
+                self.otel.traces_sampler_ratio.to_string(),
+            ),
+            (
+                "OTEL_PROVIDER",
+                self.otel_provider
+                    .provider
+                    .map(|provider| provider.to_string())
+                    .unwrap_or_default(),
+            ),
+            (
+                "OTEL_PROVIDER_ORGANIZATION",
+                optional(&self.otel_provider.organization),
+            ),
+            (
+                "OTEL_PROVIDER_STREAM_NAME",
+                optional(&self.otel_provider.stream_name),
+            ),
+            (
+                "OTEL_PROVIDER_AUTH_TOKEN",
+                secret(&self.otel_provider.auth_token),
+            ),
+            ("OTEL_EXPORTER_OTLP_HEADERS", headers),
+            ("LOG_FORMAT", self.log.format.to_string()),
+            ("TESSDATA_PATH", self.tesseract.data_path.clone()),
+            (
+                "TESSDATA_WATCH_ENABLED",
+                self.tesseract.watch_enabled.to_string(),
+            ),
+            (
+                "TESSDATA_WATCH_DEBOUNCE",
+                self.tesseract.watch_debounce.as_millis().to_string(),
+            ),
+            ("TESSDATA_DEFAULT_MODELS", default_models),
+            (
+                "TESSDATA_MODEL_PREFERENCE",
+                self.tesseract.model_preferences.order.join(","),
+            ),
+            (
+                "TESSDATA_INSTALL_SOURCES",
+                self.tesseract.install_sources.join(","),
+            ),
+            (
+                "JOBS_RESULT_RETENTION",
+                self.jobs.result_retention.as_secs().to_string(),
+            ),
+            ("JOBS_MAX_QUEUED", self.jobs.max_queued.to_string()),
+            ("JOBS_WORKERS", self.jobs.workers.to_string()),
+            ("CACHE_ENABLED", self.cache.enabled.to_string()),
+            ("CACHE_BACKEND", self.cache.backend.to_string()),
+            ("CACHE_MAX_ENTRIES", self.cache.max_entries.to_string()),
+            ("CACHE_TTL", self.cache.ttl.as_secs().to_string()),
+            ("CACHE_DIRECTORY", self.cache.directory.clone()),
+            (
+                "HEALTH_MAX_QUEUE_DEPTH",
+                self.health.max_queue_depth.to_string(),
+            ),
+            (
+                "HEALTH_SELF_TEST_ENABLED",
+                self.health.self_test_enabled.to_string(),
+            ),
+            ("RATE_LIMIT_ENABLED", self.rate_limit.enabled.to_string()),
+            (
+                "RATE_LIMIT_REQUESTS_PER_MINUTE",
+                self.rate_limit.requests_per_minute.to_string(),
+            ),
+            ("RATE_LIMIT_BURST", self.rate_limit.burst.to_string()),
+            (
+                "RATE_LIMIT_MAX_CONCURRENT",
+                self.rate_limit.max_concurrent.to_string(),
+            ),
+            ("RATE_LIMIT_KEY", self.rate_limit.key.to_string()),
+            (
+                "RATE_LIMIT_TRUST_FORWARDED_FOR",
+                self.rate_limit.trust_forwarded_for.to_string(),
+            ),
+        ]
+    }
+
+    fn from_loader(loader: &mut ConfigLoader) -> AppConfig {
+        let environment = loader.string("SERVER_ENVIRONMENT", DEFAULT_SERVER_ENVIRONMENT);
+        let production = environment.eq_ignore_ascii_case(PRODUCTION_ENVIRONMENT);
+        let cors_default = |default: &str, production_default: &str| -> Vec<String> {
+            comma_separated(if production {
+                production_default
+            } else {
+                default
+            })
+            .map(str::to_owned)
+            .collect()
+        };
+        AppConfig {
             server: ServerConfig {
-                host: env::var("SERVER_HOST").unwrap_or(DEFAULT_SERVER_HOST.to_string()),
-                port: env::var("SERVER_PORT")
-                    .unwrap_or(DEFAULT_SERVER_PORT.to_string())
-                    .parse::<u16>()
-                    .unwrap_or(DEFAULT_SERVER_PORT),
-                file_upload_max_size: env::var("SERVER_FILE_UPLOAD_MAX_SIZE")
-                    .unwrap_or(DEFAULT_SERVER_FILE_UPLOAD_MAX_SIZE.to_string())
-                    .parse::<usize>()
-                    .unwrap_or(DEFAULT_SERVER_FILE_UPLOAD_MAX_SIZE),
-                file_upload_max_size_enabled: env::var("SERVER_FILE_UPLOAD_MAX_SIZE_ENABLED")
-                    .unwrap_or(DEFAULT_SERVER_FILE_UPLOAD_MAX_SIZE_ENABLED.to_string())
-                    .parse::<bool>()
-                    .unwrap_or(DEFAULT_SERVER_FILE_UPLOAD_MAX_SIZE_ENABLED),
-                environment: env::var("SERVER_ENVIRONMENT")
-                    .unwrap_or(DEFAULT_SERVER_ENVIRONMENT.to_string()),
+                host: loader.string("SERVER_HOST", DEFAULT_SERVER_HOST),
+                port: loader.parse("SERVER_PORT", DEFAULT_SERVER_PORT),
+                file_upload_max_size: loader.parse(
+                    "SERVER_FILE_UPLOAD_MAX_SIZE",
+                    DEFAULT_SERVER_FILE_UPLOAD_MAX_SIZE,
+                ),
+                file_upload_max_size_enabled: loader.parse(
+                    "SERVER_FILE_UPLOAD_MAX_SIZE_ENABLED",
+                    DEFAULT_SERVER_FILE_UPLOAD_MAX_SIZE_ENABLED,
+                ),
+                model_upload_max_size: loader.parse(
+                    "SERVER_MODEL_UPLOAD_MAX_SIZE",
+                    DEFAULT_SERVER_MODEL_UPLOAD_MAX_SIZE,
+                ),
+                environment,
                 timeout: Duration::from_secs(
-                    env::var("SERVER_REQUEST_TIMEOUT")
-                        .unwrap_or(DEFAULT_SERVER_REQUEST_TIMEOUT.to_string())
-                        .parse::<u64>()
-                        .unwrap_or(DEFAULT_SERVER_REQUEST_TIMEOUT),
+                    loader.parse("SERVER_REQUEST_TIMEOUT", DEFAULT_SERVER_REQUEST_TIMEOUT),
                 ),
+                grpc_enabled: loader.parse("SERVER_GRPC_ENABLED", DEFAULT_SERVER_GRPC_ENABLED),
+                grpc_port: loader.parse("SERVER_GRPC_PORT", DEFAULT_SERVER_GRPC_PORT),
             },
             service: ServiceConfig {
-                name: env::var("SERVICE_NAME").unwrap_or(DEFAULT_SERVICE_NAME.to_string()),
-                default_language: env::var("SERVICE_DEFAULT_LANGUAGE")
-                    .unwrap_or(DEFAULT_SERVICE_DEFAULT_LANGUAGE.to_string()),
+                name: loader.string("SERVICE_NAME", DEFAULT_SERVICE_NAME),
+                default_language: loader
+                    .string("SERVICE_DEFAULT_LANGUAGE", DEFAULT_SERVICE_DEFAULT_LANGUAGE),
             },
             security: SecurityConfig {
-                max_access_control_age: Duration::from_secs(
-                    env::var("SECURITY_MAX_ACCESS_CONTROL_AGE")
-                        .unwrap_or(DEFAULT_MAX_ACCESS_CONTROL_AGE.to_string())
-                        .parse::<u64>()
-                        .unwrap_or(DEFAULT_MAX_ACCESS_CONTROL_AGE),
+                max_access_control_age: Duration::from_secs(loader.parse(
+                    "SECURITY_MAX_ACCESS_CONTROL_AGE",
+                    DEFAULT_MAX_ACCESS_CONTROL_AGE,
+                )),
+                admin_token: loader.value("SECURITY_ADMIN_TOKEN"),
+                expose_model_paths: loader.parse(
+                    "SECURITY_EXPOSE_MODEL_PATHS",
+                    DEFAULT_SECURITY_EXPOSE_MODEL_PATHS,
                 ),
+                api_keys: loader
+                    .parse_with("SECURITY_API_KEYS", parse_api_keys)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .chain(
+                        loader
+                            .parse_with("SECURITY_API_KEYS_FILE", read_api_keys_file)
+                            .unwrap_or_default(),
+                    )
+                    .collect(),
+                api_keys_file: loader.value("SECURITY_API_KEYS_FILE"),
+                jwt: JwtConfig {
+                    jwks: loader.value("SECURITY_JWT_JWKS"),
+                    jwks_refresh_interval: Duration::from_secs(loader.parse(
+                        "SECURITY_JWT_JWKS_REFRESH_INTERVAL",
+                        DEFAULT_SECURITY_JWT_JWKS_REFRESH_INTERVAL,
+                    )),
+                    issuer: loader.value("SECURITY_JWT_ISSUER"),
+                    audience: loader.value("SECURITY_JWT_AUDIENCE"),
+                    leeway: Duration::from_secs(
+                        loader.parse("SECURITY_JWT_LEEWAY", DEFAULT_SECURITY_JWT_LEEWAY),
+                    ),
+                    tenant_claim: loader.string(
+                        "SECURITY_JWT_TENANT_CLAIM",
+                        DEFAULT_SECURITY_JWT_TENANT_CLAIM,
+                    ),
+                    scopes_claim: loader.string(
+                        "SECURITY_JWT_SCOPES_CLAIM",
+                        DEFAULT_SECURITY_JWT_SCOPES_CLAIM,
+                    ),
+                },
+                cors: CorsConfig {
+                    allowed_origins: loader
+                        .parse_with("SECURITY_CORS_ALLOWED_ORIGINS", parse_cors_origins)
+                        .unwrap_or_else(|| {
+                            cors_default(
+                                DEFAULT_SECURITY_CORS_ALLOWED_ORIGINS,
+                                PRODUCTION_SECURITY_CORS_ALLOWED_ORIGINS,
+                            )
+                        }),
+                    allowed_methods: loader
+                        .parse_with("SECURITY_CORS_ALLOWED_METHODS", parse_cors_methods)
+                        .unwrap_or_else(|| {
+                            cors_default(
+                                DEFAULT_SECURITY_CORS_ALLOWED_METHODS,
+                                PRODUCTION_SECURITY_CORS_ALLOWED_METHODS,
+                            )
+                        }),
+                    allowed_headers: loader
+                        .parse_with("SECURITY_CORS_ALLOWED_HEADERS", parse_cors_headers)
+                        .unwrap_or_else(|| {
+                            cors_default(
+                                DEFAULT_SECURITY_CORS_ALLOWED_HEADERS,
+                                PRODUCTION_SECURITY_CORS_ALLOWED_HEADERS,
+                            )
+                        }),
+                    exposed_headers: loader
+                        .parse_with("SECURITY_CORS_EXPOSED_HEADERS", parse_cors_headers)
+                        .unwrap_or_else(|| {
+                            cors_default(
+                                DEFAULT_SECURITY_CORS_EXPOSED_HEADERS,
+                                DEFAULT_SECURITY_CORS_EXPOSED_HEADERS,
+                            )
+                        }),
+                    allow_credentials: loader.parse(
+                        "SECURITY_CORS_ALLOW_CREDENTIALS",
+                        DEFAULT_SECURITY_CORS_ALLOW_CREDENTIALS,
+                    ),
+                },
             },
             otel: OtelConfig {
-                enabled: env::var("OTEL_ENABLED")
-                    .unwrap_or("false".to_string())
-                    .parse::<bool>()
-                    .unwrap_or(false),
-                service_name: env::var("OTEL_SERVICE_NAME").ok(),
-                traces_endpoint: env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").ok(),
-                logs_endpoint: env::var("OTEL_EXPORTER_OTLP_LOGS_ENDPOINT").ok(),
-                metrics_endpoint: env::var("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT").ok(),
-                metric_export_interval: env::var("OTEL_METRIC_EXPORT_INTERVAL")
-                    .ok()
-                    .map(|interval| Duration::from_millis(interval.parse::<u64>().unwrap())),
+                enabled: loader.parse("OTEL_ENABLED", false),
+                service_name: loader.value("OTEL_SERVICE_NAME"),
+                traces_endpoint: loader.value("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"),
+                logs_endpoint: loader.value("OTEL_EXPORTER_OTLP_LOGS_ENDPOINT"),
+                metrics_endpoint: loader.value("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT"),
+                metric_export_interval: loader
+                    .parse_optional("OTEL_METRIC_EXPORT_INTERVAL")
+                    .map(Duration::from_millis),
+                metrics_exporter: loader.parse("OTEL_METRICS_EXPORTER", MetricsExporter::default()),
+                protocol: loader.parse("OTEL_EXPORTER_OTLP_PROTOCOL", OtlpProtocol::default()),
+                traces_sampler: loader.parse("OTEL_TRACES_SAMPLER", TracesSampler::default()),
+                traces_sampler_ratio: loader
+                    .parse_with("OTEL_TRACES_SAMPLER_ARG", parse_sampler_ratio)
+                    .unwrap_or(DEFAULT_OTEL_TRACES_SAMPLER_ARG),
             },
             otel_provider: OtelProviderConfig {
-                provider: env::var("OTEL_PROVIDER").ok(),
-                organization: env::var("OTEL_PROVIDER_ORGANIZATION").ok(),
-                stream_name: env::var("OTEL_PROVIDER_STREAM_NAME").ok(),
-                auth_token: env::var("OTEL_PROVIDER_AUTH_TOKEN").ok(),
+                provider: loader.parse_optional("OTEL_PROVIDER"),
+                organization: loader.value("OTEL_PROVIDER_ORGANIZATION"),
+                stream_name: loader.value("OTEL_PROVIDER_STREAM_NAME"),
+                auth_token: loader.value("OTEL_PROVIDER_AUTH_TOKEN"),
+                headers: loader
+                    .parse_with("OTEL_EXPORTER_OTLP_HEADERS", parse_otlp_headers)
+                    .unwrap_or_default(),
+            },
+            log: LogConfig {
+                format: loader.parse("LOG_FORMAT", LogFormat::default()),
             },
             tesseract: TesseractConfig {
-                data_path: env::var("TESSDATA_PATH")
-                    .unwrap_or(DEFAULT_TESSERACT_DATA_PATH.to_string()),
+                data_path: loader.string("TESSDATA_PATH", DEFAULT_TESSERACT_DATA_PATH),
+                watch_enabled: loader
+                    .parse("TESSDATA_WATCH_ENABLED", DEFAULT_TESSDATA_WATCH_ENABLED),
+                watch_debounce: Duration::from_millis(
+                    loader.parse("TESSDATA_WATCH_DEBOUNCE", DEFAULT_TESSDATA_WATCH_DEBOUNCE),
+                ),
+                model_preferences: ModelPreferences {
+                    default_models: loader
+                        .parse_with("TESSDATA_DEFAULT_MODELS", parse_default_models)
+                        .unwrap_or_default(),
+                    order: loader
+                        .value("TESSDATA_MODEL_PREFERENCE")
+                        .map(|order| {
+                            order
+                                .split(',')
+                                .map(str::trim)
+                                .filter(|name| !name.is_empty())
+                                .map(str::to_owned)
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                },
+                install_sources: loader
+                    .value("TESSDATA_INSTALL_SOURCES")
+                    .map(|sources| {
+                        sources
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|source| !source.is_empty())
+                            .map(str::to_owned)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
             },
-        })
+            jobs: JobsConfig {
+                result_retention: Duration::from_secs(
+                    loader.parse("JOBS_RESULT_RETENTION", DEFAULT_JOBS_RESULT_RETENTION),
+                ),
+                max_queued: loader.parse("JOBS_MAX_QUEUED", DEFAULT_JOBS_MAX_QUEUED),
+                workers: loader
+                    .parse_optional("JOBS_WORKERS")
+                    .unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from)),
+            },
+            cache: CacheConfig {
+                enabled: loader.parse("CACHE_ENABLED", DEFAULT_CACHE_ENABLED),
+                backend: loader.parse("CACHE_BACKEND", CacheBackend::default()),
+                max_entries: loader.parse("CACHE_MAX_ENTRIES", DEFAULT_CACHE_MAX_ENTRIES),
+                ttl: Duration::from_secs(loader.parse("CACHE_TTL", DEFAULT_CACHE_TTL)),
+                directory: loader.string("CACHE_DIRECTORY", DEFAULT_CACHE_DIRECTORY),
+            },
+            health: HealthConfig {
+                max_queue_depth: loader
+                    .parse("HEALTH_MAX_QUEUE_DEPTH", DEFAULT_HEALTH_MAX_QUEUE_DEPTH),
+                self_test_enabled: loader
+                    .parse("HEALTH_SELF_TEST_ENABLED", DEFAULT_HEALTH_SELF_TEST_ENABLED),
+            },
+            rate_limit: RateLimitConfig {
+                enabled: loader.parse("RATE_LIMIT_ENABLED", DEFAULT_RATE_LIMIT_ENABLED),
+                requests_per_minute: loader.parse(
+                    "RATE_LIMIT_REQUESTS_PER_MINUTE",
+                    DEFAULT_RATE_LIMIT_REQUESTS_PER_MINUTE,
+                ),
+                burst: loader.parse("RATE_LIMIT_BURST", DEFAULT_RATE_LIMIT_BURST),
+                max_concurrent: loader.parse(
+                    "RATE_LIMIT_MAX_CONCURRENT",
+                    DEFAULT_RATE_LIMIT_MAX_CONCURRENT,
+                ),
+                key: loader.parse("RATE_LIMIT_KEY", RateLimitKey::default()),
+                trust_forwarded_for: loader.parse(
+                    "RATE_LIMIT_TRUST_FORWARDED_FOR",
+                    DEFAULT_RATE_LIMIT_TRUST_FORWARDED_FOR,
+                ),
+            },
+        }
+    }
+
+    /// Check the values that are valid on their own but not together.
+    fn validate(&self, loader: &mut ConfigLoader) {
+        for error in self.otel.missing_endpoints() {
+            loader.error(error);
+        }
+        if self.server.grpc_enabled && self.server.grpc_port == self.server.port {
+            loader.error(ConfigError::new(
+                "SERVER_GRPC_PORT",
+                &self.server.grpc_port.to_string(),
+                "Must be different from SERVER_PORT when SERVER_GRPC_ENABLED is true",
+            ));
+        }
+        for (key, value) in [
+            ("JOBS_MAX_QUEUED", self.jobs.max_queued),
+            ("JOBS_WORKERS", self.jobs.workers),
+        ] {
+            if value == 0 {
+                loader.error(ConfigError::new(key, "0", "Must be greater than 0"));
+            }
+        }
+        if self.health.max_queue_depth == 0 {
+            loader.error(ConfigError::new(
+                "HEALTH_MAX_QUEUE_DEPTH",
+                "0",
+                "Must be greater than 0",
+            ));
+        }
+        if self.security.jwt.enabled() {
+            for (key, value) in [
+                ("SECURITY_JWT_ISSUER", &self.security.jwt.issuer),
+                ("SECURITY_JWT_AUDIENCE", &self.security.jwt.audience),
+            ] {
+                if value.is_none() {
+                    loader.error(ConfigError::new(
+                        key,
+                        "",
+                        "Must be set when SECURITY_JWT_JWKS is set",
+                    ));
+                }
+            }
+        }
+        if self.security.cors.allow_credentials {
+            // Browsers reject credentialed responses that allow any origin or expose any header.
+            for (key, values) in [
+                (
+                    "SECURITY_CORS_ALLOWED_ORIGINS",
+                    &self.security.cors.allowed_origins,
+                ),
+                (
+                    "SECURITY_CORS_EXPOSED_HEADERS",
+                    &self.security.cors.exposed_headers,
+                ),
+            ] {
+                if values.iter().any(|value| value == "*") {
+                    loader.error(ConfigError::new(
+                        key,
+                        &values.join(","),
+                        "Must not be * when SECURITY_CORS_ALLOW_CREDENTIALS is true",
+                    ));
+                }
+            }
+        }
+        let mut api_key_names = BTreeSet::new();
+        for api_key in &self.security.api_keys {
+            if !api_key_names.insert(&api_key.name) {
+                loader.error(ConfigError::new(
+                    "SECURITY_API_KEYS",
+                    &api_key.name,
+                    "API key names must be unique",
+                ));
+            }
+        }
+        if self.rate_limit.enabled {
+            for (key, value) in [
+                (
+                    "RATE_LIMIT_REQUESTS_PER_MINUTE",
+                    self.rate_limit.requests_per_minute as usize,
+                ),
+                ("RATE_LIMIT_BURST", self.rate_limit.burst as usize),
+                ("RATE_LIMIT_MAX_CONCURRENT", self.rate_limit.max_concurrent),
+            ] {
+                if value == 0 {
+                    loader.error(ConfigError::new(
+                        key,
+                        "0",
+                        "Must be greater than 0 when RATE_LIMIT_ENABLED is true",
+                    ));
+                }
+            }
+        }
+        if self.cache.enabled && self.cache.max_entries == 0 {
+            loader.error(ConfigError::new(
+                "CACHE_MAX_ENTRIES",
+                "0",
+                "Must be greater than 0 when CACHE_ENABLED is true",
+            ));
+        }
+    }
+}
+
+fn parse_sampler_ratio(ratio: &str) -> Result<f64, String> {
+    let ratio = ratio.parse::<f64>().map_err(|error| error.to_string())?;
+    if (0.0..=1.0).contains(&ratio) {
+        Ok(ratio)
+    } else {
+        Err("Must be between 0.0 and 1.0".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::io::Write as _;
+
+    use crate::config::app_config::{
+        AppConfig, MetricsExporter, OtelConfig, OtelProvider, OtelProviderConfig, OtlpProtocol,
+        RateLimitKey, Scope, TracesSampler, parse_api_keys, parse_cors_headers, parse_cors_methods,
+        parse_cors_origins, parse_default_models, parse_otlp_headers,
+    };
+    use crate::config::error::{REDACTED, SECRET_KEYS, ServerError};
+    use crate::config::loader::ConfigLoader;
+
+    fn create_test_otel_config() -> OtelConfig {
+        OtelConfig {
+            enabled: true,
+            service_name: None,
+            traces_endpoint: Some("http://localhost:4317".to_string()),
+            logs_endpoint: Some("http://localhost:4317".to_string()),
+            metrics_endpoint: None,
+            metric_export_interval: None,
+            metrics_exporter: MetricsExporter::Prometheus,
+            protocol: OtlpProtocol::Grpc,
+            traces_sampler: TracesSampler::TraceIdRatio,
+            traces_sampler_ratio: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_otel_config_validate() {
+        let mut otel_config = create_test_otel_config();
+        assert!(otel_config.validate().is_ok());
+
+        otel_config.metrics_exporter = MetricsExporter::Otlp;
+        otel_config.logs_endpoint = None;
+        match otel_config.validate() {
+            Err(ServerError::ConfigInvalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.key.as_str())
+                    .collect::<Vec<_>>(),
+                [
+                    "OTEL_EXPORTER_OTLP_LOGS_ENDPOINT",
+                    "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT"
+                ]
+            ),
+            result => panic!("Expected missing endpoints, got {result:?}"),
+        }
+
+        otel_config.enabled = false;
+        assert!(otel_config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_otel_enums_from_str() {
+        assert_eq!(
+            "http/protobuf".parse::<OtlpProtocol>(),
+            Ok(OtlpProtocol::HttpProtobuf)
+        );
+        assert_eq!(
+            "TraceIdRatio".parse::<TracesSampler>(),
+            Ok(TracesSampler::TraceIdRatio)
+        );
+        assert!("sometimes".parse::<TracesSampler>().is_err());
+        // Like before the sampler was configurable, every trace is sampled by default.
+        assert_eq!(TracesSampler::default(), TracesSampler::TraceIdRatio);
+    }
+
+    fn create_test_provider_config(provider: Option<OtelProvider>) -> OtelProviderConfig {
+        OtelProviderConfig {
+            provider,
+            organization: Some("123".to_string()),
+            stream_name: Some("ocr".to_string()),
+            auth_token: Some("secret".to_string()),
+            headers: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_otlp_headers() {
+        let headers = parse_otlp_headers(" API-Key = secret ,x-tenant=a%20b%2Cc,,").unwrap();
+        assert_eq!(
+            headers,
+            BTreeMap::from([
+                ("api-key".to_string(), "secret".to_string()),
+                ("x-tenant".to_string(), "a b,c".to_string()),
+            ])
+        );
+        assert_eq!(parse_otlp_headers("").unwrap(), BTreeMap::new());
+        assert_eq!(
+            parse_otlp_headers("token=a=b").unwrap()["token"],
+            "a=b".to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_otlp_headers_invalid() {
+        assert!(parse_otlp_headers("api-key").is_err());
+        assert!(parse_otlp_headers("=secret").is_err());
+        assert!(parse_otlp_headers("api-key=%FF").is_err());
+        assert_eq!(
+            parse_otlp_headers("x-tenant=a,Authorization: Bearer secret").unwrap_err(),
+            "Header 2 is not in the key=value format"
+        );
+    }
+
+    #[test]
+    fn test_parse_default_models() {
+        assert_eq!(
+            parse_default_models("eng=best, chi_sim = chi_sim_vert,").unwrap(),
+            BTreeMap::from([
+                ("chi_sim".to_string(), "chi_sim_vert".to_string()),
+                ("eng".to_string(), "best".to_string()),
+            ])
+        );
+        assert!(parse_default_models("eng").is_err());
+        assert!(parse_default_models("eng=").is_err());
+    }
+
+    #[test]
+    fn test_parse_api_keys() {
+        let sha256 = "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08";
+        let api_keys = parse_api_keys(&format!(
+            "ci:{sha256}:ocr languages:read, ops:{sha256}:admin,"
+        ))
+        .unwrap();
+
+        assert_eq!(api_keys.len(), 2);
+        assert_eq!(api_keys[0].name, "ci");
+        assert_eq!(api_keys[0].sha256, sha256.to_lowercase());
+        assert_eq!(
+            api_keys[0].scopes,
+            BTreeSet::from([Scope::Ocr, Scope::LanguagesRead])
+        );
+        assert_eq!(api_keys[1].scopes, BTreeSet::from([Scope::Admin]));
+        assert!(parse_api_keys(&format!("ci:{sha256}")).is_err());
+        assert!(parse_api_keys(&format!(":{sha256}:ocr")).is_err());
+        assert!(parse_api_keys(&format!("ci:{sha256}:")).is_err());
+        assert!(parse_api_keys(&format!("ci:{sha256}:write")).is_err());
+        // Keys must be hashed.
+        assert!(parse_api_keys("ci:secret-key:ocr").is_err());
+    }
+
+    #[test]
+    fn test_jwt_requires_issuer_and_audience() {
+        let env = BTreeMap::from([(
+            "SECURITY_JWT_JWKS".to_string(),
+            "https://issuer.example.com/.well-known/jwks.json".to_string(),
+        )]);
+        let mut loader = ConfigLoader::from_sources(env, BTreeMap::new());
+        let app_config = AppConfig::from_loader(&mut loader);
+        app_config.validate(&mut loader);
+
+        assert!(app_config.security.jwt.enabled());
+        match loader.finish() {
+            Err(ServerError::ConfigInvalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.key.as_str())
+                    .collect::<Vec<_>>(),
+                ["SECURITY_JWT_ISSUER", "SECURITY_JWT_AUDIENCE"]
+            ),
+            result => panic!("Expected invalid configuration, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_requires_positive_limits() {
+        let env = BTreeMap::from([
+            ("RATE_LIMIT_ENABLED".to_string(), "true".to_string()),
+            ("RATE_LIMIT_BURST".to_string(), "0".to_string()),
+            ("RATE_LIMIT_KEY".to_string(), "ip".to_string()),
+        ]);
+        let mut loader = ConfigLoader::from_sources(env, BTreeMap::new());
+        let app_config = AppConfig::from_loader(&mut loader);
+        app_config.validate(&mut loader);
+
+        assert_eq!(app_config.rate_limit.key, RateLimitKey::Ip);
+        match loader.finish() {
+            Err(ServerError::ConfigInvalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.key.as_str())
+                    .collect::<Vec<_>>(),
+                ["RATE_LIMIT_BURST"]
+            ),
+            result => panic!("Expected invalid configuration, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_cors_defaults() {
+        let mut loader = ConfigLoader::from_sources(BTreeMap::new(), BTreeMap::new());
+        let cors = AppConfig::from_loader(&mut loader).security.cors;
+        assert_eq!(cors.allowed_origins, ["*"]);
+        assert_eq!(cors.allowed_methods, ["*"]);
+
+        let env = BTreeMap::from([("SERVER_ENVIRONMENT".to_string(), "production".to_string())]);
+        let mut loader = ConfigLoader::from_sources(env, BTreeMap::new());
+        let cors = AppConfig::from_loader(&mut loader).security.cors;
+        assert!(cors.allowed_origins.is_empty());
+        assert_eq!(cors.allowed_methods, ["GET", "POST"]);
+        assert!(cors.allowed_headers.contains(&"x-api-key".to_string()));
+        assert!(!cors.allow_credentials);
+    }
+
+    #[test]
+    fn test_parse_cors() {
+        assert_eq!(
+            parse_cors_origins("https://App.example.com, https://*.example.com:8443").unwrap(),
+            ["https://app.example.com", "https://*.example.com:8443"]
+        );
+        for origin in [
+            "app.example.com",
+            "https://",
+            "https://app.example.com/",
+            "https://app.*.com",
+        ] {
+            assert!(parse_cors_origins(origin).is_err(), "{origin}");
+        }
+        assert_eq!(parse_cors_methods("get, POST").unwrap(), ["GET", "POST"]);
+        assert!(parse_cors_methods("GET POST").is_err());
+        assert_eq!(
+            parse_cors_headers("X-API-Key,content-type").unwrap(),
+            ["x-api-key", "content-type"]
+        );
+        assert!(parse_cors_headers("x api key").is_err());
+    }
+
+    #[test]
+    fn test_cors_credentials_require_origins() {
+        let env = BTreeMap::from([(
+            "SECURITY_CORS_ALLOW_CREDENTIALS".to_string(),
+            "true".to_string(),
+        )]);
+        let mut loader = ConfigLoader::from_sources(env, BTreeMap::new());
+        let app_config = AppConfig::from_loader(&mut loader);
+        app_config.validate(&mut loader);
+
+        match loader.finish() {
+            Err(ServerError::ConfigInvalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.key.as_str())
+                    .collect::<Vec<_>>(),
+                ["SECURITY_CORS_ALLOWED_ORIGINS"]
+            ),
+            result => panic!("Expected invalid configuration, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_api_keys_file() {
+        let sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "# Issued to the CI\nci:{sha256}:ocr\n\nops:{sha256}:admin"
+        )
+        .unwrap();
+        let env = BTreeMap::from([
+            (
+                "SECURITY_API_KEYS".to_string(),
+                format!("web:{sha256}:languages:read"),
+            ),
+            (
+                "SECURITY_API_KEYS_FILE".to_string(),
+                file.path().to_string_lossy().to_string(),
+            ),
+        ]);
+        let mut loader = ConfigLoader::from_sources(env.clone(), BTreeMap::new());
+        let app_config = AppConfig::from_loader(&mut loader);
+        loader.finish().unwrap();
+
+        let names = app_config
+            .security
+            .api_keys
+            .iter()
+            .map(|api_key| api_key.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["web", "ci", "ops"]);
+        assert_eq!(
+            app_config
+                .entries()
+                .into_iter()
+                .find(|(key, _)| *key == "SECURITY_API_KEYS"),
+            Some((
+                "SECURITY_API_KEYS",
+                "web:********:languages:read,ci:********:ocr,ops:********:admin".to_string()
+            ))
+        );
+
+        // The names of the keys identify them, so they must be unique.
+        writeln!(file, "ci:{sha256}:admin").unwrap();
+        let mut loader = ConfigLoader::from_sources(env, BTreeMap::new());
+        AppConfig::from_loader(&mut loader).validate(&mut loader);
+        assert!(loader.finish().is_err());
+    }
+
+    #[test]
+    fn test_provider_preset_headers() {
+        let openobserve = create_test_provider_config(Some(OtelProvider::OpenObserve)).headers();
+        assert_eq!(openobserve["authorization"], "secret");
+        assert_eq!(openobserve["organization"], "123");
+        assert_eq!(openobserve["stream-name"], "ocr");
+
+        let honeycomb = create_test_provider_config(Some(OtelProvider::Honeycomb)).headers();
+        assert_eq!(honeycomb["x-honeycomb-team"], "secret");
+        assert_eq!(honeycomb["x-honeycomb-dataset"], "ocr");
+
+        let grafana = create_test_provider_config(Some(OtelProvider::GrafanaCloud)).headers();
+        assert_eq!(grafana["authorization"], "Basic MTIzOnNlY3JldA==");
+
+        assert!(create_test_provider_config(None).headers().is_empty());
+    }
+
+    #[test]
+    fn test_explicit_headers_override_preset() {
+        let mut provider_config = create_test_provider_config(Some(OtelProvider::NewRelic));
+        provider_config.headers = parse_otlp_headers("api-key=override,x-extra=1").unwrap();
+
+        let headers = provider_config.headers();
+        assert_eq!(headers["api-key"], "override");
+        assert_eq!(headers["x-extra"], "1");
+    }
+
+    #[test]
+    fn test_app_config_reports_all_invalid_values() {
+        let env = BTreeMap::from([
+            ("SERVER_PORT".to_string(), "9000".to_string()),
+            ("SERVER_GRPC_PORT".to_string(), "9000".to_string()),
+            (
+                "OTEL_METRIC_EXPORT_INTERVAL".to_string(),
+                "soon".to_string(),
+            ),
+            ("OTEL_TRACES_SAMPLER_ARG".to_string(), "2".to_string()),
+        ]);
+        let mut loader = ConfigLoader::from_sources(env, BTreeMap::new());
+        let app_config = AppConfig::from_loader(&mut loader);
+        app_config.validate(&mut loader);
+
+        assert_eq!(app_config.server.port, 9000);
+        assert_eq!(app_config.otel.metric_export_interval, None);
+        assert_eq!(app_config.otel.traces_sampler_ratio, 1.0);
+        match loader.finish() {
+            Err(ServerError::ConfigInvalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| (error.key.as_str(), error.value.as_str()))
+                    .collect::<Vec<_>>(),
+                [
+                    ("OTEL_METRIC_EXPORT_INTERVAL", "soon"),
+                    ("OTEL_TRACES_SAMPLER_ARG", "2"),
+                    ("SERVER_GRPC_PORT", "9000"),
+                ]
+            ),
+            result => panic!("Expected invalid configuration, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_app_config_entries_redact_secrets() {
+        let env = BTreeMap::from([
+            ("OTEL_PROVIDER_AUTH_TOKEN".to_string(), "secret".to_string()),
+            (
+                "OTEL_EXPORTER_OTLP_HEADERS".to_string(),
+                "api-key=secret".to_string(),
+            ),
+            ("CACHE_BACKEND".to_string(), "DISK".to_string()),
+        ]);
+        let mut loader = ConfigLoader::from_sources(env, BTreeMap::new());
+        let entries = BTreeMap::from_iter(AppConfig::from_loader(&mut loader).entries());
+
+        assert_eq!(entries["OTEL_PROVIDER_AUTH_TOKEN"], "********");
+        assert_eq!(entries["OTEL_EXPORTER_OTLP_HEADERS"], "api-key=********");
+        assert_eq!(entries["CACHE_BACKEND"], "disk");
+        assert_eq!(entries["OTEL_SERVICE_NAME"], "");
+        assert_eq!(entries["SERVER_PORT"], "8080");
+
+        let sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let env = BTreeMap::from([
+            ("SECURITY_ADMIN_TOKEN".to_string(), sha256.to_string()),
+            ("SECURITY_API_KEYS".to_string(), format!("ci:{sha256}:ocr")),
+            ("OTEL_PROVIDER_AUTH_TOKEN".to_string(), sha256.to_string()),
+            (
+                "OTEL_EXPORTER_OTLP_HEADERS".to_string(),
+                format!("api-key={sha256}"),
+            ),
+        ]);
+        let mut loader = ConfigLoader::from_sources(env, BTreeMap::new());
+        let entries = BTreeMap::from_iter(AppConfig::from_loader(&mut loader).entries());
+        for key in SECRET_KEYS {
+            assert!(entries[key].contains(REDACTED), "{key}");
+            assert!(!entries[key].contains(sha256), "{key}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_otlp_headers_error_redacts_secrets() {
+        for headers in [
+            "Authorization: Bearer secret",
+            "=secret",
+            "api-key=secret%FF",
+        ] {
+            let env = BTreeMap::from([(
+                "OTEL_EXPORTER_OTLP_HEADERS".to_string(),
+                headers.to_string(),
+            )]);
+            let mut loader = ConfigLoader::from_sources(env, BTreeMap::new());
+            AppConfig::from_loader(&mut loader);
+            let error = loader.finish().unwrap_err().to_string();
+            assert!(error.contains("OTEL_EXPORTER_OTLP_HEADERS"), "{error}");
+            assert!(!error.contains("secret"), "{error}");
+        }
     }
 }
@@ -1,11 +1,64 @@
+use std::fmt;
+
 use thiserror::Error;
 
 pub type ServerResult<T> = core::result::Result<T, ServerError>;
 
+/// Replaces secrets in the output of `AppConfig::entries` and in `ConfigError`s.
+pub(crate) const REDACTED: &str = "********";
+
+/// The keys whose values are secret, so they are never printed.
+pub(crate) const SECRET_KEYS: [&str; 4] = [
+    "SECURITY_ADMIN_TOKEN",
+    "SECURITY_API_KEYS",
+    "OTEL_PROVIDER_AUTH_TOKEN",
+    "OTEL_EXPORTER_OTLP_HEADERS",
+];
+
 #[derive(Debug, Error)]
 pub enum ServerError {
-    #[error("Failed to load the environnement variable file, not found : `{0}`")]
-    ConfigMissingEnv(&'static str),
-    #[error("Failed to load the environnement variable file, wrong format : `{0}`")]
-    ConfigWrongFormat(&'static str),
+    #[error("Failed to load the configuration file `{path}`: {message}")]
+    ConfigFile { path: String, message: String },
+    #[error("Invalid configuration:{}", format_config_errors(.0))]
+    ConfigInvalid(Vec<ConfigError>),
+}
+
+/// A configuration value that cannot be used, with the key and value that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// The environment variable (or its equivalent in the configuration file).
+    pub key: String,
+    /// The offending value, empty if the key is missing.
+    pub value: String,
+    pub message: String,
+}
+
+impl ConfigError {
+    #[must_use]
+    pub fn new(key: &str, value: &str, message: impl Into<String>) -> Self {
+        Self {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The value of a missing secret is shown, so it is clear that the key is not set.
+        let value = if SECRET_KEYS.contains(&self.key.as_str()) && !self.value.is_empty() {
+            REDACTED
+        } else {
+            &self.value
+        };
+        write!(f, "{}={value:?}: {}", self.key, self.message)
+    }
+}
+
+fn format_config_errors(errors: &[ConfigError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("\n  - {error}"))
+        .collect()
 }
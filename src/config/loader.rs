@@ -0,0 +1,357 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use super::error::{ConfigError, ServerError, ServerResult};
+
+/// Reads configuration values from environment variables layered over a configuration file.
+///
+/// Every key is the name of an environment variable. In the configuration file keys are written
+/// in lower case and may be grouped in tables by their prefix, so `SERVER_PORT` can be set with
+/// `server_port = 8080` or with `port = 8080` in a `[server]` table.
+///
+/// Problems are collected instead of failing on the first one, so all of them can be reported
+/// at once by `finish`.
+#[derive(Debug, Default)]
+pub struct ConfigLoader {
+    env: BTreeMap<String, String>,
+    file: BTreeMap<String, String>,
+    used_keys: BTreeSet<String>,
+    errors: Vec<ConfigError>,
+}
+
+impl ConfigLoader {
+    /// Create a loader for the environment of the process and an optional configuration file.
+    /// `overrides` take precedence over the environment.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigFile` if the file cannot be read or parsed.
+    pub fn new(
+        config_file: Option<&Path>,
+        overrides: BTreeMap<String, String>,
+    ) -> ServerResult<Self> {
+        let file = match config_file {
+            Some(path) => read_config_file(path)?,
+            None => BTreeMap::new(),
+        };
+        let mut env: BTreeMap<String, String> = env::vars().collect();
+        env.extend(overrides);
+        Ok(Self::from_sources(env, file))
+    }
+
+    /// Create a loader from environment variables and flattened configuration file values.
+    #[must_use]
+    pub fn from_sources(env: BTreeMap<String, String>, file: BTreeMap<String, String>) -> Self {
+        Self {
+            env,
+            file,
+            ..Self::default()
+        }
+    }
+
+    /// The raw value of a key. Environment variables take precedence over the configuration
+    /// file and empty values are treated as unset.
+    pub fn value(&mut self, key: &str) -> Option<String> {
+        self.used_keys.insert(key.to_owned());
+        [&self.env, &self.file]
+            .into_iter()
+            .filter_map(|values| values.get(key))
+            .find(|value| !value.is_empty())
+            .cloned()
+    }
+
+    pub fn string(&mut self, key: &str, default: &str) -> String {
+        self.value(key).unwrap_or_else(|| default.to_owned())
+    }
+
+    /// Parse a value, or use `default` if it is unset. Invalid values are recorded as errors.
+    pub fn parse<T>(&mut self, key: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.parse_optional(key).unwrap_or(default)
+    }
+
+    /// Parse a value that has no default. Invalid values are recorded as errors.
+    pub fn parse_optional<T>(&mut self, key: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.parse_with(key, |value| {
+            value.parse::<T>().map_err(|error| error.to_string())
+        })
+    }
+
+    /// Parse a value with a custom parser. Invalid values are recorded as errors.
+    pub fn parse_with<T>(
+        &mut self,
+        key: &str,
+        parser: impl FnOnce(&str) -> Result<T, String>,
+    ) -> Option<T> {
+        let value = self.value(key)?;
+        match parser(&value) {
+            Ok(parsed) => Some(parsed),
+            Err(message) => {
+                self.errors.push(ConfigError::new(key, &value, message));
+                None
+            }
+        }
+    }
+
+    /// Record a problem found while validating the loaded configuration.
+    pub fn error(&mut self, error: ConfigError) {
+        self.errors.push(error);
+    }
+
+    /// Report all the recorded problems, and the keys of the configuration file that are not
+    /// configuration keys (e.g. typos).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigInvalid` with every problem if there is at least one.
+    pub fn finish(mut self) -> ServerResult<()> {
+        for (key, value) in &self.file {
+            if !self.used_keys.contains(key) {
+                self.errors.push(ConfigError::new(
+                    key,
+                    value,
+                    "Unknown configuration key in the configuration file",
+                ));
+            }
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ServerError::ConfigInvalid(self.errors))
+        }
+    }
+}
+
+/// Read a TOML or YAML configuration file into flat keys, based on its extension.
+fn read_config_file(path: &Path) -> ServerResult<BTreeMap<String, String>> {
+    let config_file_error = |message: String| ServerError::ConfigFile {
+        path: path.display().to_string(),
+        message,
+    };
+    let contents =
+        fs::read_to_string(path).map_err(|error| config_file_error(error.to_string()))?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let document = match extension.as_str() {
+        "toml" => toml::from_str::<Value>(&contents).map_err(|error| error.to_string()),
+        "yaml" | "yml" => {
+            serde_yaml_ng::from_str::<Value>(&contents).map_err(|error| error.to_string())
+        }
+        _ => Err("Unsupported format, expected a .toml, .yaml or .yml file".to_owned()),
+    }
+    .map_err(config_file_error)?;
+
+    let Value::Object(table) = document else {
+        return Err(config_file_error(
+            "Expected a table of configuration keys".to_owned(),
+        ));
+    };
+    let mut values = BTreeMap::new();
+    flatten(None, Value::Object(table), &mut values);
+    Ok(values)
+}
+
+/// Flatten nested tables into upper case keys joined by `_`. Arrays become comma separated values.
+fn flatten(key: Option<String>, value: Value, values: &mut BTreeMap<String, String>) {
+    let scalar = |value: Value| match value {
+        Value::String(string) => string,
+        value => value.to_string(),
+    };
+    match (key, value) {
+        (key, Value::Object(table)) => {
+            for (name, value) in table {
+                let name = name.to_uppercase().replace('-', "_");
+                let nested_key = match &key {
+                    Some(key) => format!("{key}_{name}"),
+                    None => name,
+                };
+                flatten(Some(nested_key), value, values);
+            }
+        }
+        (_, Value::Null) | (None, _) => {}
+        (Some(key), Value::Array(items)) => {
+            let items: Vec<String> = items.into_iter().map(scalar).collect();
+            values.insert(key, items.join(","));
+        }
+        (Some(key), value) => {
+            values.insert(key, scalar(value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::io::Write as _;
+
+    use crate::config::error::{ConfigError, ServerError};
+    use crate::config::loader::{ConfigLoader, read_config_file};
+
+    fn sources(values: &[(&str, &str)]) -> BTreeMap<String, String> {
+        values
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn write_config_file(extension: &str, contents: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new()
+            .suffix(&format!(".{extension}"))
+            .tempfile()
+            .unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_env_takes_precedence_over_file() {
+        let mut loader = ConfigLoader::from_sources(
+            sources(&[("SERVER_PORT", "9000"), ("SERVER_HOST", "")]),
+            sources(&[("SERVER_PORT", "8000"), ("SERVER_HOST", "127.0.0.1")]),
+        );
+        assert_eq!(loader.parse("SERVER_PORT", 8080_u16), 9000);
+        // Empty environment variables are unset, so the file value is used.
+        assert_eq!(loader.string("SERVER_HOST", "0.0.0.0"), "127.0.0.1");
+        assert_eq!(loader.string("SERVICE_NAME", "ocr-service"), "ocr-service");
+        assert!(loader.finish().is_ok());
+    }
+
+    #[test]
+    fn test_all_errors_are_reported() {
+        let mut loader = ConfigLoader::from_sources(
+            sources(&[("SERVER_PORT", "80a"), ("CACHE_ENABLED", "yes")]),
+            sources(&[("SERVER_PROT", "8000")]),
+        );
+        assert_eq!(loader.parse("SERVER_PORT", 8080_u16), 8080);
+        assert!(!loader.parse("CACHE_ENABLED", false));
+
+        let Err(ServerError::ConfigInvalid(errors)) = loader.finish() else {
+            panic!("Expected invalid configuration");
+        };
+        let keys: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|error| (error.key.as_str(), error.value.as_str()))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("SERVER_PORT", "80a"),
+                ("CACHE_ENABLED", "yes"),
+                ("SERVER_PROT", "8000")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_toml_config_file() {
+        let file = write_config_file(
+            "toml",
+            r#"
+            tessdata_path = "/usr/share/tessdata"
+
+            [server]
+            port = 9000
+            grpc-enabled = false
+
+            [otel.exporter_otlp]
+            traces_endpoint = "http://collector:4317"
+            "#,
+        );
+        let values = read_config_file(file.path()).unwrap();
+        assert_eq!(
+            values,
+            sources(&[
+                (
+                    "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+                    "http://collector:4317"
+                ),
+                ("SERVER_GRPC_ENABLED", "false"),
+                ("SERVER_PORT", "9000"),
+                ("TESSDATA_PATH", "/usr/share/tessdata"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_read_yaml_config_file() {
+        let file = write_config_file(
+            "yaml",
+            "server:\n  port: 9000\ncache:\n  enabled: true\n  ttl: 60\nlist: [a, b]\n",
+        );
+        let values = read_config_file(file.path()).unwrap();
+        assert_eq!(
+            values,
+            sources(&[
+                ("CACHE_ENABLED", "true"),
+                ("CACHE_TTL", "60"),
+                ("LIST", "a,b"),
+                ("SERVER_PORT", "9000"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_read_invalid_config_file() {
+        let file = write_config_file("toml", "[server\nport = 1");
+        assert!(matches!(
+            read_config_file(file.path()),
+            Err(ServerError::ConfigFile { .. })
+        ));
+        let file = write_config_file("ini", "port = 1");
+        assert!(matches!(
+            read_config_file(file.path()),
+            Err(ServerError::ConfigFile { .. })
+        ));
+    }
+
+    #[test]
+    fn test_config_error_display() {
+        let error = ConfigError::new("SERVER_PORT", "80a", "invalid digit found in string");
+        assert_eq!(
+            error.to_string(),
+            "SERVER_PORT=\"80a\": invalid digit found in string"
+        );
+
+        let error = ConfigError::new("SECURITY_ADMIN_TOKEN", "short", "Must be longer");
+        assert_eq!(
+            error.to_string(),
+            "SECURITY_ADMIN_TOKEN=\"********\": Must be longer"
+        );
+        let error = ConfigError::new("SECURITY_ADMIN_TOKEN", "", "Is required");
+        assert_eq!(error.to_string(), "SECURITY_ADMIN_TOKEN=\"\": Is required");
+
+        let error = ServerError::ConfigInvalid(vec![ConfigError::new(
+            "SERVER_PORT",
+            "80a",
+            "invalid digit found in string",
+        )]);
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration:\n  - SERVER_PORT=\"80a\": invalid digit found in string"
+        );
+        let error = ServerError::ConfigFile {
+            path: "config.toml".to_owned(),
+            message: "expected a table".to_owned(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Failed to load the configuration file `config.toml`: expected a table"
+        );
+    }
+}
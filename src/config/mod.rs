@@ -1,2 +1,3 @@
 pub mod app_config;
 pub mod error;
+pub mod loader;
@@ -0,0 +1,8 @@
+pub mod service;
+
+/// Types and service traits generated from `proto/ocr_service.proto`.
+pub mod proto {
+    tonic::include_proto!("ocr_service.v1");
+}
+
+pub use service::{OcrGrpcService, grpc_service};
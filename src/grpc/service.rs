@@ -0,0 +1,218 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use futures_util::{Stream, StreamExt as _};
+use tonic::{Request, Response, Status, Streaming, metadata::MetadataMap};
+
+use crate::{
+    AppState,
+    config::app_config::Scope,
+    grpc::proto::{
+        HealthRequest, HealthResponse, ListLanguagesRequest, ListLanguagesResponse,
+        RecognizeRequest, RecognizeResponse, TesseractModel,
+        ocr_service_server::{OcrService, OcrServiceServer},
+    },
+    middleware::{auth::authenticate, rate_limit::recognition_permit},
+    models::{error::ErrorType, health::HealthStatus, images::ImagesQueryParams},
+    utils::{
+        cache::cache_key,
+        health::check_readiness,
+        languages::sorted_languages,
+        metrics::model_attributes,
+        ocr::{decode_image, recognize_image},
+        rate_limit::RecognitionPermit,
+        validations::{validate_file_type, validate_language_params},
+    },
+};
+
+/// Create the gRPC `OcrService`, sharing the state of the REST API.
+pub fn grpc_service(app_state: AppState) -> OcrServiceServer<OcrGrpcService> {
+    // Like uploads to the REST API, images are only limited when the upload limit is enabled.
+    let server = &app_state.app_config.server;
+    let max_message_size = if server.file_upload_max_size_enabled {
+        server.file_upload_max_size
+    } else {
+        usize::MAX
+    };
+    OcrServiceServer::new(OcrGrpcService { state: app_state })
+        .max_decoding_message_size(max_message_size)
+}
+
+/// Implementation of the gRPC `OcrService`.
+#[derive(Clone, Debug)]
+pub struct OcrGrpcService {
+    state: AppState,
+}
+
+impl OcrGrpcService {
+    /// Require an API key with `scope` in the metadata of a request, like the REST API.
+    async fn authorize(&self, metadata: &MetadataMap, scope: Scope) -> Result<(), ErrorType> {
+        let headers = metadata.clone().into_headers();
+        authenticate(&self.state, &headers, scope).await.map(|_| ())
+    }
+
+    /// Require an API key with the `ocr` scope and take a recognition permit for the client of a
+    /// request, like the REST API.
+    async fn authorize_recognition(
+        &self,
+        metadata: &MetadataMap,
+        remote_address: Option<SocketAddr>,
+    ) -> Result<RecognitionPermit, ErrorType> {
+        let headers = metadata.clone().into_headers();
+        let principal = authenticate(&self.state, &headers, Scope::Ocr).await?;
+        recognition_permit(&self.state, principal.as_ref(), &headers, remote_address)
+    }
+}
+
+impl From<ErrorType> for Status {
+    fn from(error: ErrorType) -> Self {
+        tracing::error!("{}", error);
+        match error {
+            ErrorType::InvalidJsonBody(_) | ErrorType::InvalidRequest(_) => {
+                Status::invalid_argument(error.to_string())
+            }
+            ErrorType::Unauthorized(_) => Status::unauthenticated(error.to_string()),
+            ErrorType::Forbidden(_) => Status::permission_denied(error.to_string()),
+            ErrorType::TooManyRequests(_) => Status::resource_exhausted(error.to_string()),
+            ErrorType::ServiceUnavailable(_) => Status::unavailable(error.to_string()),
+            ErrorType::NotFound(_) => Status::not_found(error.to_string()),
+            ErrorType::InternalError(err) => Status::internal(err.to_string()),
+        }
+    }
+}
+
+/// Validate a gRPC recognize request the same way as the REST API and perform OCR on its image.
+async fn recognize(state: &AppState, request: RecognizeRequest) -> Result<String, ErrorType> {
+    let params = ImagesQueryParams {
+        language: request.language,
+        model: request.model,
+    };
+    let tesseract_model = validate_language_params(
+        &params,
+        &state.available_tesseract_languages.load(),
+        &state.app_config.service.default_language,
+        &state.app_config.tesseract.model_preferences,
+    )?;
+    if let Some(content_type) = &request.content_type {
+        validate_file_type(content_type)?;
+    }
+
+    let cached_result = state
+        .result_cache
+        .clone()
+        .map(|cache| (cache, cache_key(&request.image, &tesseract_model)));
+    if let Some((cache, key)) = &cached_result {
+        if let Some(text) = cache.get(key).await {
+            return Ok(text);
+        }
+    }
+
+    let data_path = state.app_config.tesseract.data_path.clone();
+    let span = tracing::Span::current();
+    let text = tokio::task::spawn_blocking(move || {
+        let _guard = span.enter();
+        let img = decode_image(&request.image, &model_attributes(&tesseract_model))?;
+        recognize_image(&data_path, &tesseract_model, &img, None)
+    })
+    .await
+    .map_err(|join_error| {
+        ErrorType::InternalError(anyhow::anyhow!("Recognition task failed: {join_error}"))
+    })??;
+
+    if let Some((cache, key)) = cached_result {
+        cache.insert(key, text.clone()).await;
+    }
+    Ok(text)
+}
+
+#[tonic::async_trait]
+impl OcrService for OcrGrpcService {
+    type RecognizeStreamStream =
+        Pin<Box<dyn Stream<Item = Result<RecognizeResponse, Status>> + Send + 'static>>;
+
+    #[tracing::instrument(skip(self, request))]
+    async fn recognize(
+        &self,
+        request: Request<RecognizeRequest>,
+    ) -> Result<Response<RecognizeResponse>, Status> {
+        let _permit = self
+            .authorize_recognition(request.metadata(), request.remote_addr())
+            .await?;
+        let text = recognize(&self.state, request.into_inner()).await?;
+        Ok(Response::new(RecognizeResponse { text, frame_id: 1 }))
+    }
+
+    #[tracing::instrument(skip(self, request))]
+    async fn recognize_stream(
+        &self,
+        request: Request<Streaming<RecognizeRequest>>,
+    ) -> Result<Response<Self::RecognizeStreamStream>, Status> {
+        let metadata = request.metadata().clone();
+        let mut permit = self
+            .authorize_recognition(&metadata, request.remote_addr())
+            .await?;
+        let state = self.state.clone();
+        // Requests are processed one at a time, in order, so responses match the request order.
+        // The stream counts as one recognition until it ends, and each request after the first
+        // one takes a token.
+        let responses = request
+            .into_inner()
+            .enumerate()
+            .then(move |(index, request)| {
+                let frame_token = permit.take_token();
+                let state = state.clone();
+                async move {
+                    let request = request?;
+                    frame_token.map_err(ErrorType::TooManyRequests)?;
+                    let text = recognize(&state, request).await?;
+                    Ok(RecognizeResponse {
+                        text,
+                        frame_id: index as u64 + 1,
+                    })
+                }
+            });
+        Ok(Response::new(Box::pin(responses)))
+    }
+
+    #[tracing::instrument(skip(self, request))]
+    async fn list_languages(
+        &self,
+        request: Request<ListLanguagesRequest>,
+    ) -> Result<Response<ListLanguagesResponse>, Status> {
+        self.authorize(request.metadata(), Scope::LanguagesRead)
+            .await?;
+        let languages = sorted_languages(&self.state.available_tesseract_languages.load())
+            .into_iter()
+            .map(|tesseract_model| TesseractModel {
+                language: tesseract_model.language,
+                model: tesseract_model.model,
+            })
+            .collect();
+        Ok(Response::new(ListLanguagesResponse { languages }))
+    }
+
+    async fn health(
+        &self,
+        _request: Request<HealthRequest>,
+    ) -> Result<Response<HealthResponse>, Status> {
+        let health = check_readiness(&self.state).await;
+        if health.status != HealthStatus::Ok {
+            let failures = health
+                .components
+                .iter()
+                .filter(|component| component.status != HealthStatus::Ok)
+                .map(|component| {
+                    format!(
+                        "{}: {}",
+                        component.name,
+                        component.message.as_deref().unwrap_or_default()
+                    )
+                })
+                .collect::<Vec<_>>();
+            return Err(Status::unavailable(failures.join(", ")));
+        }
+        Ok(Response::new(HealthResponse {
+            status: "ok".to_owned(),
+        }))
+    }
+}
@@ -1,18 +1,21 @@
-use std::collections::HashSet;
-
 use axum::extract::DefaultBodyLimit;
 use opentelemetry::global;
 use utoipa::OpenApi;
 pub mod config;
+pub mod grpc;
 pub mod middleware;
 pub mod models;
 pub mod routes;
 pub mod utils;
 
-use config::app_config::AppConfig;
-use middleware::{security, server};
-use models::languages::TesseractModel;
-use utils::languages::get_available_languages_with_models;
+use config::app_config::{AppConfig, Scope};
+use middleware::{admin, auth, request_id, security, server};
+use utils::cache::ResultCache;
+use utils::health::DefaultModelCheck;
+use utils::jobs::JobStore;
+use utils::jwt::JwtValidator;
+use utils::languages::{AvailableLanguages, get_available_languages_with_models};
+use utils::rate_limit::RateLimiter;
 use utoipa_axum::router::OpenApiRouter;
 use utoipa_scalar::{Scalar, Servable as _};
 
@@ -20,7 +23,19 @@ use utoipa_scalar::{Scalar, Servable as _};
 #[non_exhaustive]
 pub struct AppState {
     pub app_config: AppConfig,
-    pub available_tesseract_languages: HashSet<TesseractModel>,
+    /// The languages found in the tesseract data path, reloaded when it changes.
+    pub available_tesseract_languages: AvailableLanguages,
+    /// Whether the default model loads, checked again when the languages are rescanned.
+    pub default_model_check: DefaultModelCheck,
+    pub jobs: JobStore,
+    /// Cache of OCR results, when enabled in `CacheConfig`.
+    pub result_cache: Option<ResultCache>,
+    /// Registry served on `/system/metrics`, when metrics are exported to Prometheus.
+    pub prometheus_registry: Option<prometheus::Registry>,
+    /// Validates JWT bearer tokens, when a JWKS is configured.
+    pub jwt_validator: Option<JwtValidator>,
+    /// Quotas of the recognitions of each client, when enabled in `RateLimitConfig`.
+    pub rate_limiter: Option<RateLimiter>,
 }
 
 #[derive(OpenApi)]
@@ -30,27 +45,86 @@ pub struct AppState {
         description = "API documentation for OCR Service",
     ),
     tags(
+        (name = "admin", description = "Admin API"),
         (name = "health", description = "Health API"),
         (name = "images", description = "Images API"),
+        (name = "jobs", description = "Jobs API"),
         (name = "languages", description = "Languages API"),
+        (name = "metrics", description = "Metrics API"),
     )
 )]
 struct ApiDoc;
 
+impl AppState {
+    /// Create the state shared by the REST and gRPC APIs.
+    pub fn new(app_config: AppConfig) -> Self {
+        let available_tesseract_languages = AvailableLanguages::new(
+            get_available_languages_with_models(&app_config)
+                .expect("Failed to get available Tesseract languages"),
+        );
+
+        let result_cache = app_config.cache.enabled.then(|| {
+            ResultCache::new(&app_config.cache).expect("Failed to create the OCR result cache")
+        });
+
+        let jwt_validator = JwtValidator::new(&app_config.security.jwt);
+        let rate_limiter = RateLimiter::new(&app_config.rate_limit);
+        let jobs = JobStore::new(&app_config.jobs);
+
+        Self {
+            app_config,
+            available_tesseract_languages,
+            default_model_check: DefaultModelCheck::default(),
+            jobs,
+            result_cache,
+            prometheus_registry: None,
+            jwt_validator,
+            rate_limiter,
+        }
+    }
+}
+
 pub fn router(app_config: AppConfig) -> axum::Router {
-    let available_tesseract_languages = get_available_languages_with_models(&app_config)
-        .expect("Failed to get available Tesseract languages");
+    router_with_state(AppState::new(app_config))
+}
 
-    let app_state = AppState {
-        app_config,
-        available_tesseract_languages,
+pub fn router_with_state(app_state: AppState) -> axum::Router {
+    // The API requires an API key or a JWT with the scope of each route, when either is
+    // configured.
+    let api_key = |scope| {
+        axum::middleware::from_fn_with_state((app_state.clone(), scope), auth::require_scope)
     };
+    let mut api_router = OpenApiRouter::new()
+        .nest(
+            "/api",
+            routes::ImagesApi::router().layer(api_key(Scope::Ocr)),
+        )
+        .nest("/api", routes::JobsApi::router().layer(api_key(Scope::Ocr)))
+        .nest(
+            "/api",
+            routes::LanguagesApi::router().layer(api_key(Scope::LanguagesRead)),
+        )
+        .nest("/system", routes::HealthApi::router())
+        .nest("/system", routes::MetricsApi::router());
+    if app_state.app_config.server.file_upload_max_size_enabled {
+        api_router = api_router.layer(server::body_limit_layer(&app_state.app_config.server));
+    }
+
+    // The admin API requires the admin token (or an admin API key) and accepts models, which are larger than images.
+    let mut admin_router = OpenApiRouter::new().nest("/api", routes::AdminApi::router());
+    if app_state.app_config.server.file_upload_max_size_enabled {
+        admin_router =
+            admin_router.layer(server::model_body_limit_layer(&app_state.app_config.server));
+    }
+    let admin_router = admin_router.layer(axum::middleware::from_fn_with_state(
+        app_state.clone(),
+        admin::admin_token,
+    ));
 
     // Create the router with the routes and the OpenAPI documentation.
     let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .nest("/api", routes::ImagesApi::router())
-        .nest("/api", routes::LanguagesApi::router())
-        .nest("/system", routes::HealthApi::router())
+        .merge(api_router)
+        .merge(admin_router)
         .split_for_parts();
 
     // Use `leak()` because the meter provider wants a static string (&str) but the service name is from an env variable.
@@ -62,26 +136,15 @@ pub fn router(app_config: AppConfig) -> axum::Router {
         .build()
         .unwrap();
 
-    let body_limit_layer = if app_state.app_config.server.file_upload_max_size_enabled {
-        Some(server::body_limit_layer(&app_state.app_config.server))
-    } else {
-        None
-    };
-
     // Combine all the routes and apply the middleware layers.
     // The order of the layers is important. The first layer is the outermost layer.
-    let mut router = router
+    router
         .merge(Scalar::with_url("/api-docs", api))
         .layer(security::cors_layer(&app_state.app_config.security))
-        .layer(DefaultBodyLimit::disable());
-
-    if let Some(limit_layer) = body_limit_layer {
-        router = router.layer(limit_layer);
-    }
-
-    router
+        .layer(DefaultBodyLimit::disable())
         .layer(server::normalize_path_layer())
         .layer(server::timeout_layer(&app_state.app_config.server))
         .layer(otel_metrics_layer)
+        .layer(axum::middleware::from_fn(request_id::request_id))
         .with_state(app_state)
 }
@@ -1,32 +1,344 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
 use axum::BoxError;
-use ocr_service::config::app_config::app_config;
-use ocr_service::utils::telemetry::initialize_opentelemetry_providers;
+use clap::{Parser, Subcommand};
+use ocr_service::AppState;
+use ocr_service::config::app_config::AppConfig;
+use ocr_service::grpc::grpc_service;
+use ocr_service::models::{
+    error::ErrorType, images::ImagesQueryParams, languages::LanguagesResponse,
+};
+use ocr_service::utils::{
+    health::check_default_model,
+    languages::{get_available_languages_with_models, sorted_languages},
+    model_installer::{DEFAULT_MANIFEST, ModelSource, install_models},
+    telemetry::initialize_opentelemetry_providers,
+    tessdata_watcher::watch_tessdata,
+    validations::validate_language_params,
+};
 use tokio::net::TcpListener;
+use tonic::transport::server::TcpIncoming;
+
+/// The OCR Service: a REST and gRPC API for Tesseract OCR.
+///
+/// The configuration is read from environment variables (and the `.env` file), layered over the
+/// configuration file. Command-line options take precedence over both.
+#[derive(Debug, Parser)]
+#[command(name = "ocr_service", version)]
+struct Cli {
+    /// A TOML or YAML configuration file.
+    #[arg(short, long, global = true, env = "CONFIG_FILE")]
+    config: Option<PathBuf>,
+
+    /// The Tesseract data path. Overrides TESSDATA_PATH.
+    #[arg(long, global = true)]
+    tessdata: Option<String>,
+
+    /// Defaults to `serve`.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Start the REST and gRPC servers.
+    Serve(ServeArgs),
+    /// List the Tesseract languages and models found in the data path.
+    Languages {
+        /// Print the languages as JSON, like `GET /api/v1/languages`.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Validate the configuration and the Tesseract data path without starting the servers.
+    CheckConfig,
+    /// Print the effective configuration as environment variables, with secrets redacted.
+    PrintConfig,
+    /// Install models into the data path from a local mirror directory, an HTTP mirror or a
+    /// tar/zip bundle, checking them against the checksums of its manifest.
+    InstallModels(InstallModelsArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct InstallModelsArgs {
+    /// A directory, an http(s) URL, or a .tar, .tar.gz, .tgz or .zip bundle, laid out like the
+    /// data path.
+    #[arg(long)]
+    from: ModelSource,
+
+    /// The manifest at the root of the source, in the format of `sha256sum`.
+    #[arg(long, default_value = DEFAULT_MANIFEST)]
+    manifest: String,
+
+    /// The languages to install (e.g. `eng` or `de`). Defaults to every model of the manifest.
+    languages: Vec<String>,
+}
+
+#[derive(Debug, Default, clap::Args)]
+struct ServeArgs {
+    /// The host the servers listen on. Overrides SERVER_HOST.
+    #[arg(long)]
+    host: Option<String>,
+
+    /// The port of the REST API. Overrides SERVER_PORT.
+    #[arg(short, long)]
+    port: Option<u16>,
+
+    /// The port of the gRPC API. Overrides SERVER_GRPC_PORT.
+    #[arg(long)]
+    grpc_port: Option<u16>,
+
+    /// Only serve the REST API. Overrides SERVER_GRPC_ENABLED.
+    #[arg(long)]
+    no_grpc: bool,
+}
+
+impl Cli {
+    /// The options that override configuration keys.
+    fn overrides(&self) -> BTreeMap<String, String> {
+        let mut overrides = BTreeMap::new();
+        if let Some(tessdata) = &self.tessdata {
+            overrides.insert("TESSDATA_PATH".to_owned(), tessdata.clone());
+        }
+        if let Some(Command::Serve(serve_args)) = &self.command {
+            if let Some(host) = &serve_args.host {
+                overrides.insert("SERVER_HOST".to_owned(), host.clone());
+            }
+            if let Some(port) = serve_args.port {
+                overrides.insert("SERVER_PORT".to_owned(), port.to_string());
+            }
+            if let Some(grpc_port) = serve_args.grpc_port {
+                overrides.insert("SERVER_GRPC_PORT".to_owned(), grpc_port.to_string());
+            }
+            if serve_args.no_grpc {
+                overrides.insert("SERVER_GRPC_ENABLED".to_owned(), "false".to_owned());
+            }
+        }
+        overrides
+    }
+}
 
 #[tokio::main]
-async fn main() -> Result<(), BoxError> {
+async fn main() -> ExitCode {
     dotenvy::dotenv().ok();
+    let cli = Cli::parse();
 
     tracing::debug!("Initializing server configuration");
-    let app_config = app_config().to_owned();
+    let app_config = match AppConfig::load_with(cli.config.as_deref(), cli.overrides()) {
+        Ok(app_config) => app_config,
+        Err(error) => {
+            eprintln!("{error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match cli.command.unwrap_or(Command::Serve(ServeArgs::default())) {
+        Command::Serve(_) => match serve(app_config).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{error}");
+                ExitCode::FAILURE
+            }
+        },
+        Command::Languages { json } => languages(&app_config, json),
+        Command::CheckConfig => check_config(&app_config),
+        Command::PrintConfig => {
+            for (key, value) in app_config.entries() {
+                println!("{key}={value}");
+            }
+            ExitCode::SUCCESS
+        }
+        Command::InstallModels(install_args) => {
+            let data_path = app_config.tesseract.data_path.clone();
+            let installed = tokio::task::spawn_blocking(move || {
+                install_models(
+                    &data_path,
+                    &install_args.from,
+                    &install_args.manifest,
+                    &install_args.languages,
+                )
+            })
+            .await;
+            match installed {
+                Ok(Ok(installed)) => {
+                    for tesseract_model in installed {
+                        println!(
+                            "{}",
+                            tesseract_model.full_path.as_deref().unwrap_or_default()
+                        );
+                    }
+                    ExitCode::SUCCESS
+                }
+                Ok(Err(ErrorType::InternalError(error))) => {
+                    eprintln!("{error:#}");
+                    ExitCode::FAILURE
+                }
+                Ok(Err(error)) => {
+                    eprintln!("{error}");
+                    ExitCode::FAILURE
+                }
+                Err(join_error) => {
+                    eprintln!("{join_error}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+    }
+}
 
+async fn serve(app_config: AppConfig) -> Result<(), BoxError> {
     // Initialize the OpenTelemetry Providers and hold the guard to keep them alive.
-    let _guard = initialize_opentelemetry_providers(&app_config).await?;
+    let otel_guard = initialize_opentelemetry_providers(&app_config).await?;
 
-    // Start the server.
+    let mut app_state = AppState::new(app_config.clone());
+    app_state.prometheus_registry = otel_guard.prometheus_registry();
+
+    // Reload the languages when models are added to or removed from the data path, for as long
+    // as the watcher is alive.
+    let _tessdata_watcher = if app_config.tesseract.watch_enabled {
+        watch_tessdata(
+            app_config.clone(),
+            app_state.available_tesseract_languages.clone(),
+        )
+        .inspect_err(|error| tracing::warn!("Failed to watch the Tesseract data path: {error}"))
+        .ok()
+    } else {
+        None
+    };
+
+    // Load the default model once up front; readiness reuses the result until the next rescan.
+    if let Err(error) = check_default_model(&app_state).await {
+        tracing::warn!("The default model failed to load: {error}");
+    }
+
+    // Bind both servers before serving, so a port that is in use fails the startup.
     let server_address = format!("{}:{}", app_config.server.host, app_config.server.port);
-    tracing::info!("Starting server on {}", server_address);
     let listener = TcpListener::bind(&server_address)
         .await
-        .expect("Failed to bind address");
-    let router = ocr_service::router(app_config);
-    axum::serve(listener, router.into_make_service())
-        .with_graceful_shutdown(shutdown_server())
-        .await
-        .expect("Failed to start server");
+        .map_err(|error| format!("Failed to bind the server to {server_address}: {error}"))?;
+    let grpc_listener = if app_config.server.grpc_enabled {
+        let grpc_address = format!("{}:{}", app_config.server.host, app_config.server.grpc_port);
+        let grpc_listener = TcpListener::bind(&grpc_address).await.map_err(|error| {
+            format!("Failed to bind the gRPC server to {grpc_address}: {error}")
+        })?;
+        Some((grpc_address, grpc_listener))
+    } else {
+        None
+    };
+
+    // Start the gRPC server on its own port, sharing the state of the REST API.
+    let grpc_server = match grpc_listener {
+        Some((grpc_address, grpc_listener)) => {
+            tracing::info!("Starting gRPC server on {}", grpc_address);
+            let incoming = TcpIncoming::from_listener(grpc_listener, true, None)?;
+            Some(
+                tonic::transport::Server::builder()
+                    .add_service(grpc_service(app_state.clone()))
+                    .serve_with_incoming_shutdown(incoming, shutdown_server()),
+            )
+        }
+        None => None,
+    };
+
+    // Start the server.
+    tracing::info!("Starting server on {}", server_address);
+    let router = ocr_service::router_with_state(app_state);
+    // The address of the client is needed to rate limit requests without credentials.
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_server());
+
+    // Either server failing stops the process, rather than leaving the other one running alone.
+    match grpc_server {
+        Some(grpc_server) => {
+            tokio::try_join!(async { server.await.map_err(BoxError::from) }, async {
+                grpc_server.await.map_err(BoxError::from)
+            },)?;
+        }
+        None => server.await?,
+    }
     Ok(())
 }
 
+/// Print the languages and models found in the Tesseract data path.
+fn languages(app_config: &AppConfig, json: bool) -> ExitCode {
+    let available_languages = match get_available_languages_with_models(app_config) {
+        Ok(available_languages) => available_languages,
+        Err(error) => {
+            eprintln!(
+                "Failed to read Tesseract languages from '{}': {error}",
+                app_config.tesseract.data_path
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    let languages = sorted_languages(&available_languages);
+    if json {
+        let response = LanguagesResponse::new(languages);
+        match serde_json::to_string_pretty(&response) {
+            Ok(response) => println!("{response}"),
+            Err(error) => {
+                eprintln!("{error}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        for language in languages {
+            println!(
+                "{}\t{}\t{}",
+                language.language,
+                language.model.as_deref().unwrap_or("-"),
+                language.full_path.as_deref().unwrap_or_default()
+            );
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Check that the server could start with this configuration: the Tesseract data path must be
+/// readable and the default language must resolve to a model.
+fn check_config(app_config: &AppConfig) -> ExitCode {
+    let available_languages = match get_available_languages_with_models(app_config) {
+        Ok(available_languages) if !available_languages.is_empty() => available_languages,
+        Ok(_) => {
+            eprintln!(
+                "No Tesseract models found in '{}'",
+                app_config.tesseract.data_path
+            );
+            return ExitCode::FAILURE;
+        }
+        Err(error) => {
+            eprintln!(
+                "Failed to read Tesseract languages from '{}': {error}",
+                app_config.tesseract.data_path
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(error) = validate_language_params(
+        &ImagesQueryParams::new(None, None),
+        &available_languages,
+        &app_config.service.default_language,
+        &app_config.tesseract.model_preferences,
+    ) {
+        eprintln!(
+            "SERVICE_DEFAULT_LANGUAGE={:?}: {error}",
+            app_config.service.default_language
+        );
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "Configuration is valid, {} Tesseract models found in '{}'",
+        available_languages.len(),
+        app_config.tesseract.data_path
+    );
+    ExitCode::SUCCESS
+}
+
 async fn shutdown_server() {
     #[cfg(unix)]
     let sig_term = async {
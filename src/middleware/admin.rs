@@ -0,0 +1,54 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::AppState;
+use crate::config::app_config::Scope;
+use crate::middleware::api_key::{bearer_token, constant_time_eq};
+use crate::middleware::auth::authenticate;
+use crate::models::error::ErrorType;
+
+/// Require the `Authorization: Bearer <token>` header to match `SecurityConfig::admin_token`, or
+/// an API key or JWT with the `admin` scope.
+///
+/// # Errors
+///
+/// - `NotFound`: If neither an admin token, an admin API key nor JWT validation is configured,
+///   so the admin API is disabled.
+/// - `Unauthorized`: If the bearer token is missing or does not match.
+/// - `Forbidden`: If the API key or JWT does not have the `admin` scope.
+pub async fn admin_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ErrorType> {
+    let security = &state.app_config.security;
+    let admin_credentials = state.jwt_validator.is_some()
+        || security
+            .api_keys
+            .iter()
+            .any(|api_key| api_key.scopes.contains(&Scope::Admin));
+    if security.admin_token.is_none() && !admin_credentials {
+        return Err(ErrorType::NotFound(
+            "The admin API is disabled, set SECURITY_ADMIN_TOKEN, an API key with the admin scope \
+             or SECURITY_JWT_JWKS to enable it"
+                .to_owned(),
+        ));
+    }
+    let token = bearer_token(request.headers()).map(str::trim);
+    if let (Some(admin_token), Some(token)) = (&security.admin_token, token) {
+        if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) {
+            return Ok(next.run(request).await);
+        }
+    }
+    if admin_credentials {
+        authenticate(&state, request.headers(), Scope::Admin).await?;
+        return Ok(next.run(request).await);
+    }
+    Err(ErrorType::Unauthorized(match token {
+        Some(_) => "Invalid bearer token".to_owned(),
+        None => "Missing bearer token".to_owned(),
+    }))
+}
@@ -0,0 +1,37 @@
+use axum::http::{HeaderMap, header};
+use sha2::{Digest, Sha256};
+
+use crate::config::app_config::{ApiKey, SecurityConfig};
+
+/// The header the API key is sent in, unless it is sent as a bearer token.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Find the configured API key matching `key`, by its SHA-256 checksum.
+#[must_use]
+pub fn find_api_key<'a>(security: &'a SecurityConfig, key: &str) -> Option<&'a ApiKey> {
+    let sha256 = format!("{:x}", Sha256::digest(key.trim().as_bytes()));
+    security
+        .api_keys
+        .iter()
+        .find(|api_key| constant_time_eq(api_key.sha256.as_bytes(), sha256.as_bytes()))
+}
+
+/// The API key of the `X-API-Key` header.
+pub(crate) fn api_key_header(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// The token of the `Authorization: Bearer <token>` header.
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Compare secrets without leaking the position of the first difference through timing.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
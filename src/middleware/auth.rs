@@ -0,0 +1,141 @@
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts, Request, State},
+    http::{HeaderMap, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::AppState;
+use crate::config::app_config::{ApiKey, Scope, SecurityConfig};
+use crate::middleware::api_key::{api_key_header, bearer_token, find_api_key};
+use crate::models::error::ErrorType;
+
+/// The client of a request, authenticated with an API key or a JWT bearer token.
+///
+/// Handlers extract it to know who sent a request. Extracting `Option<Principal>` also accepts
+/// requests to an open API, when neither API keys nor JWT validation are configured.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Principal {
+    /// The name of the API key, or the subject (`sub`) of the token.
+    pub subject: String,
+    /// The tenant of the token, from `SECURITY_JWT_TENANT_CLAIM`. API keys have no tenant.
+    pub tenant_id: Option<String>,
+    pub scopes: BTreeSet<Scope>,
+}
+
+impl Principal {
+    /// Who owns what the principal creates, e.g. `tenant:acme` for the tokens of a tenant, or
+    /// `subject:ci` for an API key or a token without a tenant.
+    #[must_use]
+    pub fn owner(&self) -> String {
+        match &self.tenant_id {
+            Some(tenant_id) => format!("tenant:{tenant_id}"),
+            None => format!("subject:{}", self.subject),
+        }
+    }
+}
+
+impl From<&ApiKey> for Principal {
+    fn from(api_key: &ApiKey) -> Self {
+        Self {
+            subject: api_key.name.clone(),
+            tenant_id: None,
+            scopes: api_key.scopes.clone(),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = ErrorType;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Principal>()
+            .cloned()
+            .ok_or_else(|| ErrorType::Unauthorized("The request is not authenticated".to_owned()))
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for Principal {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<Principal>().cloned())
+    }
+}
+
+/// Require an API key or a JWT bearer token with `scope`, when either is configured.
+///
+/// The `Principal` is added to the extensions of the request, for the handlers.
+///
+/// # Errors
+///
+/// - `Unauthorized`: If the credentials are missing or invalid.
+/// - `Forbidden`: If the credentials do not have the scope.
+pub async fn require_scope(
+    State((state, scope)): State<(AppState, Scope)>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ErrorType> {
+    if let Some(principal) = authenticate(&state, request.headers(), scope).await? {
+        request.extensions_mut().insert(principal);
+    }
+    Ok(next.run(request).await)
+}
+
+/// Authenticate a request with the API key of the `X-API-Key` header, or with a bearer token
+/// that is either a JWT or an API key, and check that it has `scope`.
+///
+/// Returns `None` when neither API keys nor JWT validation are configured, so the API is open.
+///
+/// # Errors
+///
+/// - `Unauthorized`: If the credentials are missing or invalid.
+/// - `Forbidden`: If the credentials do not have the scope.
+pub async fn authenticate(
+    state: &AppState,
+    headers: &HeaderMap,
+    scope: Scope,
+) -> Result<Option<Principal>, ErrorType> {
+    let security = &state.app_config.security;
+    if security.api_keys.is_empty() && state.jwt_validator.is_none() {
+        return Ok(None);
+    }
+    let principal = match (api_key_header(headers), bearer_token(headers)) {
+        (Some(key), _) => api_key_principal(security, key)?,
+        (None, Some(token)) => match &state.jwt_validator {
+            // A JWT has three dot-separated parts, unlike API keys.
+            Some(jwt_validator) if token.split('.').count() == 3 => {
+                jwt_validator.validate(token.trim()).await?
+            }
+            _ => api_key_principal(security, token)?,
+        },
+        (None, None) => {
+            return Err(ErrorType::Unauthorized(
+                "Missing credentials, send an API key in the X-API-Key header or a bearer token"
+                    .to_owned(),
+            ));
+        }
+    };
+    if !principal.scopes.contains(&scope) {
+        return Err(ErrorType::Forbidden(format!(
+            "'{}' does not have the '{scope}' scope",
+            principal.subject
+        )));
+    }
+    Ok(Some(principal))
+}
+
+fn api_key_principal(security: &SecurityConfig, key: &str) -> Result<Principal, ErrorType> {
+    find_api_key(security, key)
+        .map(Principal::from)
+        .ok_or_else(|| ErrorType::Unauthorized("Invalid API key".to_owned()))
+}
@@ -1,2 +1,7 @@
+pub mod admin;
+pub mod api_key;
+pub mod auth;
+pub mod rate_limit;
+pub mod request_id;
 pub mod security;
 pub mod server;
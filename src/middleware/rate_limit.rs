@@ -0,0 +1,133 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{HeaderMap, request::Parts},
+};
+
+use crate::AppState;
+use crate::config::app_config::{RateLimitConfig, RateLimitKey};
+use crate::middleware::auth::Principal;
+use crate::models::error::ErrorType;
+use crate::utils::rate_limit::RecognitionPermit;
+
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Take a token from the bucket of the client of the request and count the recognition against
+/// its concurrent recognitions, for as long as the handler keeps the permit.
+impl FromRequestParts<AppState> for RecognitionPermit {
+    type Rejection = ErrorType;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        recognition_permit(
+            state,
+            parts.extensions.get::<Principal>(),
+            &parts.headers,
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|connect_info| connect_info.0),
+        )
+    }
+}
+
+/// Take a recognition permit for the client of a request, if rate limiting is enabled.
+///
+/// # Errors
+///
+/// - `TooManyRequests`: If the client exceeded its rate limit or concurrent recognitions.
+pub fn recognition_permit(
+    state: &AppState,
+    principal: Option<&Principal>,
+    headers: &HeaderMap,
+    remote_address: Option<SocketAddr>,
+) -> Result<RecognitionPermit, ErrorType> {
+    let Some(rate_limiter) = &state.rate_limiter else {
+        return Ok(RecognitionPermit::unlimited());
+    };
+    let client = rate_limit_client(
+        &state.app_config.rate_limit,
+        principal,
+        headers,
+        remote_address,
+    );
+    rate_limiter.acquire(&client).map_err(|rate_limited| {
+        tracing::warn!("Rate limited {client}: {}", rate_limited.message);
+        ErrorType::TooManyRequests(rate_limited)
+    })
+}
+
+/// The client the quotas of a request are counted against, e.g. `tenant:acme`, `subject:ci` or
+/// `ip:10.0.0.1`.
+#[must_use]
+pub fn rate_limit_client(
+    config: &RateLimitConfig,
+    principal: Option<&Principal>,
+    headers: &HeaderMap,
+    remote_address: Option<SocketAddr>,
+) -> String {
+    if let (RateLimitKey::Client, Some(principal)) = (config.key, principal) {
+        return principal.owner();
+    }
+    let forwarded_for = headers
+        .get(FORWARDED_FOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(str::trim)
+        .filter(|ip| config.trust_forwarded_for && !ip.is_empty());
+    match (forwarded_for, remote_address) {
+        (Some(ip), _) => format!("ip:{ip}"),
+        (None, Some(remote_address)) => format!("ip:{}", remote_address.ip()),
+        (None, None) => "ip:unknown".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use axum::http::HeaderMap;
+
+    use crate::config::app_config::{RateLimitConfig, RateLimitKey};
+    use crate::middleware::auth::Principal;
+    use crate::middleware::rate_limit::rate_limit_client;
+
+    #[test]
+    fn test_rate_limit_client() {
+        let mut config = RateLimitConfig {
+            enabled: true,
+            requests_per_minute: 60,
+            burst: 10,
+            max_concurrent: 2,
+            key: RateLimitKey::Client,
+            trust_forwarded_for: false,
+        };
+        let mut principal = Principal {
+            subject: "ci".to_owned(),
+            tenant_id: None,
+            scopes: BTreeSet::new(),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "10.0.0.1, 10.0.0.2".parse().unwrap());
+        let remote_address = Some("192.168.0.1:443".parse().unwrap());
+
+        let client = |config: &RateLimitConfig, principal: Option<&Principal>| {
+            rate_limit_client(config, principal, &headers, remote_address)
+        };
+        assert_eq!(client(&config, Some(&principal)), "subject:ci");
+        principal.tenant_id = Some("acme".to_owned());
+        assert_eq!(client(&config, Some(&principal)), "tenant:acme");
+        assert_eq!(client(&config, None), "ip:192.168.0.1");
+
+        config.key = RateLimitKey::Ip;
+        config.trust_forwarded_for = true;
+        assert_eq!(client(&config, Some(&principal)), "ip:10.0.0.1");
+        assert_eq!(
+            rate_limit_client(&config, None, &HeaderMap::new(), None),
+            "ip:unknown"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use tracing::Instrument as _;
+use uuid::Uuid;
+
+/// Name of the header carrying the id of a request, in both requests and responses.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longer request ids sent by clients are replaced by a generated one.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// The id of the request being handled, when called while handling a request.
+#[must_use]
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Middleware to give every request an id to correlate client errors with logs and traces.
+///
+/// The id is taken from the `X-Request-Id` header of the request, or generated as a UUIDv7 if
+/// the header is missing or invalid. The request is handled in an `http.request` span with the
+/// id as `request_id` field (and OpenTelemetry attribute), and the id is returned in the
+/// `X-Request-Id` header of the response.
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map_or_else(|| Uuid::now_v7().to_string(), str::to_owned);
+    let header_value =
+        HeaderValue::from_str(&request_id).expect("Request ids are valid header values");
+    request
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, header_value.clone());
+
+    let span = tracing::info_span!(
+        "http.request",
+        request_id = %request_id,
+        http.request.method = %request.method(),
+        url.path = %request.uri().path(),
+    );
+    let mut response = REQUEST_ID
+        .scope(request_id, next.run(request))
+        .instrument(span)
+        .await;
+    response
+        .headers_mut()
+        .insert(REQUEST_ID_HEADER, header_value);
+    response
+}
+
+fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LENGTH
+        && request_id.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::middleware::request_id::is_valid_request_id;
+
+    #[test]
+    fn test_is_valid_request_id() {
+        assert!(is_valid_request_id("0196f4a4-8b5e-7d2c-9b1e-3f2a1c4d5e6f"));
+        assert!(is_valid_request_id("client-42"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("with space"));
+        assert!(!is_valid_request_id(&"a".repeat(129)));
+    }
+}
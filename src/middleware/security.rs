@@ -1,12 +1,108 @@
-use tower_http::cors::{AllowHeaders, Any, CorsLayer};
+use http::{HeaderName, HeaderValue, Method, request::Parts};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer, ExposeHeaders};
 
 use crate::config::app_config::SecurityConfig;
 
-/// Layer to configure CORS / CORS headers.
+/// Layer to configure CORS / CORS headers, from `SecurityConfig::cors`.
 pub fn cors_layer(security_config: &SecurityConfig) -> CorsLayer {
+    let cors = &security_config.cors;
+    let any = |values: &[String]| values.iter().any(|value| value == "*");
+
+    let allowed_origins = cors.allowed_origins.clone();
+    let allow_origin = if any(&allowed_origins) {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::predicate(move |origin: &HeaderValue, _: &Parts| {
+            origin.to_str().is_ok_and(|origin| {
+                let origin = origin.to_ascii_lowercase();
+                allowed_origins
+                    .iter()
+                    .any(|allowed_origin| origin_matches(allowed_origin, &origin))
+            })
+        })
+    };
+    // Mirroring the request allows any method or header, also with credentials.
+    let allow_methods = if any(&cors.allowed_methods) {
+        AllowMethods::mirror_request()
+    } else {
+        AllowMethods::list(
+            cors.allowed_methods
+                .iter()
+                .filter_map(|method| Method::from_bytes(method.as_bytes()).ok()),
+        )
+    };
+    let allow_headers = if any(&cors.allowed_headers) {
+        AllowHeaders::mirror_request()
+    } else {
+        AllowHeaders::list(header_names(&cors.allowed_headers))
+    };
+    let expose_headers = if any(&cors.exposed_headers) {
+        ExposeHeaders::any()
+    } else {
+        ExposeHeaders::list(header_names(&cors.exposed_headers))
+    };
+
     CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(AllowHeaders::mirror_request())
+        .allow_origin(allow_origin)
+        .allow_methods(allow_methods)
+        .allow_headers(allow_headers)
+        .expose_headers(expose_headers)
+        .allow_credentials(cors.allow_credentials)
         .max_age(security_config.max_access_control_age)
 }
+
+fn header_names(names: &[String]) -> Vec<HeaderName> {
+    names
+        .iter()
+        .filter_map(|name| HeaderName::from_bytes(name.as_bytes()).ok())
+        .collect()
+}
+
+/// Whether a lower case `origin` matches an allowed origin, where `https://*.example.com`
+/// matches `https://app.example.com` and `https://eu.app.example.com`, but not
+/// `https://example.com`.
+fn origin_matches(allowed_origin: &str, origin: &str) -> bool {
+    let Some((scheme, domain)) = allowed_origin.split_once("://*.") else {
+        return allowed_origin == origin;
+    };
+    origin
+        .strip_prefix(scheme)
+        .and_then(|origin| origin.strip_prefix("://"))
+        .and_then(|host| host.strip_suffix(domain))
+        .and_then(|subdomain| subdomain.strip_suffix('.'))
+        .is_some_and(|subdomain| {
+            !subdomain.is_empty()
+                && subdomain
+                    .chars()
+                    .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '.')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::middleware::security::origin_matches;
+
+    #[test]
+    fn test_origin_matches() {
+        assert!(origin_matches(
+            "https://app.example.com",
+            "https://app.example.com"
+        ));
+        assert!(!origin_matches(
+            "https://app.example.com",
+            "http://app.example.com"
+        ));
+
+        let wildcard = "https://*.example.com";
+        assert!(origin_matches(wildcard, "https://app.example.com"));
+        assert!(origin_matches(wildcard, "https://eu.app.example.com"));
+        assert!(!origin_matches(wildcard, "https://example.com"));
+        assert!(!origin_matches(wildcard, "https://evilexample.com"));
+        assert!(!origin_matches(
+            wildcard,
+            "https://app.example.com.evil.com"
+        ));
+        assert!(!origin_matches(wildcard, "https://app.example.com:8443"));
+        assert!(!origin_matches(wildcard, "http://app.example.com"));
+    }
+}
@@ -1,3 +1,4 @@
+use http::StatusCode;
 use tower_http::{
     limit::RequestBodyLimitLayer, normalize_path::NormalizePathLayer, timeout::TimeoutLayer,
 };
@@ -9,9 +10,14 @@ pub fn body_limit_layer(server_config: &ServerConfig) -> RequestBodyLimitLayer {
     RequestBodyLimitLayer::new(server_config.file_upload_max_size)
 }
 
+/// Layer to configure the maximum body size for Tesseract model uploads.
+pub fn model_body_limit_layer(server_config: &ServerConfig) -> RequestBodyLimitLayer {
+    RequestBodyLimitLayer::new(server_config.model_upload_max_size)
+}
+
 /// Layer to configure the timeout for requests.
 pub fn timeout_layer(server_config: &ServerConfig) -> TimeoutLayer {
-    TimeoutLayer::new(server_config.timeout)
+    TimeoutLayer::with_status_code(StatusCode::REQUEST_TIMEOUT, server_config.timeout)
 }
 
 /// Layer to normalize URL paths.
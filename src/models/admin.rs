@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::languages::TesseractModel;
+
+/// The slot of a Tesseract model in the data path.
+#[derive(Debug, Deserialize, IntoParams)]
+#[non_exhaustive]
+pub struct ModelQueryParams {
+    /// The language of the model, e.g. "deu". Without a model, the model is stored as
+    /// `<language>.traineddata` at the root of the data path.
+    pub language: String,
+    /// (Optional) The model of the language, e.g. "deu_best". The model is stored as
+    /// `<language>/<model>.traineddata`.
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[allow(unused)]
+#[non_exhaustive]
+pub struct ModelForm {
+    /// The `.traineddata` file of the model.
+    #[schema(format = Binary, content_media_type = "application/octet-stream")]
+    file: String,
+}
+
+/// Models to install from a mirror or a bundle available to the server.
+#[derive(Debug, Deserialize, ToSchema)]
+#[non_exhaustive]
+pub struct InstallModelsRequest {
+    /// A directory, an http(s) URL, or a `.tar`, `.tar.gz`, `.tgz` or `.zip` bundle, laid out
+    /// like the data path. Paths are read on the server, and the source must be one of
+    /// `TESSDATA_INSTALL_SOURCES`.
+    pub source: String,
+    /// (Optional) The manifest at the root of the source, in the format of `sha256sum`.
+    /// Defaults to "SHA256SUMS".
+    pub manifest: Option<String>,
+    /// (Optional) The languages to install, e.g. "eng" or "de". Defaults to every model of the
+    /// manifest.
+    #[serde(default)]
+    pub languages: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[non_exhaustive]
+pub struct InstallModelsResponse {
+    /// The installed models, with their metadata.
+    pub installed: Vec<TesseractModel>,
+}
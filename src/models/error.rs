@@ -3,12 +3,21 @@ use axum::{
     extract::rejection::JsonRejection,
     response::{IntoResponse, Response},
 };
-use hyper::StatusCode;
+use hyper::{
+    StatusCode,
+    header::{self, HeaderValue},
+};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tracing::error;
 use utoipa::ToSchema;
 
+use crate::middleware::request_id::current_request_id;
+use crate::utils::rate_limit::RateLimited;
+
+const RATE_LIMIT_LIMIT: &str = "ratelimit-limit";
+const RATE_LIMIT_REMAINING: &str = "ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "ratelimit-reset";
+
 /// Custom error type for the API.
 /// The `#[from]` attribute allows for easy conversion from other error types.
 #[derive(Error, Debug)]
@@ -22,6 +31,26 @@ pub enum ErrorType {
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
 
+    /// For requests without valid credentials.
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// For credentials that do not give access to the requested resource.
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// For requests over the rate limits of their client.
+    #[error("Too many requests: {}", .0.message)]
+    TooManyRequests(RateLimited),
+
+    /// For requests the service has no capacity for right now.
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    /// For requests that reference a resource that does not exist.
+    #[error("Not found: {0}")]
+    NotFound(String),
+
     /// Converts from any `anyhow::Error`.
     #[error("An internal server error has occurred.")]
     InternalError(#[from] anyhow::Error),
@@ -31,6 +60,9 @@ pub enum ErrorType {
 #[non_exhaustive]
 pub struct ErrorResponse {
     pub message: String,
+    /// The id of the request, also returned in the `X-Request-Id` header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 // The IntoResponse implementation for ApiError logs the error message.
@@ -39,6 +71,10 @@ pub struct ErrorResponse {
 // the message that we log from the API response message.
 impl IntoResponse for ErrorType {
     fn into_response(self) -> Response {
+        let rate_limited = match &self {
+            Self::TooManyRequests(rate_limited) => Some(rate_limited.clone()),
+            _ => None,
+        };
         // Log detailed error for telemetry.
         let (error, status) = match self {
             Self::InvalidJsonBody(err) => (
@@ -54,14 +90,41 @@ impl IntoResponse for ErrorType {
                 StatusCode::BAD_REQUEST,
             ),
             Self::InvalidRequest(err) => (err, StatusCode::BAD_REQUEST),
+            Self::Unauthorized(err) => (err, StatusCode::UNAUTHORIZED),
+            Self::Forbidden(err) => (err, StatusCode::FORBIDDEN),
+            Self::TooManyRequests(rate_limited) => {
+                (rate_limited.message, StatusCode::TOO_MANY_REQUESTS)
+            }
+            Self::ServiceUnavailable(err) => (err, StatusCode::SERVICE_UNAVAILABLE),
+            Self::NotFound(err) => (err, StatusCode::NOT_FOUND),
             Self::InternalError(err) => (err.to_string(), StatusCode::INTERNAL_SERVER_ERROR),
         };
 
         // Log detailed error for telemetry.
         tracing::error!("{}", error);
         // Create a generic response to hide specific implementation details.
-        let error_response = ErrorResponse { message: error };
+        let error_response = ErrorResponse {
+            message: error,
+            request_id: current_request_id(),
+        };
 
-        (status, Json(error_response)).into_response()
+        let mut response = (status, Json(error_response)).into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        if let Some(rate_limited) = rate_limited {
+            let retry_after = rate_limited.retry_after_secs();
+            let headers = response.headers_mut();
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(rate_limited.limit));
+            headers.insert(
+                RATE_LIMIT_REMAINING,
+                HeaderValue::from(rate_limited.remaining),
+            );
+            headers.insert(RATE_LIMIT_RESET, HeaderValue::from(retry_after));
+        }
+        response
     }
 }
@@ -1,17 +1,68 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Error,
+}
+
+/// The result of a single readiness check.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[non_exhaustive]
+pub struct ComponentHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    /// Details of the check, e.g. why it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ComponentHealth {
+    #[must_use]
+    pub fn new(name: &str, result: Result<String, String>) -> Self {
+        let (status, message) = match result {
+            Ok(message) => (HealthStatus::Ok, message),
+            Err(message) => (HealthStatus::Error, message),
+        };
+        Self {
+            name: name.to_owned(),
+            status,
+            message: Some(message),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[non_exhaustive]
 pub struct HealthResponse {
-    pub status: String,
+    pub status: HealthStatus,
+    /// The checks behind the status. Only reported by readiness.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<ComponentHealth>,
 }
 
 impl HealthResponse {
     #[must_use]
-    pub fn new(status: &str) -> Self {
+    pub fn new(status: HealthStatus) -> Self {
         Self {
-            status: status.to_owned(),
+            status,
+            components: Vec::new(),
         }
     }
+
+    /// The status is `ok` only if every component is `ok`.
+    #[must_use]
+    pub fn from_components(components: Vec<ComponentHealth>) -> Self {
+        let status = if components
+            .iter()
+            .all(|component| component.status == HealthStatus::Ok)
+        {
+            HealthStatus::Ok
+        } else {
+            HealthStatus::Error
+        };
+        Self { status, components }
+    }
 }
@@ -25,3 +25,45 @@ pub struct ImagesQueryParams {
     /// (Optional) The model to use for the OCR. Defaults to "eng".
     pub model: Option<String>,
 }
+
+impl ImagesQueryParams {
+    #[must_use]
+    pub fn new(language: Option<String>, model: Option<String>) -> Self {
+        Self { language, model }
+    }
+}
+
+/// A message sent to the client of the image stream WebSocket, one per received frame.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ImagesStreamMessage {
+    /// The text extracted from a frame.
+    Result {
+        /// The id of the frame, counting the binary messages received on the connection from 1.
+        frame_id: u64,
+        /// The text extracted from the frame.
+        text: String,
+    },
+    /// The frame was replaced by a newer one before it could be processed.
+    Dropped {
+        /// The id of the frame that was dropped.
+        frame_id: u64,
+    },
+    /// The frame was not processed because the client exceeded its rate limit.
+    RateLimited {
+        /// The id of the frame that was rejected.
+        frame_id: u64,
+        /// The reason the frame was rejected.
+        message: String,
+        /// Seconds until the next frame can be processed.
+        retry_after: u64,
+    },
+    /// The frame could not be processed.
+    Error {
+        /// The id of the frame that failed.
+        frame_id: u64,
+        /// The reason the frame failed.
+        message: String,
+    },
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[allow(unused)]
+#[non_exhaustive]
+pub struct JobsForm {
+    /// The pages of the document to process, one image file per page in upload order.
+    #[schema(value_type = Vec<String>, format = Binary, content_media_type = "application/octet-stream")]
+    files: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// The job has been accepted but recognition has not started yet.
+    Queued,
+    /// At least one page is being recognized.
+    Running,
+    /// All pages have been recognized.
+    Completed,
+    /// Recognition failed, see `error`.
+    Failed,
+}
+
+/// A snapshot of the state of an OCR job.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[non_exhaustive]
+pub struct JobResponse {
+    /// The unique id of the job.
+    pub id: Uuid,
+    /// The current status of the job.
+    pub status: JobStatus,
+    /// The number of pages in the job.
+    pub pages: usize,
+    /// The number of pages that have been recognized.
+    pub pages_completed: usize,
+    /// The overall progress of the job in percent.
+    pub percent: u8,
+    /// The text extracted from the document, once the job has completed.
+    pub text: Option<String>,
+    /// The reason the job failed, if it did.
+    pub error: Option<String>,
+}
+
+/// Sent as a `progress` event while a page is being recognized.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[non_exhaustive]
+pub struct JobProgressEvent {
+    /// The page being recognized, starting at 1.
+    pub page: usize,
+    /// The number of pages in the job.
+    pub pages: usize,
+    /// The progress of the current page in percent, as reported by Tesseract.
+    pub page_percent: u8,
+    /// The overall progress of the job in percent.
+    pub percent: u8,
+}
+
+/// Sent as a `page` event once a page has been recognized.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[non_exhaustive]
+pub struct JobPageEvent {
+    /// The page that was recognized, starting at 1.
+    pub page: usize,
+    /// The text extracted from the page.
+    pub text: String,
+}
+
+/// Sent as a `result` event once every page has been recognized. This is the last event.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[non_exhaustive]
+pub struct JobResultEvent {
+    /// The text extracted from the document, pages separated by a form feed.
+    pub text: String,
+    /// The number of pages in the job.
+    pub pages: usize,
+}
@@ -1,9 +1,77 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::utils::language_tags::available_aliases;
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct LanguagesResponse {
     pub languages: Vec<TesseractModel>,
+    /// The ISO 639 and BCP-47 tags accepted for each language, e.g. `"deu": ["de", "ger"]`.
+    pub aliases: BTreeMap<String, Vec<String>>,
+    /// The page of the models, when the list is paginated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+}
+
+impl LanguagesResponse {
+    /// List the languages with the aliases of their languages.
+    #[must_use]
+    pub fn new(languages: Vec<TesseractModel>) -> Self {
+        let aliases = available_aliases(&languages);
+        Self {
+            languages,
+            aliases,
+            pagination: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Pagination {
+    /// The page, counting from 1.
+    pub page: usize,
+    /// The maximum number of models of a page.
+    pub per_page: usize,
+    /// The number of models matching the filters, on all pages.
+    pub total: usize,
+}
+
+/// Filters and pagination of the available models. Filters are combined.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[non_exhaustive]
+pub struct LanguagesQueryParams {
+    /// (Optional) Only list the languages written in this ISO 15924 script, e.g. "Latn" or "Hans".
+    pub script: Option<String>,
+    /// (Optional) Only list the models with this name, e.g. "best" (which also matches
+    /// "eng_best"), or "default" for the models without a name.
+    pub model: Option<String>,
+    /// (Optional) Only list the models for vertical text (`true`) or for horizontal text
+    /// (`false`).
+    pub vertical: Option<bool>,
+    /// (Optional) The page to return, counting from 1. Models are not paginated unless `page`
+    /// or `per_page` is given.
+    #[param(minimum = 1)]
+    pub page: Option<usize>,
+    /// (Optional) The number of models of a page. Defaults to 50, at most 500.
+    #[param(minimum = 1, maximum = 500)]
+    pub per_page: Option<usize>,
+}
+
+/// A language with all its models.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[non_exhaustive]
+pub struct LanguageResponse {
+    /// The Tesseract language, e.g. "chi_sim".
+    pub language: String,
+    /// The ISO 15924 script of the language, e.g. "Hans", if known.
+    pub script: Option<String>,
+    /// The ISO 639 and BCP-47 tags accepted for the language.
+    pub aliases: Vec<String>,
+    /// The models of the language, with their metadata.
+    pub models: Vec<TesseractModel>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq, Hash)]
@@ -13,8 +81,51 @@ pub struct TesseractModel {
     pub language: String,
     /// The unique model of the Tesseract language model. Optional if there is only one model for the language.
     pub model: Option<String>,
-    /// The full file path of the Tesseract language model.
+    /// The full file path of the Tesseract language model. Only listed when `SECURITY_EXPOSE_MODEL_PATHS` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub full_path: Option<String>,
-    /// The relative file path of the Tesseract language model without the $TESSDATA_PREFIX or the .traineddata extension.
+    /// The relative file path of the Tesseract language model without the $TESSDATA_PREFIX or the .traineddata extension. Only listed when `SECURITY_EXPOSE_MODEL_PATHS` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub relative_path: Option<String>,
+    /// The size, checksum and components of the `.traineddata` file, if it could be read.
+    #[serde(default, flatten, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ModelMetadata>,
+}
+
+impl TesseractModel {
+    /// The model without the file paths, which reveal the layout of the server's filesystem.
+    #[must_use]
+    pub fn without_paths(self) -> Self {
+        Self {
+            full_path: None,
+            relative_path: None,
+            ..self
+        }
+    }
+}
+
+/// Information read from the `.traineddata` file of a model.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ModelMetadata {
+    /// The size of the `.traineddata` file in bytes.
+    pub size: u64,
+    /// The SHA-256 checksum of the `.traineddata` file, in hexadecimal.
+    pub sha256: String,
+    /// The OCR engine modes supported by the components of the model.
+    pub engine_modes: Vec<EngineMode>,
+    /// The version string of the model, e.g. "4.00.00alpha:eng:synth20170629".
+    pub version: Option<String>,
+}
+
+/// The OCR engines of Tesseract, named after its `--oem` values.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineMode {
+    /// The legacy engine, available with the legacy components.
+    TesseractOnly,
+    /// The LSTM neural network engine, available with the LSTM components.
+    LstmOnly,
+    /// Both engines combined, available with both components.
+    TesseractLstmCombined,
 }
@@ -1,4 +1,6 @@
+pub mod admin;
 pub mod error;
 pub mod health;
 pub mod images;
+pub mod jobs;
 pub mod languages;
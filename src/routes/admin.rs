@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Multipart, Query, State};
+use axum::http::StatusCode;
+
+use crate::AppState;
+use crate::models::admin::{
+    InstallModelsRequest, InstallModelsResponse, ModelForm, ModelQueryParams,
+};
+use crate::models::error::ErrorType;
+use crate::models::languages::{LanguagesResponse, TesseractModel};
+use crate::utils::languages::sorted_languages;
+use crate::utils::model_installer::{self, DEFAULT_MANIFEST, ModelSource};
+use crate::utils::model_store::{self, model_slot};
+
+/// Rescan the tesseract data path and reload the available languages and models.
+///
+/// # Errors
+///
+/// - `InternalError`: If the tesseract data path cannot be read. The languages are kept.
+#[utoipa::path(
+    post,
+    operation_id = "rescan-languages",
+    path = "/v1/admin/languages/rescan",
+    summary = "Rescan the Tesseract data path and reload the available languages and models.",
+    responses(
+        (status = 200, description = "The available languages after the rescan", body = LanguagesResponse, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid admin token, API key or bearer token"),
+        (status = 403, description = "The API key or bearer token does not have the `admin` scope"),
+        (status = 500, description = "The Tesseract data path cannot be read"),
+   ),
+    security(("admin_token" = []), ("api_key" = ["admin"]), ("jwt" = ["admin"])),
+    tag = "admin",
+)]
+#[tracing::instrument(skip(state))]
+pub async fn rescan_languages(
+    State(state): State<AppState>,
+) -> Result<Json<LanguagesResponse>, ErrorType> {
+    let languages = tokio::task::spawn_blocking(move || {
+        state
+            .available_tesseract_languages
+            .rescan(&state.app_config)
+    })
+    .await
+    .map_err(|join_error| ErrorType::InternalError(join_error.into()))?
+    .map_err(|io_error| ErrorType::InternalError(io_error.into()))?;
+
+    Ok(Json(LanguagesResponse::new(sorted_languages(&languages))))
+}
+
+/// Upload a Tesseract model into a language/model slot of the data path.
+///
+/// The model replaces the model already in the slot, once Tesseract has loaded it.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If the slot is invalid, no file is provided or the model cannot be loaded.
+/// - `InternalError`: If the model cannot be written to the data path.
+#[utoipa::path(
+    put,
+    operation_id = "upload-model",
+    path = "/v1/admin/models",
+    request_body(content = inline(ModelForm), content_type = "multipart/form-data"),
+    params(ModelQueryParams),
+    responses(
+        (status = 201, description = "The model was installed", body = TesseractModel, content_type = "application/json"),
+        (status = 200, description = "The model replaced the model in the slot", body = TesseractModel, content_type = "application/json"),
+        (status = 400, description = "The slot is invalid or the file is not a Tesseract model"),
+        (status = 401, description = "Missing or invalid admin token, API key or bearer token"),
+        (status = 403, description = "The API key or bearer token does not have the `admin` scope"),
+   ),
+    security(("admin_token" = []), ("api_key" = ["admin"]), ("jwt" = ["admin"])),
+    tag = "admin",
+)]
+#[tracing::instrument(skip(state, multipart))]
+pub async fn upload_model(
+    State(state): State<AppState>,
+    Query(params): Query<ModelQueryParams>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<TesseractModel>), ErrorType> {
+    let data_path = state.app_config.tesseract.data_path.clone();
+    let tesseract_model = model_slot(&data_path, &params.language, params.model.as_deref())?;
+
+    let field = multipart
+        .next_field()
+        .await
+        .map_err(|multipart_error| ErrorType::InvalidRequest(multipart_error.to_string()))?
+        .ok_or_else(|| ErrorType::InvalidRequest("No model file provided".to_owned()))?;
+    let file_content = field
+        .bytes()
+        .await
+        .map_err(|extract_error| ErrorType::InvalidRequest(extract_error.to_string()))?;
+
+    let (replaced, tesseract_model) = tokio::task::spawn_blocking(move || {
+        let replaced = model_store::install_model(&data_path, &tesseract_model, &file_content)?;
+        // Respond with the model as listed, with the metadata of the uploaded file.
+        let installed_model = rescan(&state)?
+            .iter()
+            .find(|model| model.relative_path == tesseract_model.relative_path)
+            .cloned()
+            .unwrap_or(tesseract_model);
+        Ok::<_, ErrorType>((replaced, installed_model))
+    })
+    .await
+    .map_err(|join_error| ErrorType::InternalError(join_error.into()))??;
+
+    tracing::info!(
+        "{} model {}",
+        if replaced { "Replaced" } else { "Installed" },
+        tesseract_model.relative_path.as_deref().unwrap_or_default()
+    );
+    let status = if replaced {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(tesseract_model)))
+}
+
+/// Install models from a local mirror directory, an HTTP mirror or a tar/zip bundle.
+///
+/// The source must be one of `TESSDATA_INSTALL_SOURCES`. The models are checked against the
+/// checksums of the manifest of the source, and only moved into the data path once all of them
+/// match.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If the source cannot be read, a language is not in the manifest or a
+///   model does not match its checksum.
+/// - `Forbidden`: If the source is not one of `TESSDATA_INSTALL_SOURCES`.
+/// - `InternalError`: If the models cannot be written to the data path.
+#[utoipa::path(
+    post,
+    operation_id = "install-models",
+    path = "/v1/admin/models/install",
+    request_body(content = InstallModelsRequest, content_type = "application/json"),
+    responses(
+        (status = 200, description = "The models were installed", body = InstallModelsResponse, content_type = "application/json"),
+        (status = 400, description = "The source cannot be read or does not match its manifest"),
+        (status = 401, description = "Missing or invalid admin token, API key or bearer token"),
+        (status = 403, description = "The API key or bearer token does not have the `admin` scope, or the source is not one of `TESSDATA_INSTALL_SOURCES`"),
+   ),
+    security(("admin_token" = []), ("api_key" = ["admin"]), ("jwt" = ["admin"])),
+    tag = "admin",
+)]
+#[tracing::instrument(skip(state))]
+pub async fn install_models(
+    State(state): State<AppState>,
+    payload: Result<Json<InstallModelsRequest>, JsonRejection>,
+) -> Result<Json<InstallModelsResponse>, ErrorType> {
+    let Json(request) = payload?;
+    let source: ModelSource = request.source.parse().map_err(ErrorType::InvalidRequest)?;
+    // Arbitrary paths and URLs are left to the `install-models` command.
+    let allowed = state
+        .app_config
+        .tesseract
+        .install_sources
+        .iter()
+        .any(|install_source| install_source.parse::<ModelSource>().as_ref() == Ok(&source));
+    if !allowed {
+        return Err(ErrorType::Forbidden(
+            "The source is not one of TESSDATA_INSTALL_SOURCES".to_owned(),
+        ));
+    }
+    let manifest = request
+        .manifest
+        .unwrap_or_else(|| DEFAULT_MANIFEST.to_owned());
+    let data_path = state.app_config.tesseract.data_path.clone();
+
+    let installed = tokio::task::spawn_blocking(move || {
+        let installed =
+            model_installer::install_models(&data_path, &source, &manifest, &request.languages)?;
+        // Respond with the models as listed, with their metadata.
+        let languages = rescan(&state)?;
+        Ok::<_, ErrorType>(
+            installed
+                .into_iter()
+                .map(|tesseract_model| {
+                    languages
+                        .iter()
+                        .find(|model| model.relative_path == tesseract_model.relative_path)
+                        .cloned()
+                        .unwrap_or(tesseract_model)
+                })
+                .collect(),
+        )
+    })
+    .await
+    .map_err(|join_error| ErrorType::InternalError(join_error.into()))??;
+
+    Ok(Json(InstallModelsResponse { installed }))
+}
+
+/// Delete a Tesseract model from the data path.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If the slot is invalid.
+/// - `NotFound`: If there is no model in the slot.
+/// - `InternalError`: If the model cannot be deleted.
+#[utoipa::path(
+    delete,
+    operation_id = "delete-model",
+    path = "/v1/admin/models",
+    params(ModelQueryParams),
+    responses(
+        (status = 204, description = "The model was deleted"),
+        (status = 401, description = "Missing or invalid admin token, API key or bearer token"),
+        (status = 403, description = "The API key or bearer token does not have the `admin` scope"),
+        (status = 404, description = "There is no model in the slot"),
+   ),
+    security(("admin_token" = []), ("api_key" = ["admin"]), ("jwt" = ["admin"])),
+    tag = "admin",
+)]
+#[tracing::instrument(skip(state))]
+pub async fn delete_model(
+    State(state): State<AppState>,
+    Query(params): Query<ModelQueryParams>,
+) -> Result<StatusCode, ErrorType> {
+    let data_path = state.app_config.tesseract.data_path.clone();
+    let tesseract_model = model_slot(&data_path, &params.language, params.model.as_deref())?;
+
+    tokio::task::spawn_blocking(move || {
+        model_store::delete_model(&data_path, &tesseract_model)?;
+        rescan(&state).map(|_| ())
+    })
+    .await
+    .map_err(|join_error| ErrorType::InternalError(join_error.into()))??;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Update the in-memory languages right away, rather than waiting for the data path watcher.
+fn rescan(state: &AppState) -> Result<Arc<HashSet<TesseractModel>>, ErrorType> {
+    state
+        .available_tesseract_languages
+        .rescan(&state.app_config)
+        .map_err(|io_error| ErrorType::InternalError(io_error.into()))
+}
@@ -1,21 +1,78 @@
-use axum::Json;
+use axum::{Json, extract::State, http::StatusCode};
 
+use crate::AppState;
 use crate::models::error::ErrorType;
-use crate::models::health::HealthResponse;
+use crate::models::health::{HealthResponse, HealthStatus};
+use crate::utils::health::check_readiness;
+
+/// Fetch the liveness of the system.
+#[utoipa::path(
+    get,
+    operation_id = "get-system-health-live",
+    path = "/health/live",
+    summary = "Fetch the liveness of the system",
+    responses(
+        (status = 200, description = "The system is running", body = HealthResponse, content_type = "application/json",
+            example = json!({"status": "ok"})
+        ),
+   ),
+    tag = "health",
+)]
+pub async fn live() -> Result<Json<HealthResponse>, ErrorType> {
+    Ok(Json(HealthResponse::new(HealthStatus::Ok)))
+}
 
 /// Fetch the health of the system.
+///
+/// An alias of `/health/live`, kept for the probes that were configured before readiness was
+/// split from liveness.
 #[utoipa::path(
     get,
     operation_id = "get-system-health",
     path = "/health",
-    summary = "Fetch the health of the system",
+    summary = "Fetch the health of the system (alias of /health/live)",
     responses(
-        (status = 200, description = "The status of the system", body = HealthResponse, content_type = "application/json",
+        (status = 200, description = "The system is running", body = HealthResponse, content_type = "application/json",
             example = json!({"status": "ok"})
         ),
    ),
     tag = "health",
 )]
 pub async fn health() -> Result<Json<HealthResponse>, ErrorType> {
-    Ok(Json(HealthResponse::new("ok")))
+    live().await
+}
+
+/// Fetch the readiness of the system.
+///
+/// The system is ready when the tessdata directory is readable, the default language model
+/// loads, the number of queued or running OCR jobs is below `HEALTH_MAX_QUEUE_DEPTH` and, when
+/// `HEALTH_SELF_TEST_ENABLED` is set, a built-in test image is recognized correctly.
+#[utoipa::path(
+    get,
+    operation_id = "get-system-health-ready",
+    path = "/health/ready",
+    summary = "Fetch the readiness of the system",
+    responses(
+        (status = 200, description = "The system is ready to perform OCR", body = HealthResponse, content_type = "application/json",
+            example = json!({"status": "ok", "components": [
+                {"name": "tessdata", "status": "ok", "message": "'tesseract' is readable"},
+                {"name": "default_model", "status": "ok", "message": "'eng' loaded"},
+                {"name": "queue", "status": "ok", "message": "0 of at most 100 jobs queued or running"}
+            ]})
+        ),
+        (status = 503, description = "The system is not ready, see the failed components", body = HealthResponse, content_type = "application/json"),
+   ),
+    tag = "health",
+)]
+#[tracing::instrument(skip(state))]
+pub async fn ready(State(state): State<AppState>) -> (StatusCode, Json<HealthResponse>) {
+    let health = check_readiness(&state).await;
+    let status_code = match health.status {
+        HealthStatus::Ok => StatusCode::OK,
+        HealthStatus::Error => {
+            tracing::warn!("The system is not ready: {:?}", health.components);
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    };
+    (status_code, Json(health))
 }
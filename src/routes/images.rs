@@ -1,21 +1,31 @@
 use crate::{
     AppState,
     models::{
-        error::ErrorType,
-        images::{ImagesForm, ImagesQueryParams, ImagesResponse},
+        error::{ErrorResponse, ErrorType},
+        images::{ImagesForm, ImagesQueryParams, ImagesResponse, ImagesStreamMessage},
+        languages::TesseractModel,
+    },
+    utils::{
+        cache::{CACHE_HEADER, cache_key},
+        metrics::model_attributes,
+        ocr::{decode_image, recognize_image},
+        rate_limit::RecognitionPermit,
+        validations::{validate_file_type, validate_language_params, with_accepted_language},
     },
-    utils::validations::{validate_file_type, validate_language_params},
 };
 use axum::{
-    extract::{Multipart, Query, State},
-    response::Json,
+    body::Bytes,
+    extract::{
+        Multipart, Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    http::{HeaderMap, HeaderValue},
+    response::{Json, Response},
 };
-use image::ImageReader;
-use std::io::Cursor;
-use std::path::PathBuf;
-use tesseract_rs::TesseractAPI;
-
-const BYTES_PER_PIXEL: u32 = 3;
+use futures_util::{SinkExt as _, StreamExt as _};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, mpsc};
+use tracing::Instrument as _;
 
 /// Perform OCR on an image
 ///
@@ -26,33 +36,50 @@ const BYTES_PER_PIXEL: u32 = 3;
 ///
 /// - `InvalidRequest`: If the the file is not an image or the content type is not supported.
 /// - `InternalError`: If something goes wrong while creating or using the OCR Engine.
+/// - `TooManyRequests`: If the client exceeded its rate limit or concurrent recognitions.
 #[utoipa::path(
     post,
     operation_id = "perform-ocr-on-image",
     path = "/v1/images",
     request_body(content = inline(ImagesForm), content_type = "multipart/form-data"),
-    params(ImagesQueryParams),
+    params(ImagesQueryParams, ("accept-language" = Option<String>, Header, description = "Used to choose the language when no `language` is given, e.g. `de-DE, en;q=0.8`")),
     responses(
         (status = 200, description = "Text extracted from image successfully", body = ImagesResponse, content_type = "application/json",
+            headers(("x-cache" = String, description = "`hit` or `miss`, when the result cache is enabled")),
             example = json!({"text": "The text that was extracted from your image!"})
         ),
+        (status = 401, description = "Missing or invalid API key or bearer token", body = ErrorResponse, content_type = "application/json"),
+        (status = 403, description = "The API key or bearer token does not have the `ocr` scope", body = ErrorResponse, content_type = "application/json"),
+        (status = 429, description = "The client exceeded its rate limit or concurrent recognitions", body = ErrorResponse, content_type = "application/json", headers(
+            ("retry-after" = u64, description = "Seconds until the request can be retried"),
+            ("ratelimit-limit" = u64, description = "The requests allowed in a burst, or the concurrent recognitions allowed"),
+            ("ratelimit-remaining" = u64, description = "The requests remaining"),
+            ("ratelimit-reset" = u64, description = "Seconds until the quota is available again"),
+        )),
    ),
+    security((), ("api_key" = ["ocr"]), ("jwt" = ["ocr"])),
     tag = "images",
 )]
-#[tracing::instrument(skip(state))]
+#[tracing::instrument(skip(state, _permit, request_headers))]
 pub async fn images(
     State(state): State<AppState>,
+    // Counts against the quotas of the client until the response is sent.
+    _permit: RecognitionPermit,
     Query(params): Query<ImagesQueryParams>,
+    request_headers: HeaderMap,
     mut multipart: Multipart,
-) -> Result<Json<ImagesResponse>, ErrorType> {
+) -> Result<(HeaderMap, Json<ImagesResponse>), ErrorType> {
     tracing::debug!("Request received to perform OCR on image: {:?}", params);
     let default_language = state.app_config.service.default_language.to_owned();
+    let available_languages = state.available_tesseract_languages.load();
+    let params = with_accepted_language(params, &request_headers, &available_languages);
 
     // Validate language parameters and get appropriate TesseractModel
     let tesseract_model = validate_language_params(
         &params,
-        &state.available_tesseract_languages,
+        &available_languages,
         &default_language,
+        &state.app_config.tesseract.model_preferences,
     )?;
 
     // Log which language we're using
@@ -84,72 +111,229 @@ pub async fn images(
         .await
         .map_err(|extract_error| ErrorType::InvalidRequest(extract_error.to_string()))?;
 
-    // Instantiate the Tesseract API
-    let tesseract_api = TesseractAPI::new();
-
-    // Get the $TESSDATA_PATH environment variable stored in the AppConfig
-    let resource_path = PathBuf::from(&state.app_config.tesseract.data_path);
-
     tracing::debug!(
         "Using language {} and resource path {}",
         tesseract_model.language,
-        resource_path.to_str().unwrap_or_default()
+        state.app_config.tesseract.data_path
     );
 
-    let img = ImageReader::new(Cursor::new(file_content))
-        .with_guessed_format()
-        .map_err(|error| ErrorType::InvalidRequest(error.to_string()))?
-        .decode()
-        .map_err(|image_error| ErrorType::InvalidRequest(image_error.to_string()))?;
-
-    // Convert the image to RGB8 and gather image dimensions for Tesseract
-    let rgb_image = img.to_rgb8();
-    let (width, height) = rgb_image.dimensions();
-    let bytes_per_line = (width * BYTES_PER_PIXEL).try_into().map_err(|error| {
-        ErrorType::InvalidRequest(format!("Image dimensions are too large: {error}"))
-    })?;
-    let raw_image_data = rgb_image.into_raw();
-    let language_model_path = tesseract_model.relative_path.unwrap_or_default();
+    let mut headers = HeaderMap::new();
+    let cached_result = state
+        .result_cache
+        .as_ref()
+        .map(|cache| (cache, cache_key(&file_content, &tesseract_model)));
+    if let Some((cache, key)) = &cached_result {
+        if let Some(text) = cache.get(key).await {
+            tracing::debug!("Using cached OCR result {}", key);
+            headers.insert(CACHE_HEADER, HeaderValue::from_static("hit"));
+            return Ok((headers, Json(ImagesResponse { text })));
+        }
+        headers.insert(CACHE_HEADER, HeaderValue::from_static("miss"));
+    }
+
+    // Decoding and recognition block, so keep them off the async executor.
+    let data_path = state.app_config.tesseract.data_path.clone();
+    let span = tracing::Span::current();
+    let text = tokio::task::spawn_blocking(move || {
+        let _guard = span.enter();
+        let img = decode_image(&file_content, &model_attributes(&tesseract_model))?;
+        recognize_image(&data_path, &tesseract_model, &img, None)
+    })
+    .await
+    .map_err(|join_error| {
+        ErrorType::InternalError(anyhow::anyhow!("Recognition task failed: {join_error}"))
+    })??;
 
-    tracing::debug!(
-        "Initializing Tesseract API with path: {} and language: {}",
-        resource_path.to_str().unwrap_or_default(),
-        language_model_path
-    );
-    tesseract_api
-        .init(
-            resource_path.to_str().unwrap_or_default(),
-            language_model_path.as_str(),
-        )
-        .map_err(|tess_error| {
-            ErrorType::InternalError(anyhow::anyhow!(
-                "Something went wrong while performing OCR: {tess_error}"
-            ))
-        })?;
-
-    tesseract_api
-        .set_image(
-            &raw_image_data,
-            width.try_into().map_err(|error| {
-                ErrorType::InvalidRequest(format!("Image width is too large: {error}"))
-            })?,
-            height.try_into().map_err(|error| {
-                ErrorType::InvalidRequest(format!("Image height is too large: {error}"))
-            })?,
-            BYTES_PER_PIXEL.try_into().unwrap(),
-            bytes_per_line,
-        )
-        .map_err(|tess_error| {
-            ErrorType::InternalError(anyhow::anyhow!(
-                "Something went wrong while processing the image: {tess_error}"
-            ))
-        })?;
-
-    let text = tesseract_api.get_utf8_text().map_err(|tess_error| {
-        ErrorType::InvalidRequest(format!(
-            "Something went wrong while extracting the text: {tess_error}"
-        ))
-    })?;
-
-    Ok(Json(ImagesResponse { text }))
+    if let Some((cache, key)) = cached_result {
+        cache.insert(key, text.clone()).await;
+    }
+
+    Ok((headers, Json(ImagesResponse { text })))
+}
+
+/// Perform OCR on a stream of image frames over a WebSocket
+///
+/// Every binary message is an image frame. Frames are numbered from 1 in the order they are
+/// received and answered with a JSON text message carrying the same `frame_id`. Only the newest
+/// frame is kept while a frame is being recognized; older frames are answered with a `dropped`
+/// message instead of being processed. Every frame after the first one takes a token from the
+/// rate limit of the client, and frames beyond it are answered with a `rate_limited` message.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If the language or model parameters are invalid.
+/// - `TooManyRequests`: If the client exceeded its rate limit or concurrent recognitions.
+#[utoipa::path(
+    get,
+    operation_id = "stream-images",
+    path = "/v1/images/stream",
+    params(ImagesQueryParams, ("accept-language" = Option<String>, Header, description = "Used to choose the language when no `language` is given, e.g. `de-DE, en;q=0.8`")),
+    responses(
+        (status = 101, description = "Switching to the WebSocket protocol. Each frame is answered with an `ImagesStreamMessage`"),
+        (status = 400, description = "Invalid language or model parameters"),
+        (status = 401, description = "Missing or invalid API key or bearer token", body = ErrorResponse, content_type = "application/json"),
+        (status = 403, description = "The API key or bearer token does not have the `ocr` scope", body = ErrorResponse, content_type = "application/json"),
+        (status = 429, description = "The client exceeded its rate limit or concurrent recognitions", body = ErrorResponse, content_type = "application/json", headers(
+            ("retry-after" = u64, description = "Seconds until the request can be retried"),
+            ("ratelimit-limit" = u64, description = "The requests allowed in a burst, or the concurrent recognitions allowed"),
+            ("ratelimit-remaining" = u64, description = "The requests remaining"),
+            ("ratelimit-reset" = u64, description = "Seconds until the quota is available again"),
+        )),
+   ),
+    security((), ("api_key" = ["ocr"]), ("jwt" = ["ocr"])),
+    tag = "images",
+)]
+#[tracing::instrument(skip(state, permit, headers, upgrade))]
+pub async fn images_stream(
+    State(state): State<AppState>,
+    permit: RecognitionPermit,
+    Query(params): Query<ImagesQueryParams>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, ErrorType> {
+    tracing::debug!("Request received to stream images: {:?}", params);
+    let default_language = state.app_config.service.default_language.to_owned();
+    let available_languages = state.available_tesseract_languages.load();
+    let params = with_accepted_language(params, &headers, &available_languages);
+
+    // Resolve the model once for the whole connection, before upgrading.
+    let tesseract_model = validate_language_params(
+        &params,
+        &available_languages,
+        &default_language,
+        &state.app_config.tesseract.model_preferences,
+    )?;
+
+    let data_path = state.app_config.tesseract.data_path.clone();
+    // Like uploads to the REST API, frames are only limited when the upload limit is enabled.
+    let server = &state.app_config.server;
+    let max_frame_size = if server.file_upload_max_size_enabled {
+        server.file_upload_max_size
+    } else {
+        usize::MAX
+    };
+    // The connection is handled on a new task, so keep it under the span of this request.
+    let span = tracing::Span::current();
+    Ok(upgrade
+        .max_message_size(max_frame_size)
+        .max_frame_size(max_frame_size)
+        .on_upgrade(move |socket| async move {
+            // Frames are recognized one at a time, so the connection counts as one recognition.
+            handle_images_stream(socket, permit, data_path, tesseract_model)
+                .instrument(span)
+                .await;
+        }))
+}
+
+/// The most recent frame that has not been picked up for recognition yet.
+#[derive(Default)]
+struct LatestFrame {
+    frame: Mutex<Option<(u64, Bytes)>>,
+    notify: Notify,
+}
+
+async fn handle_images_stream(
+    socket: WebSocket,
+    mut permit: RecognitionPermit,
+    data_path: String,
+    tesseract_model: TesseractModel,
+) {
+    let (mut socket_sender, mut socket_receiver) = socket.split();
+    let (message_sender, mut message_receiver) = mpsc::channel::<ImagesStreamMessage>(16);
+    let latest_frame = Arc::new(LatestFrame::default());
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = message_receiver.recv().await {
+            let Ok(json) = serde_json::to_string(&message) else {
+                continue;
+            };
+            if socket_sender
+                .send(Message::Text(json.into()))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let worker = tokio::spawn({
+        let latest_frame = latest_frame.clone();
+        let message_sender = message_sender.clone();
+        async move {
+            loop {
+                latest_frame.notify.notified().await;
+                let Some((frame_id, frame)) = latest_frame.frame.lock().unwrap().take() else {
+                    continue;
+                };
+                let data_path = data_path.clone();
+                let tesseract_model = tesseract_model.clone();
+                let span = tracing::info_span!("ocr.frame", frame_id);
+                let result = tokio::task::spawn_blocking(move || {
+                    let _guard = span.enter();
+                    let img = decode_image(&frame, &model_attributes(&tesseract_model))?;
+                    recognize_image(&data_path, &tesseract_model, &img, None)
+                })
+                .await;
+                let message = match result {
+                    Ok(Ok(text)) => ImagesStreamMessage::Result { frame_id, text },
+                    Ok(Err(error)) => ImagesStreamMessage::Error {
+                        frame_id,
+                        message: error.to_string(),
+                    },
+                    Err(join_error) => ImagesStreamMessage::Error {
+                        frame_id,
+                        message: format!("Recognition task failed: {join_error}"),
+                    },
+                };
+                if message_sender.send(message).await.is_err() {
+                    break;
+                }
+            }
+        }
+        .in_current_span()
+    });
+
+    let mut frame_id: u64 = 0;
+    while let Some(Ok(message)) = socket_receiver.next().await {
+        match message {
+            Message::Binary(frame) => {
+                frame_id += 1;
+                if let Err(rate_limited) = permit.take_token() {
+                    tracing::debug!("Rate limited frame {}: {}", frame_id, rate_limited.message);
+                    let rejected = ImagesStreamMessage::RateLimited {
+                        frame_id,
+                        retry_after: rate_limited.retry_after_secs(),
+                        message: rate_limited.message,
+                    };
+                    if message_sender.send(rejected).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                let stale = latest_frame
+                    .frame
+                    .lock()
+                    .unwrap()
+                    .replace((frame_id, frame));
+                latest_frame.notify.notify_one();
+                if let Some((stale_frame_id, _)) = stale {
+                    tracing::debug!("Dropping stale frame {}", stale_frame_id);
+                    let dropped = ImagesStreamMessage::Dropped {
+                        frame_id: stale_frame_id,
+                    };
+                    if message_sender.send(dropped).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            Message::Close(_) => break,
+            // Text messages are not part of the protocol and pings are answered automatically.
+            _ => {}
+        }
+    }
+
+    worker.abort();
+    drop(message_sender);
+    let _ = writer.await;
 }
@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        Json,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures_util::{Stream, stream};
+use tokio::sync::watch;
+use tracing::Instrument as _;
+use uuid::Uuid;
+
+use crate::{
+    AppState,
+    middleware::auth::Principal,
+    models::{
+        error::{ErrorResponse, ErrorType},
+        images::ImagesQueryParams,
+        jobs::{JobPageEvent, JobProgressEvent, JobResponse, JobResultEvent, JobStatus, JobsForm},
+    },
+    utils::{
+        jobs::JobState,
+        metrics::model_attributes,
+        ocr::decode_image,
+        rate_limit::RecognitionPermit,
+        validations::{validate_file_type, validate_language_params, with_accepted_language},
+    },
+};
+
+/// Start an OCR job for a (multi-page) document
+///
+/// multipart: One image file per page, in page order.
+/// language: (Optional) The language to use for the OCR. Defaults to "eng".
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If no files are given, or a file is not an image or the content type is not supported.
+/// - `ServiceUnavailable`: If `JobsConfig::max_queued` jobs are queued or running.
+/// - `TooManyRequests`: If the client exceeded its rate limit or concurrent recognitions.
+#[utoipa::path(
+    post,
+    operation_id = "create-ocr-job",
+    path = "/v1/jobs",
+    request_body(content = inline(JobsForm), content_type = "multipart/form-data"),
+    params(ImagesQueryParams, ("accept-language" = Option<String>, Header, description = "Used to choose the language when no `language` is given, e.g. `de-DE, en;q=0.8`")),
+    responses(
+        (status = 202, description = "OCR job accepted", body = JobResponse, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid API key or bearer token", body = ErrorResponse, content_type = "application/json"),
+        (status = 403, description = "The API key or bearer token does not have the `ocr` scope", body = ErrorResponse, content_type = "application/json"),
+        (status = 429, description = "The client exceeded its rate limit or concurrent recognitions", body = ErrorResponse, content_type = "application/json", headers(
+            ("retry-after" = u64, description = "Seconds until the request can be retried"),
+            ("ratelimit-limit" = u64, description = "The requests allowed in a burst, or the concurrent recognitions allowed"),
+            ("ratelimit-remaining" = u64, description = "The requests remaining"),
+            ("ratelimit-reset" = u64, description = "Seconds until the quota is available again"),
+        )),
+        (status = 503, description = "Too many OCR jobs are queued or running", body = ErrorResponse, content_type = "application/json"),
+   ),
+    security((), ("api_key" = ["ocr"]), ("jwt" = ["ocr"])),
+    tag = "jobs",
+)]
+#[tracing::instrument(skip(state, principal, permit, headers))]
+pub async fn create_job(
+    State(state): State<AppState>,
+    principal: Option<Principal>,
+    permit: RecognitionPermit,
+    Query(params): Query<ImagesQueryParams>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<JobResponse>), ErrorType> {
+    tracing::debug!("Request received to create OCR job: {:?}", params);
+    let default_language = state.app_config.service.default_language.to_owned();
+    let available_languages = state.available_tesseract_languages.load();
+    let params = with_accepted_language(params, &headers, &available_languages);
+
+    let tesseract_model = validate_language_params(
+        &params,
+        &available_languages,
+        &default_language,
+        &state.app_config.tesseract.model_preferences,
+    )?;
+
+    // Rejected before the pages are read, so a full queue does not buffer more uploads.
+    state.jobs.check_capacity()?;
+    let mut uploads = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|multipart_error| ErrorType::InvalidRequest(multipart_error.to_string()))?
+    {
+        if let Some(content_type) = field.content_type() {
+            validate_file_type(content_type)?;
+        } else {
+            return Err(ErrorType::InvalidRequest(
+                "No content type provided for given file".to_owned(),
+            ));
+        }
+
+        let file_content = field
+            .bytes()
+            .await
+            .map_err(|extract_error| ErrorType::InvalidRequest(extract_error.to_string()))?;
+        uploads.push(file_content);
+    }
+
+    if uploads.is_empty() {
+        return Err(ErrorType::InvalidRequest(
+            "No image file provided".to_owned(),
+        ));
+    }
+
+    // Decoding blocks, so keep it off the async executor like the recognition of the pages.
+    let attributes = model_attributes(&tesseract_model);
+    let span = tracing::Span::current();
+    let pages = tokio::task::spawn_blocking(move || {
+        let _guard = span.enter();
+        uploads
+            .iter()
+            .map(|file_content| decode_image(file_content, &attributes))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .await
+    .map_err(|join_error| {
+        ErrorType::InternalError(anyhow::anyhow!("Decoding task failed: {join_error}"))
+    })??;
+
+    // Only the owner can read the job, see `get_job`.
+    let owner = principal.as_ref().map(Principal::owner);
+    let sender = state.jobs.create(pages.len(), owner)?;
+    let job = JobResponse::from(&*sender.borrow());
+    tracing::info!("Created OCR job {} with {} page(s)", job.id, job.pages);
+
+    tokio::spawn(
+        state
+            .jobs
+            .clone()
+            .run(
+                sender,
+                state.app_config.tesseract.data_path.clone(),
+                tesseract_model,
+                pages,
+                state.app_config.jobs.result_retention,
+                permit,
+            )
+            .in_current_span(),
+    );
+
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// Fetch the current state of an OCR job.
+///
+/// Jobs can only be read by the tenant (or the API key or token subject, without a tenant) that
+/// created them. Other clients get a 404, as if the job did not exist.
+#[utoipa::path(
+    get,
+    operation_id = "get-ocr-job",
+    path = "/v1/jobs/{id}",
+    summary = "Fetch the current state of an OCR job",
+    params(("id" = Uuid, Path, description = "The id of the job")),
+    responses(
+        (status = 200, description = "The state of the job", body = JobResponse, content_type = "application/json"),
+        (status = 404, description = "The job does not exist or belongs to another client", body = ErrorResponse, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid API key or bearer token", body = ErrorResponse, content_type = "application/json"),
+        (status = 403, description = "The API key or bearer token does not have the `ocr` scope", body = ErrorResponse, content_type = "application/json"),
+   ),
+    security((), ("api_key" = ["ocr"]), ("jwt" = ["ocr"])),
+    tag = "jobs",
+)]
+#[tracing::instrument(skip(state, principal))]
+pub async fn get_job(
+    State(state): State<AppState>,
+    principal: Option<Principal>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<JobResponse>, ErrorType> {
+    let owner = principal.as_ref().map(Principal::owner);
+    let job = state
+        .jobs
+        .get(&id, owner.as_deref())
+        .ok_or_else(|| ErrorType::NotFound(format!("Job '{id}' not found")))?;
+    Ok(Json(JobResponse::from(&job)))
+}
+
+/// Stream the progress of an OCR job as Server-Sent Events
+///
+/// Emits `progress` events while a page is recognized, a `page` event when a page is done and
+/// finally either a `result` or an `error` event, after which the stream ends.
+///
+/// # Errors
+///
+/// - `NotFound`: If the job does not exist (anymore), or was created by another client.
+#[utoipa::path(
+    get,
+    operation_id = "stream-ocr-job-events",
+    path = "/v1/jobs/{id}/events",
+    summary = "Stream the progress of an OCR job as Server-Sent Events",
+    params(("id" = Uuid, Path, description = "The id of the job")),
+    responses(
+        (status = 200, description = "A stream of `progress`, `page`, `result` and `error` events", content_type = "text/event-stream"),
+        (status = 404, description = "The job does not exist or belongs to another client", body = ErrorResponse, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid API key or bearer token", body = ErrorResponse, content_type = "application/json"),
+        (status = 403, description = "The API key or bearer token does not have the `ocr` scope", body = ErrorResponse, content_type = "application/json"),
+   ),
+    security((), ("api_key" = ["ocr"]), ("jwt" = ["ocr"])),
+    tag = "jobs",
+)]
+#[tracing::instrument(skip(state, principal))]
+pub async fn job_events(
+    State(state): State<AppState>,
+    principal: Option<Principal>,
+    Path(id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ErrorType> {
+    let owner = principal.as_ref().map(Principal::owner);
+    let receiver = state
+        .jobs
+        .subscribe(&id, owner.as_deref())
+        .ok_or_else(|| ErrorType::NotFound(format!("Job '{id}' not found")))?;
+    Ok(Sse::new(job_event_stream(receiver)).keep_alive(KeepAlive::default()))
+}
+
+/// Turn the state changes of a job into SSE events, ending the stream once the job has finished.
+fn job_event_stream(
+    receiver: watch::Receiver<JobState>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let initial = (receiver, JobEventTracker::default(), VecDeque::new(), false);
+    stream::unfold(
+        initial,
+        |(mut receiver, mut tracker, mut pending, mut finished)| async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    return Some((Ok(event), (receiver, tracker, pending, finished)));
+                }
+                if finished {
+                    return None;
+                }
+                let state = receiver.borrow_and_update().clone();
+                pending.extend(tracker.events(&state));
+                finished = state.is_finished();
+                if pending.is_empty() && !finished && receiver.changed().await.is_err() {
+                    return None;
+                }
+            }
+        },
+    )
+}
+
+/// Remembers what has already been sent to a subscriber so only new information is emitted.
+#[derive(Default)]
+struct JobEventTracker {
+    pages_sent: usize,
+    last_progress: Option<(usize, u8)>,
+}
+
+impl JobEventTracker {
+    fn events(&mut self, state: &JobState) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        for (index, text) in state.page_texts.iter().enumerate().skip(self.pages_sent) {
+            events.push(json_event(
+                "page",
+                &JobPageEvent {
+                    page: index + 1,
+                    text: text.clone(),
+                },
+            ));
+        }
+        self.pages_sent = state.page_texts.len();
+
+        let progress = (state.current_page, state.page_percent);
+        if state.status == JobStatus::Running
+            && state.page_texts.len() < state.current_page
+            && self.last_progress != Some(progress)
+        {
+            self.last_progress = Some(progress);
+            events.push(json_event(
+                "progress",
+                &JobProgressEvent {
+                    page: state.current_page,
+                    pages: state.pages,
+                    page_percent: state.page_percent,
+                    percent: state.percent(),
+                },
+            ));
+        }
+
+        match state.status {
+            JobStatus::Completed => events.push(json_event(
+                "result",
+                &JobResultEvent {
+                    text: state.text(),
+                    pages: state.pages,
+                },
+            )),
+            JobStatus::Failed => events.push(json_event(
+                "error",
+                &ErrorResponse {
+                    message: state.error.clone().unwrap_or_default(),
+                    request_id: None,
+                },
+            )),
+            JobStatus::Queued | JobStatus::Running => {}
+        }
+        events
+    }
+}
+
+fn json_event<T: serde::Serialize>(name: &str, data: &T) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|_| Event::default().event(name))
+}
@@ -1,32 +1,146 @@
 use axum::Json;
-use axum::extract::State;
+use axum::extract::{Path, Query, State};
 
 use crate::AppState;
-use crate::models::error::ErrorType;
-use crate::models::languages::{LanguagesResponse, TesseractModel};
+use crate::models::error::{ErrorResponse, ErrorType};
+use crate::models::languages::{
+    LanguageResponse, LanguagesQueryParams, LanguagesResponse, Pagination, TesseractModel,
+};
+use crate::utils::language_tags::{language_aliases, language_script, resolve_language};
+use crate::utils::languages::{filter_languages, sorted_languages};
+
+/// The number of models of a page when only `page` is given.
+const DEFAULT_PER_PAGE: usize = 50;
+const MAX_PER_PAGE: usize = 500;
 
 /// Fetch all of the available OCR Processing languages and models.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If the pagination parameters are out of range.
 #[utoipa::path(
     get,
     operation_id = "get-available-languages",
     path = "/v1/languages",
     summary = "Fetch all of the available OCR Processing languages and models.",
+    params(LanguagesQueryParams),
     responses(
         (status = 200, description = "The available languages", body = LanguagesResponse, content_type = "application/json",
-            example = json!({"languages": [{"language": "eng", "model": "eng.traineddata"}, {"language": "deu", "model": "deu.traineddata"}]})
+            example = json!({"languages": [{"language": "eng", "model": null, "size": 4113088, "sha256": "7d4322bd2a7749724879683fc3912cb542f19906c83bcc1a52132556427170b2", "engine_modes": ["tesseract_only", "lstm_only", "tesseract_lstm_combined"], "version": "4.00.00alpha:eng:synth20170629:[1,36,0,1Ct3,3,16Mp3,3Lfys64Lfx96Lrx96Lfx512O1c1]"}], "aliases": {"eng": ["en"]}})
         ),
+        (status = 400, description = "Invalid pagination parameters", body = ErrorResponse, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid API key or bearer token", body = ErrorResponse, content_type = "application/json"),
+        (status = 403, description = "The API key or bearer token does not have the `languages:read` scope", body = ErrorResponse, content_type = "application/json"),
    ),
+    security((), ("api_key" = ["languages:read"]), ("jwt" = ["languages:read"])),
     tag = "languages",
 )]
 #[tracing::instrument(skip(state))]
 pub async fn languages(
     State(state): State<AppState>,
+    Query(params): Query<LanguagesQueryParams>,
 ) -> Result<Json<LanguagesResponse>, ErrorType> {
-    let available_languages = state.available_tesseract_languages;
-    // Sort the languages by language name then by model name
-    let mut sorted_languages: Vec<TesseractModel> = available_languages.into_iter().collect();
-    sorted_languages.sort_by(|a, b| a.language.cmp(&b.language).then(a.model.cmp(&b.model)));
-    Ok(Json(LanguagesResponse {
-        languages: sorted_languages,
+    let languages = filter_languages(
+        sorted_languages(&state.available_tesseract_languages.load()),
+        &params,
+    );
+    let (languages, pagination) = paginate(languages, &params)?;
+
+    let mut response = LanguagesResponse::new(visible_models(&state, languages));
+    response.pagination = pagination;
+    Ok(Json(response))
+}
+
+/// Fetch a language with all of its models and their metadata.
+///
+/// # Errors
+///
+/// - `NotFound`: If the language is not available.
+#[utoipa::path(
+    get,
+    operation_id = "get-language",
+    path = "/v1/languages/{language}",
+    summary = "Fetch a language with all of its models.",
+    params(("language" = String, Path, description = "The Tesseract language or one of its aliases, e.g. \"chi_sim\" or \"zh-Hans\"")),
+    responses(
+        (status = 200, description = "The language and its models", body = LanguageResponse, content_type = "application/json",
+            example = json!({"language": "chi_sim", "script": "Hans", "aliases": ["zh", "zh-hans"], "models": [{"language": "chi_sim", "model": "chi_sim", "size": 2475614, "sha256": "b1e9a1a0e3a7f0dd6e8b4f8a2c9e0f5d4c3b2a1908f7e6d5c4b3a29180f7e6d5", "engine_modes": ["lstm_only"], "version": "4.1.0"}]})
+        ),
+        (status = 404, description = "The language is not available", body = ErrorResponse, content_type = "application/json"),
+        (status = 401, description = "Missing or invalid API key or bearer token", body = ErrorResponse, content_type = "application/json"),
+        (status = 403, description = "The API key or bearer token does not have the `languages:read` scope", body = ErrorResponse, content_type = "application/json"),
+   ),
+    security((), ("api_key" = ["languages:read"]), ("jwt" = ["languages:read"])),
+    tag = "languages",
+)]
+#[tracing::instrument(skip(state))]
+pub async fn language(
+    State(state): State<AppState>,
+    Path(language): Path<String>,
+) -> Result<Json<LanguageResponse>, ErrorType> {
+    let available_languages = state.available_tesseract_languages.load();
+    let resolved_language = resolve_language(&language, &available_languages)
+        .ok_or_else(|| ErrorType::NotFound(format!("Language '{language}' is not available")))?;
+
+    let models = sorted_languages(&available_languages)
+        .into_iter()
+        .filter(|tesseract_model| tesseract_model.language == resolved_language)
+        .collect();
+    Ok(Json(LanguageResponse {
+        script: language_script(&resolved_language).map(str::to_owned),
+        aliases: language_aliases(&resolved_language)
+            .iter()
+            .map(|alias| (*alias).to_owned())
+            .collect(),
+        models: visible_models(&state, models),
+        language: resolved_language,
     }))
 }
+
+/// Hide the file paths of the models unless `SECURITY_EXPOSE_MODEL_PATHS` is set.
+fn visible_models(state: &AppState, models: Vec<TesseractModel>) -> Vec<TesseractModel> {
+    if state.app_config.security.expose_model_paths {
+        return models;
+    }
+    models
+        .into_iter()
+        .map(TesseractModel::without_paths)
+        .collect()
+}
+
+/// The requested page of the models, only when `page` or `per_page` is given.
+fn paginate(
+    models: Vec<TesseractModel>,
+    params: &LanguagesQueryParams,
+) -> Result<(Vec<TesseractModel>, Option<Pagination>), ErrorType> {
+    if params.page.is_none() && params.per_page.is_none() {
+        return Ok((models, None));
+    }
+    let page = params.page.unwrap_or(1);
+    let per_page = params.per_page.unwrap_or(DEFAULT_PER_PAGE);
+    if page == 0 {
+        return Err(ErrorType::InvalidRequest(
+            "The page must be at least 1".to_owned(),
+        ));
+    }
+    if !(1..=MAX_PER_PAGE).contains(&per_page) {
+        return Err(ErrorType::InvalidRequest(format!(
+            "The number of models per page must be between 1 and {MAX_PER_PAGE}"
+        )));
+    }
+
+    let total = models.len();
+    let models = models
+        .into_iter()
+        .skip((page - 1).saturating_mul(per_page))
+        .take(per_page)
+        .collect();
+    Ok((
+        models,
+        Some(Pagination {
+            page,
+            per_page,
+            total,
+        }),
+    ))
+}
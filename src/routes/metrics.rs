@@ -0,0 +1,43 @@
+use axum::{
+    extract::State,
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use prometheus::{Encoder as _, TextEncoder};
+
+use crate::AppState;
+use crate::models::error::ErrorType;
+
+/// Fetch the metrics of the service in the Prometheus text format.
+///
+/// # Errors
+///
+/// - `NotFound`: If metrics are not exported to Prometheus (`OTEL_METRICS_EXPORTER`).
+/// - `InternalError`: If the metrics cannot be encoded.
+#[utoipa::path(
+    get,
+    operation_id = "get-system-metrics",
+    path = "/metrics",
+    summary = "Fetch the metrics of the service in the Prometheus text format",
+    responses(
+        (status = 200, description = "The metrics of the service", body = String, content_type = "text/plain; version=0.0.4"),
+        (status = 404, description = "Metrics are not exported to Prometheus"),
+   ),
+    tag = "metrics",
+)]
+pub async fn metrics(State(state): State<AppState>) -> Result<Response, ErrorType> {
+    let registry = state
+        .prometheus_registry
+        .as_ref()
+        .ok_or_else(|| ErrorType::NotFound("Metrics are not exported to Prometheus".to_owned()))?;
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    encoder
+        .encode(&registry.gather(), &mut buffer)
+        .map_err(|encode_error| ErrorType::InternalError(encode_error.into()))?;
+
+    let content_type = HeaderValue::from_str(encoder.format_type())
+        .map_err(|header_error| ErrorType::InternalError(header_error.into()))?;
+    Ok(([(header::CONTENT_TYPE, content_type)], buffer).into_response())
+}
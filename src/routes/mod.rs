@@ -1,41 +1,151 @@
-use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
+pub mod admin;
 pub mod health;
 pub mod images;
+pub mod jobs;
 pub mod languages;
+pub mod metrics;
 
 use crate::{
     AppState,
-    models::{health::HealthResponse, images::ImagesResponse, languages::LanguagesResponse},
+    models::{
+        health::{ComponentHealth, HealthResponse, HealthStatus},
+        images::{ImagesResponse, ImagesStreamMessage},
+        jobs::{JobPageEvent, JobProgressEvent, JobResponse, JobResultEvent},
+        languages::{LanguageResponse, LanguagesResponse, Pagination, TesseractModel},
+    },
 };
 
 #[derive(OpenApi)]
-#[openapi(components(schemas(ImagesResponse)))]
+#[openapi(
+    components(schemas(TesseractModel)),
+    modifiers(&AdminTokenSecurity, &ClientSecurity)
+)]
+pub struct AdminApi;
+
+impl AdminApi {
+    pub fn router() -> OpenApiRouter<AppState> {
+        OpenApiRouter::with_openapi(AdminApi::openapi())
+            .routes(routes!(admin::rescan_languages))
+            .routes(routes!(admin::upload_model, admin::delete_model))
+            .routes(routes!(admin::install_models))
+    }
+}
+
+/// Documents the bearer token required by the admin API.
+struct AdminTokenSecurity;
+
+impl Modify for AdminTokenSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_default()
+            .add_security_scheme(
+                "admin_token",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+    }
+}
+
+/// Documents the API keys and JWT bearer tokens required when `SECURITY_API_KEYS`,
+/// `SECURITY_API_KEYS_FILE` or `SECURITY_JWT_JWKS` is set.
+struct ClientSecurity;
+
+impl Modify for ClientSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_default();
+        components.add_security_scheme(
+            "jwt",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some(
+                        "A JWT signed with a key of the configured JWKS, with the scopes of the \
+                         route in its scopes claim.",
+                    ))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-API-Key",
+                "An API key with the scopes of the route (`ocr`, `languages:read` or \
+                     `admin`), also accepted as a bearer token. Only required when API keys are \
+                     configured.",
+            ))),
+        );
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    components(schemas(ImagesResponse, ImagesStreamMessage)),
+    modifiers(&ClientSecurity)
+)]
 pub struct ImagesApi;
 
 impl ImagesApi {
     pub fn router() -> OpenApiRouter<AppState> {
-        OpenApiRouter::with_openapi(ImagesApi::openapi()).routes(routes!(images::images))
+        OpenApiRouter::with_openapi(ImagesApi::openapi())
+            .routes(routes!(images::images))
+            .routes(routes!(images::images_stream))
     }
 }
 
 #[derive(OpenApi)]
-#[openapi(components(schemas(HealthResponse)))]
+#[openapi(components(schemas(HealthResponse, HealthStatus, ComponentHealth)))]
 pub struct HealthApi;
 
 impl HealthApi {
     pub fn router() -> OpenApiRouter<AppState> {
-        OpenApiRouter::with_openapi(HealthApi::openapi()).routes(routes!(health::health))
+        OpenApiRouter::with_openapi(HealthApi::openapi())
+            .routes(routes!(health::health))
+            .routes(routes!(health::live))
+            .routes(routes!(health::ready))
     }
 }
 
 #[derive(OpenApi)]
-#[openapi(components(schemas(LanguagesResponse)))]
+#[openapi(
+    components(schemas(LanguagesResponse, LanguageResponse, Pagination)),
+    modifiers(&ClientSecurity)
+)]
 pub struct LanguagesApi;
 
 impl LanguagesApi {
     pub fn router() -> OpenApiRouter<AppState> {
-        OpenApiRouter::with_openapi(LanguagesApi::openapi()).routes(routes!(languages::languages))
+        OpenApiRouter::with_openapi(LanguagesApi::openapi())
+            .routes(routes!(languages::languages))
+            .routes(routes!(languages::language))
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    components(schemas(JobResponse, JobProgressEvent, JobPageEvent, JobResultEvent)),
+    modifiers(&ClientSecurity)
+)]
+pub struct JobsApi;
+
+impl JobsApi {
+    pub fn router() -> OpenApiRouter<AppState> {
+        OpenApiRouter::with_openapi(JobsApi::openapi())
+            .routes(routes!(jobs::create_job))
+            .routes(routes!(jobs::get_job))
+            .routes(routes!(jobs::job_events))
+    }
+}
+
+#[derive(OpenApi)]
+pub struct MetricsApi;
+
+impl MetricsApi {
+    pub fn router() -> OpenApiRouter<AppState> {
+        OpenApiRouter::with_openapi(MetricsApi::openapi()).routes(routes!(metrics::metrics))
     }
 }
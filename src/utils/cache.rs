@@ -0,0 +1,381 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+
+use crate::config::app_config::{CacheBackend, CacheConfig};
+use crate::models::languages::TesseractModel;
+
+/// Name of the response header that tells whether a result came from the cache.
+pub const CACHE_HEADER: &str = "x-cache";
+
+/// Compute the cache key of an OCR result.
+///
+/// The key is the SHA-256 of the image bytes and the resolved Tesseract model, so the same image
+/// recognized with different languages or models never shares a result.
+#[must_use]
+pub fn cache_key(image: &[u8], tesseract_model: &TesseractModel) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(image);
+    for part in [
+        Some(tesseract_model.language.as_str()),
+        tesseract_model.model.as_deref(),
+        tesseract_model.relative_path.as_deref(),
+        // Results of a replaced model are not reused.
+        tesseract_model
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.sha256.as_str()),
+    ] {
+        // Length-prefix each part so ("ab", "c") and ("a", "bc") hash differently.
+        let part = part.unwrap_or_default();
+        hasher.update(part.len().to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Cache of OCR results, keyed by `cache_key`.
+#[derive(Clone)]
+pub struct ResultCache {
+    store: CacheStore,
+    ttl: Duration,
+    max_entries: usize,
+}
+
+#[derive(Clone)]
+enum CacheStore {
+    Memory(Arc<Mutex<LruCache<String, CacheEntry>>>),
+    Disk(Arc<DiskStore>),
+}
+
+struct CacheEntry {
+    text: String,
+    inserted_at: Instant,
+}
+
+/// Results stored as files in a directory.
+///
+/// The keys of the files are tracked in memory, so neither lookups of missing results nor inserts
+/// have to list the directory.
+struct DiskStore {
+    directory: PathBuf,
+    /// The keys of the stored results, evicting the oldest first.
+    keys: Mutex<LruCache<String, ()>>,
+}
+
+impl fmt::Debug for ResultCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let backend = match &self.store {
+            CacheStore::Memory(_) => "memory".to_owned(),
+            CacheStore::Disk(disk) => format!("disk ({})", disk.directory.display()),
+        };
+        f.debug_struct("ResultCache")
+            .field("backend", &backend)
+            .field("ttl", &self.ttl)
+            .field("max_entries", &self.max_entries)
+            .finish()
+    }
+}
+
+impl ResultCache {
+    /// Create the cache configured in `CacheConfig`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory of the disk backend cannot be created or read.
+    pub fn new(cache_config: &CacheConfig) -> io::Result<Self> {
+        let max_entries = cache_config.max_entries.max(1);
+        let capacity = NonZeroUsize::new(max_entries).unwrap_or(NonZeroUsize::MIN);
+        let store = match cache_config.backend {
+            CacheBackend::Memory => {
+                CacheStore::Memory(Arc::new(Mutex::new(LruCache::new(capacity))))
+            }
+            CacheBackend::Disk => CacheStore::Disk(Arc::new(DiskStore::open(
+                PathBuf::from(&cache_config.directory),
+                capacity,
+            )?)),
+        };
+        Ok(Self {
+            store,
+            ttl: cache_config.ttl,
+            max_entries,
+        })
+    }
+
+    /// Get a cached result, if there is one that has not expired.
+    pub async fn get(&self, key: &str) -> Option<String> {
+        match &self.store {
+            CacheStore::Memory(entries) => {
+                let mut entries = entries.lock().expect("Result cache lock poisoned");
+                match entries.get(key) {
+                    Some(entry) if entry.inserted_at.elapsed() <= self.ttl => {
+                        Some(entry.text.clone())
+                    }
+                    Some(_) => {
+                        entries.pop(key);
+                        None
+                    }
+                    None => None,
+                }
+            }
+            CacheStore::Disk(disk) => {
+                if !disk.contains(key) {
+                    return None;
+                }
+                let disk = disk.clone();
+                let key = key.to_owned();
+                let ttl = self.ttl;
+                tokio::task::spawn_blocking(move || disk.get(&key, ttl))
+                    .await
+                    .ok()
+                    .flatten()
+            }
+        }
+    }
+
+    /// Store a result, evicting the least recently used (memory) or oldest (disk) results when full.
+    pub async fn insert(&self, key: String, text: String) {
+        match &self.store {
+            CacheStore::Memory(entries) => {
+                entries.lock().expect("Result cache lock poisoned").put(
+                    key,
+                    CacheEntry {
+                        text,
+                        inserted_at: Instant::now(),
+                    },
+                );
+            }
+            CacheStore::Disk(disk) => {
+                let disk = disk.clone();
+                let result = tokio::task::spawn_blocking(move || disk.insert(key, &text)).await;
+                if let Err(err) = result.map_err(io::Error::other).and_then(|result| result) {
+                    tracing::warn!("Failed to write OCR result to the cache: {err}");
+                }
+            }
+        }
+    }
+}
+
+impl DiskStore {
+    /// Open the directory, indexing the results already in it once.
+    fn open(directory: PathBuf, capacity: NonZeroUsize) -> io::Result<Self> {
+        fs::create_dir_all(&directory)?;
+        let mut stored: Vec<(SystemTime, String)> = fs::read_dir(&directory)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().is_none_or(|ext| ext != "txt") {
+                    return None;
+                }
+                let key = path.file_stem()?.to_str()?.to_owned();
+                let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+                Some((modified, key))
+            })
+            .collect();
+        stored.sort();
+
+        let disk = Self {
+            directory,
+            keys: Mutex::new(LruCache::new(capacity)),
+        };
+        for (_, key) in stored {
+            disk.track(key)?;
+        }
+        Ok(disk)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{key}.txt"))
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.keys
+            .lock()
+            .expect("Result cache lock poisoned")
+            .contains(key)
+    }
+
+    fn get(&self, key: &str, ttl: Duration) -> Option<String> {
+        let path = self.path(key);
+        let forget = || {
+            self.keys
+                .lock()
+                .expect("Result cache lock poisoned")
+                .pop(key);
+        };
+        let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) else {
+            // Removed from the directory, so it should not take the place of another result.
+            forget();
+            return None;
+        };
+        if is_expired(modified, ttl) {
+            let _ = fs::remove_file(&path);
+            forget();
+            return None;
+        }
+        fs::read_to_string(&path).ok()
+    }
+
+    fn insert(&self, key: String, text: &str) -> io::Result<()> {
+        // Write to a temporary file first so readers never see a partially written result. The
+        // name is unique, as the same image can be recognized by several requests at once.
+        let temporary_path = self
+            .directory
+            .join(format!("{key}.{}.tmp", uuid::Uuid::now_v7()));
+        if let Err(error) = fs::write(&temporary_path, text)
+            .and_then(|()| fs::rename(&temporary_path, self.path(&key)))
+        {
+            let _ = fs::remove_file(&temporary_path);
+            return Err(error);
+        }
+        self.track(key)
+    }
+
+    /// Remember a stored result, removing the oldest result when the cache is full.
+    fn track(&self, key: String) -> io::Result<()> {
+        let evicted = self
+            .keys
+            .lock()
+            .expect("Result cache lock poisoned")
+            .push(key.clone(), ());
+        match evicted {
+            Some((evicted, ())) if evicted != key => fs::remove_file(self.path(&evicted)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_expired(modified: SystemTime, ttl: Duration) -> bool {
+    modified
+        .elapsed()
+        .map(|elapsed| elapsed > ttl)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::config::app_config::{CacheBackend, CacheConfig};
+    use crate::models::languages::TesseractModel;
+    use crate::utils::cache::{ResultCache, cache_key};
+
+    fn create_test_config(backend: CacheBackend, directory: String) -> CacheConfig {
+        CacheConfig {
+            enabled: true,
+            backend,
+            max_entries: 2,
+            ttl: Duration::from_secs(60),
+            directory,
+        }
+    }
+
+    fn create_test_model(model: Option<&str>) -> TesseractModel {
+        TesseractModel {
+            language: "eng".to_string(),
+            model: model.map(str::to_string),
+            full_path: None,
+            relative_path: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_cache_key_depends_on_image_and_model() {
+        let key = cache_key(b"image", &create_test_model(None));
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, cache_key(b"image", &create_test_model(None)));
+        assert_ne!(key, cache_key(b"other image", &create_test_model(None)));
+        assert_ne!(key, cache_key(b"image", &create_test_model(Some("fast"))));
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_evicts_least_recently_used() {
+        let cache =
+            ResultCache::new(&create_test_config(CacheBackend::Memory, String::new())).unwrap();
+        cache.insert("a".to_string(), "text a".to_string()).await;
+        cache.insert("b".to_string(), "text b".to_string()).await;
+        assert_eq!(cache.get("a").await, Some("text a".to_string()));
+
+        cache.insert("c".to_string(), "text c".to_string()).await;
+        assert_eq!(cache.get("a").await, Some("text a".to_string()));
+        assert_eq!(cache.get("b").await, None);
+        assert_eq!(cache.get("c").await, Some("text c".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_round_trip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache = ResultCache::new(&create_test_config(
+            CacheBackend::Disk,
+            temp_dir.path().to_string_lossy().to_string(),
+        ))
+        .unwrap();
+
+        assert_eq!(cache.get("a").await, None);
+        cache.insert("a".to_string(), "text a".to_string()).await;
+        assert_eq!(cache.get("a").await, Some("text a".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_evicts_oldest() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache_config = create_test_config(
+            CacheBackend::Disk,
+            temp_dir.path().to_string_lossy().to_string(),
+        );
+        let cache = ResultCache::new(&cache_config).unwrap();
+        cache.insert("a".to_string(), "text a".to_string()).await;
+        cache.insert("b".to_string(), "text b".to_string()).await;
+        cache.insert("c".to_string(), "text c".to_string()).await;
+        assert_eq!(cache.get("a").await, None);
+        assert!(!temp_dir.path().join("a.txt").exists());
+
+        // The results already on disk are found again after a restart.
+        let cache = ResultCache::new(&cache_config).unwrap();
+        assert_eq!(cache.get("b").await, Some("text b".to_string()));
+        assert_eq!(cache.get("c").await, Some("text c".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_concurrent_inserts_and_removed_files() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache = ResultCache::new(&create_test_config(
+            CacheBackend::Disk,
+            temp_dir.path().to_string_lossy().to_string(),
+        ))
+        .unwrap();
+
+        cache.insert("b".to_string(), "text b".to_string()).await;
+        // The same image recognized by several requests at once.
+        let inserts = (0..8).map(|_| cache.insert("a".to_string(), "text a".to_string()));
+        futures_util::future::join_all(inserts).await;
+        assert_eq!(cache.get("a").await, Some("text a".to_string()));
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+
+        // A result removed from the directory no longer takes the place of another one.
+        std::fs::remove_file(temp_dir.path().join("a.txt")).unwrap();
+        assert_eq!(cache.get("a").await, None);
+        cache.insert("c".to_string(), "text c".to_string()).await;
+        assert_eq!(cache.get("b").await, Some("text b".to_string()));
+        assert_eq!(cache.get("c").await, Some("text c".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_cache_expired_entries_are_misses() {
+        let mut cache_config = create_test_config(CacheBackend::Memory, String::new());
+        cache_config.ttl = Duration::ZERO;
+        let cache = ResultCache::new(&cache_config).unwrap();
+
+        cache.insert("a".to_string(), "text a".to_string()).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert_eq!(cache.get("a").await, None);
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use crate::AppState;
+use crate::models::{
+    error::ErrorType,
+    health::{ComponentHealth, HealthResponse},
+    images::ImagesQueryParams,
+    languages::TesseractModel,
+};
+use crate::utils::{
+    metrics::model_attributes,
+    ocr::{decode_image, load_model, recognize_image},
+    validations::validate_language_params,
+};
+
+/// A small image with the word "Introduction", recognized by the self-test.
+const SELF_TEST_IMAGE: &[u8] = include_bytes!("../../assets/self-test.png");
+/// The text the self-test expects to find in `SELF_TEST_IMAGE`.
+const SELF_TEST_TEXT: &str = "Introduction";
+/// The language of the text in `SELF_TEST_IMAGE`.
+const SELF_TEST_LANGUAGE: &str = "eng";
+
+/// The result of loading the default model, for the languages it was loaded with.
+///
+/// Loading a model takes long, so readiness probes reuse the result until the languages are
+/// rescanned (which always replaces them), and only load the model again then.
+#[derive(Clone, Debug, Default)]
+pub struct DefaultModelCheck(Arc<Mutex<Option<CheckedModel>>>);
+
+#[derive(Debug)]
+struct CheckedModel {
+    languages: Arc<HashSet<TesseractModel>>,
+    result: Result<String, String>,
+}
+
+/// Check whether the service is ready to perform OCR.
+///
+/// Checks that the tessdata directory is readable, that the default language model loads (see
+/// `check_default_model`) and that the number of queued or running jobs is below
+/// `HealthConfig::max_queue_depth`. When `HealthConfig::self_test_enabled` is set, a built-in
+/// test image is also recognized.
+pub async fn check_readiness(state: &AppState) -> HealthResponse {
+    let mut components = vec![
+        ComponentHealth::new("tessdata", check_tessdata(state)),
+        ComponentHealth::new("default_model", check_default_model(state).await),
+        ComponentHealth::new("queue", check_queue(state)),
+    ];
+    if state.app_config.health.self_test_enabled {
+        components.push(ComponentHealth::new(
+            "self_test",
+            check_self_test(state).await,
+        ));
+    }
+    HealthResponse::from_components(components)
+}
+
+/// Readiness is not authenticated, so the data path itself is not reported, like the paths of
+/// the models unless `SECURITY_EXPOSE_MODEL_PATHS` is set.
+fn check_tessdata(state: &AppState) -> Result<String, String> {
+    fs::read_dir(&state.app_config.tesseract.data_path)
+        .map(|_| "readable".to_owned())
+        .map_err(|error| format!("not readable: {}", error.kind()))
+}
+
+/// Load the default model, unless it was already loaded since the languages were last scanned.
+///
+/// Called at startup, so the first readiness probe does not have to wait for the model.
+pub async fn check_default_model(state: &AppState) -> Result<String, String> {
+    let languages = state.available_tesseract_languages.load();
+    if let Some(checked) = state
+        .default_model_check
+        .0
+        .lock()
+        .expect("Default model check lock poisoned")
+        .as_ref()
+        .filter(|checked| Arc::ptr_eq(&checked.languages, &languages))
+    {
+        return checked.result.clone();
+    }
+
+    let result = load_default_model(state).await;
+    *state
+        .default_model_check
+        .0
+        .lock()
+        .expect("Default model check lock poisoned") = Some(CheckedModel {
+        languages,
+        result: result.clone(),
+    });
+    result
+}
+
+async fn load_default_model(state: &AppState) -> Result<String, String> {
+    let tesseract_model = resolve_model(state, &state.app_config.service.default_language)?;
+    let data_path = state.app_config.tesseract.data_path.clone();
+    tokio::task::spawn_blocking(move || {
+        load_model(&data_path, &tesseract_model)
+            .map(|_| format!("'{}' loaded", describe_model(&tesseract_model)))
+            .map_err(|error| {
+                format!(
+                    "'{}' failed to load: {}",
+                    describe_model(&tesseract_model),
+                    error_message(error)
+                )
+            })
+    })
+    .await
+    .map_err(|join_error| format!("Loading the model failed: {join_error}"))?
+}
+
+fn check_queue(state: &AppState) -> Result<String, String> {
+    let queue_depth = state.jobs.queue_depth();
+    let max_queue_depth = state.app_config.health.max_queue_depth;
+    let message = format!("{queue_depth} of at most {max_queue_depth} jobs queued or running");
+    if queue_depth < max_queue_depth {
+        Ok(message)
+    } else {
+        Err(message)
+    }
+}
+
+/// Skipped without an `eng` model, as the test image is in English, so such deployments can
+/// still become ready.
+async fn check_self_test(state: &AppState) -> Result<String, String> {
+    if !state
+        .available_tesseract_languages
+        .load()
+        .iter()
+        .any(|tesseract_model| tesseract_model.language == SELF_TEST_LANGUAGE)
+    {
+        return Ok(format!("Skipped, no '{SELF_TEST_LANGUAGE}' model"));
+    }
+    let tesseract_model = resolve_model(state, SELF_TEST_LANGUAGE)?;
+    let data_path = state.app_config.tesseract.data_path.clone();
+    let text = tokio::task::spawn_blocking(move || {
+        let img = decode_image(SELF_TEST_IMAGE, &model_attributes(&tesseract_model))?;
+        recognize_image(&data_path, &tesseract_model, &img, None)
+    })
+    .await
+    .map_err(|join_error| format!("Recognizing the test image failed: {join_error}"))?
+    .map_err(|error| {
+        format!(
+            "Recognizing the test image failed: {}",
+            error_message(error)
+        )
+    })?;
+
+    if text.contains(SELF_TEST_TEXT) {
+        Ok(format!("Recognized '{SELF_TEST_TEXT}'"))
+    } else {
+        Err(format!(
+            "Expected '{SELF_TEST_TEXT}' but recognized '{}'",
+            text.trim()
+        ))
+    }
+}
+
+fn resolve_model(state: &AppState, language: &str) -> Result<TesseractModel, String> {
+    validate_language_params(
+        &ImagesQueryParams::new(Some(language.to_owned()), None),
+        &state.available_tesseract_languages.load(),
+        language,
+        &state.app_config.tesseract.model_preferences,
+    )
+    .map_err(|error| error.to_string())
+}
+
+fn describe_model(tesseract_model: &TesseractModel) -> String {
+    match &tesseract_model.model {
+        Some(model) => format!("{} ({model})", tesseract_model.language),
+        None => tesseract_model.language.clone(),
+    }
+}
+
+/// Unlike API errors, health checks should report the details of internal errors.
+fn error_message(error: ErrorType) -> String {
+    match error {
+        ErrorType::InternalError(error) => format!("{error:#}"),
+        error => error.to_string(),
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use image::DynamicImage;
+use tokio::sync::{Semaphore, watch};
+use uuid::Uuid;
+
+use crate::config::app_config::JobsConfig;
+use crate::models::{
+    error::ErrorType,
+    jobs::{JobResponse, JobStatus},
+    languages::TesseractModel,
+};
+use crate::utils::ocr::{ProgressMonitor, recognize_image};
+use crate::utils::rate_limit::RecognitionPermit;
+
+/// How often Tesseract's progress monitor is polled while a page is being recognized.
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The state of a single OCR job, published to subscribers through a `watch` channel.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct JobState {
+    pub id: Uuid,
+    /// The `Principal::owner` that created the job, `None` for anonymous requests.
+    pub owner: Option<String>,
+    pub status: JobStatus,
+    pub pages: usize,
+    /// The page currently being recognized, starting at 1. Zero until recognition starts.
+    pub current_page: usize,
+    /// The progress of the current page in percent.
+    pub page_percent: u8,
+    /// The text of each recognized page, in page order.
+    pub page_texts: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl JobState {
+    fn new(id: Uuid, owner: Option<String>, pages: usize) -> Self {
+        Self {
+            id,
+            owner,
+            status: JobStatus::Queued,
+            pages,
+            current_page: 0,
+            page_percent: 0,
+            page_texts: Vec::with_capacity(pages),
+            error: None,
+        }
+    }
+
+    /// The overall progress of the job in percent.
+    #[must_use]
+    pub fn percent(&self) -> u8 {
+        if self.pages == 0 || self.status == JobStatus::Completed {
+            return 100;
+        }
+        let in_progress = if self.page_texts.len() < self.current_page {
+            usize::from(self.page_percent)
+        } else {
+            0
+        };
+        ((self.page_texts.len() * 100 + in_progress) / self.pages).min(100) as u8
+    }
+
+    /// The text of all recognized pages, separated by a form feed.
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.page_texts.join("\u{c}")
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status, JobStatus::Completed | JobStatus::Failed)
+    }
+}
+
+impl From<&JobState> for JobResponse {
+    fn from(state: &JobState) -> Self {
+        JobResponse {
+            id: state.id,
+            status: state.status,
+            pages: state.pages,
+            pages_completed: state.page_texts.len(),
+            percent: state.percent(),
+            text: (state.status == JobStatus::Completed).then(|| state.text()),
+            error: state.error.clone(),
+        }
+    }
+}
+
+/// In-memory registry of OCR jobs, whose pages are recognized by a bounded pool of workers.
+#[derive(Clone, Debug)]
+pub struct JobStore {
+    jobs: Arc<RwLock<HashMap<Uuid, watch::Sender<JobState>>>>,
+    max_queued: usize,
+    workers: Arc<Semaphore>,
+}
+
+impl JobStore {
+    #[must_use]
+    pub fn new(jobs_config: &JobsConfig) -> Self {
+        Self {
+            jobs: Arc::default(),
+            max_queued: jobs_config.max_queued,
+            workers: Arc::new(Semaphore::new(jobs_config.workers)),
+        }
+    }
+
+    /// Check that a new job would be accepted, before its pages are read.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceUnavailable`: If `JobsConfig::max_queued` jobs are queued or running.
+    pub fn check_capacity(&self) -> Result<(), ErrorType> {
+        self.check_queue_depth(self.queue_depth())
+    }
+
+    /// Register a new job of `owner` with the given number of pages and return its sender.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceUnavailable`: If `JobsConfig::max_queued` jobs are queued or running.
+    pub fn create(
+        &self,
+        pages: usize,
+        owner: Option<String>,
+    ) -> Result<watch::Sender<JobState>, ErrorType> {
+        let mut jobs = self.jobs.write().expect("Job store lock poisoned");
+        // Checked under the write lock, so concurrent requests cannot exceed the limit.
+        self.check_queue_depth(unfinished_jobs(&jobs))?;
+        let id = Uuid::now_v7();
+        let (sender, _) = watch::channel(JobState::new(id, owner, pages));
+        jobs.insert(id, sender.clone());
+        Ok(sender)
+    }
+
+    fn check_queue_depth(&self, queue_depth: usize) -> Result<(), ErrorType> {
+        if queue_depth >= self.max_queued {
+            return Err(ErrorType::ServiceUnavailable(format!(
+                "{queue_depth} OCR jobs are queued or running, try again later"
+            )));
+        }
+        Ok(())
+    }
+
+    /// The current state of a job, if it exists and belongs to `owner`.
+    pub fn get(&self, id: &Uuid, owner: Option<&str>) -> Option<JobState> {
+        self.jobs
+            .read()
+            .expect("Job store lock poisoned")
+            .get(id)
+            .map(|sender| sender.borrow().clone())
+            .filter(|state| state.owner.as_deref() == owner)
+    }
+
+    /// Subscribe to the state changes of a job, if it exists and belongs to `owner`.
+    pub fn subscribe(&self, id: &Uuid, owner: Option<&str>) -> Option<watch::Receiver<JobState>> {
+        self.jobs
+            .read()
+            .expect("Job store lock poisoned")
+            .get(id)
+            .filter(|sender| sender.borrow().owner.as_deref() == owner)
+            .map(watch::Sender::subscribe)
+    }
+
+    /// The number of jobs that are queued or running.
+    pub fn queue_depth(&self) -> usize {
+        unfinished_jobs(&self.jobs.read().expect("Job store lock poisoned"))
+    }
+
+    fn remove(&self, id: &Uuid) {
+        self.jobs
+            .write()
+            .expect("Job store lock poisoned")
+            .remove(id);
+    }
+
+    /// Recognize the pages of a job one after another, publishing progress as it goes. Each page
+    /// waits for a free worker, so the job stays queued until its first page is picked up.
+    ///
+    /// The job is removed from the store `retention` after it has finished. `permit` counts it
+    /// against the quotas of its client until then.
+    #[tracing::instrument(
+        name = "ocr.job",
+        skip_all,
+        fields(
+            job_id = %sender.borrow().id,
+            pages = pages.len(),
+            language = %tesseract_model.language,
+            model = ?tesseract_model.model,
+        )
+    )]
+    pub async fn run(
+        self,
+        sender: watch::Sender<JobState>,
+        data_path: String,
+        tesseract_model: TesseractModel,
+        pages: Vec<DynamicImage>,
+        retention: Duration,
+        permit: RecognitionPermit,
+    ) {
+        let id = sender.borrow().id;
+
+        for (index, page) in pages.into_iter().enumerate() {
+            let worker = self
+                .workers
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("Job workers semaphore closed");
+            sender.send_modify(|state| {
+                state.status = JobStatus::Running;
+                state.current_page = index + 1;
+                state.page_percent = 0;
+            });
+
+            // Without a monitor the page is still recognized, only its progress is not reported.
+            let monitor = ProgressMonitor::new()
+                .inspect_err(|error| tracing::warn!("No progress for OCR job {}: {}", id, error))
+                .ok()
+                .map(Arc::new);
+            let mut recognition = tokio::task::spawn_blocking({
+                let monitor = monitor.clone();
+                let data_path = data_path.clone();
+                let tesseract_model = tesseract_model.clone();
+                let span = tracing::info_span!("ocr.job.page", page = index + 1);
+                move || {
+                    let _guard = span.enter();
+                    let _worker = worker;
+                    recognize_image(&data_path, &tesseract_model, &page, monitor.as_deref())
+                }
+            });
+
+            let mut poll_interval = tokio::time::interval(PROGRESS_POLL_INTERVAL);
+            let result = loop {
+                tokio::select! {
+                    result = &mut recognition => break result,
+                    _ = poll_interval.tick() => {
+                        let progress = monitor.as_ref().map_or(0, |monitor| monitor.progress());
+                        sender.send_if_modified(|state| {
+                            let changed = state.page_percent != progress;
+                            state.page_percent = progress;
+                            changed
+                        });
+                    }
+                }
+            };
+
+            let error = match result {
+                Ok(Ok(text)) => {
+                    sender.send_modify(|state| {
+                        state.page_percent = 100;
+                        state.page_texts.push(text);
+                    });
+                    continue;
+                }
+                Ok(Err(ocr_error)) => ocr_error.to_string(),
+                Err(join_error) => format!("Recognition task failed: {join_error}"),
+            };
+            tracing::error!("OCR job {} failed on page {}: {}", id, index + 1, error);
+            sender.send_modify(|state| {
+                state.status = JobStatus::Failed;
+                state.error = Some(error);
+            });
+            break;
+        }
+
+        sender.send_if_modified(|state| {
+            if state.status != JobStatus::Running {
+                return false;
+            }
+            state.status = JobStatus::Completed;
+            true
+        });
+
+        // The job no longer counts against the quotas of its client while its result is kept.
+        drop(permit);
+        tokio::time::sleep(retention).await;
+        self.remove(&id);
+    }
+}
+
+fn unfinished_jobs(jobs: &HashMap<Uuid, watch::Sender<JobState>>) -> usize {
+    jobs.values()
+        .filter(|sender| !sender.borrow().is_finished())
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use std::time::Duration;
+
+    use crate::config::app_config::JobsConfig;
+    use crate::models::{error::ErrorType, jobs::JobStatus};
+    use crate::utils::jobs::{JobState, JobStore};
+
+    fn create_test_job_store(max_queued: usize) -> JobStore {
+        JobStore::new(&JobsConfig {
+            result_retention: Duration::from_secs(300),
+            max_queued,
+            workers: 1,
+        })
+    }
+
+    #[test]
+    fn test_job_state_percent() {
+        let mut state = JobState::new(Uuid::now_v7(), None, 4);
+        assert_eq!(state.percent(), 0);
+
+        state.status = JobStatus::Running;
+        state.current_page = 1;
+        state.page_percent = 50;
+        assert_eq!(state.percent(), 12);
+
+        state.page_texts.push("page one".to_string());
+        state.current_page = 2;
+        state.page_percent = 0;
+        assert_eq!(state.percent(), 25);
+
+        state
+            .page_texts
+            .extend(["two", "three", "four"].map(String::from));
+        state.status = JobStatus::Completed;
+        assert_eq!(state.percent(), 100);
+        assert_eq!(state.text(), "page one\u{c}two\u{c}three\u{c}four");
+    }
+
+    #[test]
+    fn test_job_store_create_and_get() {
+        let store = create_test_job_store(10);
+        let sender = store.create(2, None).unwrap();
+        let id = sender.borrow().id;
+
+        let job = store.get(&id, None).expect("job should exist");
+        assert_eq!(job.pages, 2);
+        assert_eq!(job.status, JobStatus::Queued);
+        assert!(store.get(&Uuid::now_v7(), None).is_none());
+    }
+
+    #[test]
+    fn test_job_store_only_owner_reads_job() {
+        let store = create_test_job_store(10);
+        let sender = store.create(1, Some("tenant:acme".to_string())).unwrap();
+        let id = sender.borrow().id;
+
+        assert!(store.get(&id, Some("tenant:acme")).is_some());
+        assert!(store.subscribe(&id, Some("tenant:acme")).is_some());
+        for owner in [None, Some("tenant:other"), Some("subject:acme")] {
+            assert!(store.get(&id, owner).is_none(), "{owner:?}");
+            assert!(store.subscribe(&id, owner).is_none(), "{owner:?}");
+        }
+    }
+
+    #[test]
+    fn test_job_store_queue_depth() {
+        let store = create_test_job_store(10);
+        let running = store.create(1, None).unwrap();
+        let finished = store.create(1, None).unwrap();
+        assert_eq!(store.queue_depth(), 2);
+
+        running.send_modify(|state| state.status = JobStatus::Running);
+        finished.send_modify(|state| state.status = JobStatus::Completed);
+        assert_eq!(store.queue_depth(), 1);
+    }
+
+    #[test]
+    fn test_job_store_max_queued() {
+        let store = create_test_job_store(1);
+        let running = store.create(1, None).unwrap();
+        assert!(matches!(
+            store.check_capacity(),
+            Err(ErrorType::ServiceUnavailable(_))
+        ));
+        assert!(matches!(
+            store.create(1, None),
+            Err(ErrorType::ServiceUnavailable(_))
+        ));
+
+        running.send_modify(|state| state.status = JobStatus::Completed);
+        assert!(store.check_capacity().is_ok());
+        assert!(store.create(1, None).is_ok());
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwap;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header, errors::ErrorKind};
+use serde_json::{Map, Value};
+use tokio::sync::Mutex;
+
+use crate::config::app_config::{JwtConfig, Scope};
+use crate::middleware::auth::Principal;
+use crate::models::error::ErrorType;
+
+/// How often at most the JWKS is read again for a token signed with an unknown key, so tokens
+/// with made-up key ids cannot hammer the identity provider.
+const MIN_JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Validates JWT bearer tokens against a JSON Web Key Set, read from a file or a URL.
+///
+/// The JWKS is cached for `JwtConfig::jwks_refresh_interval`, and read again earlier when a token
+/// is signed with a key it does not contain (e.g. after the keys are rotated).
+#[derive(Clone)]
+pub struct JwtValidator {
+    config: JwtConfig,
+    jwks: Arc<ArcSwap<CachedJwks>>,
+    /// Held while the JWKS is read, so concurrent requests read it once.
+    refresh: Arc<Mutex<()>>,
+    http_client: reqwest::Client,
+}
+
+struct CachedJwks {
+    keys: JwkSet,
+    read_at: Option<Instant>,
+}
+
+impl CachedJwks {
+    fn is_older_than(&self, duration: Duration) -> bool {
+        self.read_at
+            .is_none_or(|read_at| read_at.elapsed() >= duration)
+    }
+}
+
+impl std::fmt::Debug for JwtValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtValidator")
+            .field("jwks", &self.config.jwks)
+            .finish_non_exhaustive()
+    }
+}
+
+impl JwtValidator {
+    /// Create a validator, or `None` when JWT validation is disabled. The JWKS is read on first
+    /// use.
+    #[must_use]
+    pub fn new(config: &JwtConfig) -> Option<Self> {
+        config.enabled().then(|| Self {
+            config: config.clone(),
+            jwks: Arc::new(ArcSwap::from_pointee(CachedJwks {
+                keys: JwkSet { keys: Vec::new() },
+                read_at: None,
+            })),
+            refresh: Arc::new(Mutex::new(())),
+            http_client: reqwest::Client::new(),
+        })
+    }
+
+    /// Validate the signature, issuer, audience and expiry of a token, and map its claims.
+    ///
+    /// # Errors
+    ///
+    /// - `Unauthorized`: If the token is invalid, expired or signed with an unknown key.
+    /// - `InternalError`: If the JWKS cannot be read and no keys are cached.
+    pub async fn validate(&self, token: &str) -> Result<Principal, ErrorType> {
+        let header = decode_header(token)
+            .map_err(|_| ErrorType::Unauthorized("Invalid bearer token".to_owned()))?;
+        let jwk = self.key(header.kid.as_deref()).await?;
+        let algorithm = key_algorithm(&jwk).ok_or_else(|| {
+            ErrorType::Unauthorized("The bearer token is signed with an unsupported key".to_owned())
+        })?;
+        if header.alg != algorithm {
+            return Err(ErrorType::Unauthorized(
+                "The bearer token is not signed with the algorithm of its key".to_owned(),
+            ));
+        }
+        let decoding_key = DecodingKey::from_jwk(&jwk)
+            .map_err(|error| ErrorType::InternalError(anyhow::anyhow!("Invalid JWK: {error}")))?;
+
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = self.config.leeway.as_secs();
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        if let Some(issuer) = &self.config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if let Some(audience) = &self.config.audience {
+            validation.set_audience(&[audience]);
+        }
+        let claims = decode::<Map<String, Value>>(token, &decoding_key, &validation)
+            .map_err(|error| {
+                ErrorType::Unauthorized(
+                    match error.kind() {
+                        ErrorKind::ExpiredSignature => "The bearer token has expired",
+                        ErrorKind::ImmatureSignature => "The bearer token is not valid yet",
+                        ErrorKind::InvalidIssuer => "The bearer token has an invalid issuer",
+                        ErrorKind::InvalidAudience => "The bearer token has an invalid audience",
+                        _ => "Invalid bearer token",
+                    }
+                    .to_owned(),
+                )
+            })?
+            .claims;
+        Ok(map_claims(&claims, &self.config))
+    }
+
+    /// The key a token is signed with, by its key id, or the only key of the JWKS.
+    async fn key(&self, key_id: Option<&str>) -> Result<Jwk, ErrorType> {
+        if self
+            .jwks
+            .load()
+            .is_older_than(self.config.jwks_refresh_interval)
+        {
+            self.refresh(self.config.jwks_refresh_interval).await?;
+        }
+        if let Some(jwk) = find_key(&self.jwks.load().keys, key_id) {
+            return Ok(jwk);
+        }
+        // The keys may have been rotated since the JWKS was read.
+        self.refresh(MIN_JWKS_REFRESH_INTERVAL.min(self.config.jwks_refresh_interval))
+            .await?;
+        find_key(&self.jwks.load().keys, key_id).ok_or_else(|| {
+            ErrorType::Unauthorized("The bearer token is signed with an unknown key".to_owned())
+        })
+    }
+
+    /// Read the JWKS again, unless it was read less than `max_age` ago. The cached keys are
+    /// kept when it cannot be read.
+    async fn refresh(&self, max_age: Duration) -> Result<(), ErrorType> {
+        let _refreshing = self.refresh.lock().await;
+        // Another request may have read it while this one waited.
+        if !self.jwks.load().is_older_than(max_age) {
+            return Ok(());
+        }
+        match self.read_jwks().await {
+            Ok(keys) => {
+                self.jwks.store(Arc::new(CachedJwks {
+                    keys,
+                    read_at: Some(Instant::now()),
+                }));
+                Ok(())
+            }
+            Err(error) if self.jwks.load().read_at.is_some() => {
+                tracing::warn!("Failed to refresh the JWKS, keeping the cached keys: {error:#}");
+                Ok(())
+            }
+            Err(error) => Err(ErrorType::InternalError(error)),
+        }
+    }
+
+    async fn read_jwks(&self) -> anyhow::Result<JwkSet> {
+        let source = self.config.jwks.as_deref().unwrap_or_default();
+        let jwks = if source.starts_with("http://") || source.starts_with("https://") {
+            self.http_client
+                .get(source)
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?
+        } else {
+            tokio::fs::read_to_string(source).await?
+        };
+        serde_json::from_str(&jwks)
+            .map_err(|error| anyhow::anyhow!("Invalid JWKS '{source}': {error}"))
+    }
+}
+
+fn find_key(keys: &JwkSet, key_id: Option<&str>) -> Option<Jwk> {
+    match key_id {
+        Some(key_id) => keys.find(key_id),
+        None if keys.keys.len() == 1 => keys.keys.first(),
+        None => None,
+    }
+    .cloned()
+}
+
+/// The algorithm of a key, from its `alg` or its type. The algorithm of the token header is not
+/// trusted, and symmetric keys are rejected because a JWKS is public.
+fn key_algorithm(jwk: &Jwk) -> Option<Algorithm> {
+    let algorithm = match (&jwk.common.key_algorithm, &jwk.algorithm) {
+        (_, AlgorithmParameters::OctetKey(_)) => return None,
+        (Some(key_algorithm), _) => Algorithm::from_str(&key_algorithm.to_string()).ok()?,
+        (None, AlgorithmParameters::RSA(_)) => Algorithm::RS256,
+        (None, AlgorithmParameters::EllipticCurve(params)) => match params.curve {
+            EllipticCurve::P256 => Algorithm::ES256,
+            EllipticCurve::P384 => Algorithm::ES384,
+            _ => return None,
+        },
+        (None, AlgorithmParameters::OctetKeyPair(_)) => Algorithm::EdDSA,
+    };
+    (!matches!(
+        algorithm,
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+    ))
+    .then_some(algorithm)
+}
+
+/// Map the claims of a validated token to its subject, tenant and scopes. Scopes that are not
+/// scopes of the service (e.g. `openid`) are ignored.
+fn map_claims(claims: &Map<String, Value>, config: &JwtConfig) -> Principal {
+    let scopes = match claim(claims, &config.scopes_claim) {
+        Some(Value::String(scopes)) => scopes
+            .split_whitespace()
+            .filter_map(|scope| Scope::from_str(scope).ok())
+            .collect(),
+        Some(Value::Array(scopes)) => scopes
+            .iter()
+            .filter_map(Value::as_str)
+            .filter_map(|scope| Scope::from_str(scope).ok())
+            .collect(),
+        _ => BTreeSet::new(),
+    };
+    let tenant_id = match claim(claims, &config.tenant_claim) {
+        Some(Value::String(tenant_id)) => Some(tenant_id.clone()),
+        Some(Value::Number(tenant_id)) => Some(tenant_id.to_string()),
+        _ => None,
+    };
+    Principal {
+        subject: claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned(),
+        tenant_id,
+        scopes,
+    }
+}
+
+/// A claim, with `.` separating nested claims (e.g. `realm_access.roles`).
+fn claim<'a>(claims: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let mut parts = path.split('.');
+    let first = claims.get(parts.next()?)?;
+    parts.try_fold(first, |value, part| value.get(part))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::config::app_config::{JwtConfig, Scope};
+    use crate::utils::jwt::{key_algorithm, map_claims};
+
+    fn create_test_config() -> JwtConfig {
+        JwtConfig {
+            jwks: Some("jwks.json".to_string()),
+            jwks_refresh_interval: Duration::from_secs(300),
+            issuer: Some("https://issuer.example.com".to_string()),
+            audience: Some("ocr-service".to_string()),
+            leeway: Duration::from_secs(60),
+            tenant_claim: "tenant_id".to_string(),
+            scopes_claim: "scope".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_map_claims() {
+        let claims = json!({
+            "sub": "user-1",
+            "tenant_id": "acme",
+            "scope": "openid ocr languages:read",
+        });
+        let principal = map_claims(claims.as_object().unwrap(), &create_test_config());
+
+        assert_eq!(principal.subject, "user-1");
+        assert_eq!(principal.tenant_id.as_deref(), Some("acme"));
+        assert_eq!(
+            principal.scopes,
+            BTreeSet::from([Scope::Ocr, Scope::LanguagesRead])
+        );
+    }
+
+    #[test]
+    fn test_map_nested_claims() {
+        let config = JwtConfig {
+            tenant_claim: "org.id".to_string(),
+            scopes_claim: "realm_access.roles".to_string(),
+            ..create_test_config()
+        };
+        let claims = json!({
+            "sub": "user-1",
+            "org": {"id": 42},
+            "realm_access": {"roles": ["admin", "offline_access"]},
+        });
+        let principal = map_claims(claims.as_object().unwrap(), &config);
+
+        assert_eq!(principal.tenant_id.as_deref(), Some("42"));
+        assert_eq!(principal.scopes, BTreeSet::from([Scope::Admin]));
+
+        let principal = map_claims(json!({"sub": "user-1"}).as_object().unwrap(), &config);
+        assert_eq!(principal.tenant_id, None);
+        assert!(principal.scopes.is_empty());
+    }
+
+    #[test]
+    fn test_key_algorithm() {
+        let jwk = |jwk: serde_json::Value| serde_json::from_value(jwk).unwrap();
+
+        let okp = jwk(json!({"kty": "OKP", "crv": "Ed25519", "x": "AAAA"}));
+        assert_eq!(key_algorithm(&okp), Some(jsonwebtoken::Algorithm::EdDSA));
+        let rsa = jwk(json!({"kty": "RSA", "alg": "PS256", "n": "AAAA", "e": "AQAB"}));
+        assert_eq!(key_algorithm(&rsa), Some(jsonwebtoken::Algorithm::PS256));
+        // Symmetric keys would let anyone who can read the JWKS sign tokens.
+        let oct = jwk(json!({"kty": "oct", "alg": "HS256", "k": "AAAA"}));
+        assert_eq!(key_algorithm(&oct), None);
+    }
+}
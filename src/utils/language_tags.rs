@@ -0,0 +1,343 @@
+use std::collections::{BTreeMap, HashSet};
+
+use axum::http::{HeaderMap, header::ACCEPT_LANGUAGE};
+
+use crate::models::languages::TesseractModel;
+
+/// The ISO 15924 script and the ISO 639-1, ISO 639-2/B, ISO 639-3 and BCP-47 tags of the
+/// Tesseract languages, in lower case. Tags with a script or region subtag are only listed when
+/// they select a different Tesseract language than their primary language.
+const LANGUAGE_TAGS: &[(&str, &str, &[&str])] = &[
+    ("afr", "Latn", &["af"]),
+    ("amh", "Ethi", &["am"]),
+    ("ara", "Arab", &["ar", "arb"]),
+    ("asm", "Beng", &["as"]),
+    ("aze", "Latn", &["az", "az-latn"]),
+    ("aze_cyrl", "Cyrl", &["az-cyrl"]),
+    ("bel", "Cyrl", &["be"]),
+    ("ben", "Beng", &["bn"]),
+    ("bod", "Tibt", &["bo", "tib"]),
+    ("bos", "Latn", &["bs"]),
+    ("bre", "Latn", &["br"]),
+    ("bul", "Cyrl", &["bg"]),
+    ("cat", "Latn", &["ca"]),
+    ("ceb", "Latn", &[]),
+    ("ces", "Latn", &["cs", "cze"]),
+    (
+        "chi_sim",
+        "Hans",
+        &["zh", "zh-hans", "zh-cn", "zh-sg", "zho", "chi", "cmn"],
+    ),
+    ("chi_tra", "Hant", &["zh-hant", "zh-tw", "zh-hk", "zh-mo"]),
+    ("chr", "Cher", &[]),
+    ("cos", "Latn", &["co"]),
+    ("cym", "Latn", &["cy", "wel"]),
+    ("dan", "Latn", &["da"]),
+    ("deu", "Latn", &["de", "ger"]),
+    ("div", "Thaa", &["dv"]),
+    ("dzo", "Tibt", &["dz"]),
+    ("ell", "Grek", &["el", "gre"]),
+    ("eng", "Latn", &["en"]),
+    ("enm", "Latn", &[]),
+    ("epo", "Latn", &["eo"]),
+    ("est", "Latn", &["et", "ekk"]),
+    ("eus", "Latn", &["eu", "baq"]),
+    ("fao", "Latn", &["fo"]),
+    ("fas", "Arab", &["fa", "per", "pes"]),
+    ("fil", "Latn", &[]),
+    ("fin", "Latn", &["fi"]),
+    ("fra", "Latn", &["fr", "fre"]),
+    ("frm", "Latn", &[]),
+    ("fry", "Latn", &["fy"]),
+    ("gla", "Latn", &["gd"]),
+    ("gle", "Latn", &["ga"]),
+    ("glg", "Latn", &["gl"]),
+    ("grc", "Grek", &[]),
+    ("guj", "Gujr", &["gu"]),
+    ("hat", "Latn", &["ht"]),
+    ("heb", "Hebr", &["he"]),
+    ("hin", "Deva", &["hi"]),
+    ("hrv", "Latn", &["hr"]),
+    ("hun", "Latn", &["hu"]),
+    ("hye", "Armn", &["hy", "arm"]),
+    ("iku", "Cans", &["iu"]),
+    ("ind", "Latn", &["id"]),
+    ("isl", "Latn", &["is", "ice"]),
+    ("ita", "Latn", &["it"]),
+    ("jav", "Latn", &["jv"]),
+    ("jpn", "Jpan", &["ja"]),
+    ("jpn_vert", "Jpan", &[]),
+    ("kan", "Knda", &["kn"]),
+    ("kat", "Geor", &["ka", "geo"]),
+    ("kaz", "Cyrl", &["kk"]),
+    ("khm", "Khmr", &["km"]),
+    ("kir", "Cyrl", &["ky"]),
+    ("kmr", "Latn", &["ku", "kur"]),
+    ("kor", "Kore", &["ko"]),
+    ("lao", "Laoo", &["lo"]),
+    ("lat", "Latn", &["la"]),
+    ("lav", "Latn", &["lv", "lvs"]),
+    ("lit", "Latn", &["lt"]),
+    ("ltz", "Latn", &["lb"]),
+    ("mal", "Mlym", &["ml"]),
+    ("mar", "Deva", &["mr"]),
+    ("mkd", "Cyrl", &["mk", "mac"]),
+    ("mlt", "Latn", &["mt"]),
+    ("mon", "Cyrl", &["mn", "khk"]),
+    ("mri", "Latn", &["mi", "mao"]),
+    ("msa", "Latn", &["ms", "may", "zsm"]),
+    ("mya", "Mymr", &["my", "bur"]),
+    ("nep", "Deva", &["ne", "npi"]),
+    ("nld", "Latn", &["nl", "dut"]),
+    ("nor", "Latn", &["no", "nb", "nn", "nob", "nno"]),
+    ("oci", "Latn", &["oc"]),
+    ("ori", "Orya", &["or", "ory"]),
+    ("pan", "Guru", &["pa"]),
+    ("pol", "Latn", &["pl"]),
+    ("por", "Latn", &["pt"]),
+    ("pus", "Arab", &["ps", "pbt"]),
+    ("que", "Latn", &["qu"]),
+    ("ron", "Latn", &["ro", "rum"]),
+    ("rus", "Cyrl", &["ru"]),
+    ("san", "Deva", &["sa"]),
+    ("sin", "Sinh", &["si"]),
+    ("slk", "Latn", &["sk", "slo"]),
+    ("slv", "Latn", &["sl"]),
+    ("snd", "Arab", &["sd"]),
+    ("spa", "Latn", &["es"]),
+    ("sqi", "Latn", &["sq", "alb", "als"]),
+    ("srp", "Cyrl", &["sr", "sr-cyrl"]),
+    ("srp_latn", "Latn", &["sr-latn"]),
+    ("sun", "Latn", &["su"]),
+    ("swa", "Latn", &["sw", "swh"]),
+    ("swe", "Latn", &["sv"]),
+    ("syr", "Syrc", &[]),
+    ("tam", "Taml", &["ta"]),
+    ("tat", "Cyrl", &["tt"]),
+    ("tel", "Telu", &["te"]),
+    ("tgk", "Cyrl", &["tg"]),
+    ("tgl", "Latn", &["tl"]),
+    ("tha", "Thai", &["th"]),
+    ("tir", "Ethi", &["ti"]),
+    ("ton", "Latn", &["to"]),
+    ("tur", "Latn", &["tr"]),
+    ("uig", "Arab", &["ug"]),
+    ("ukr", "Cyrl", &["uk"]),
+    ("urd", "Arab", &["ur"]),
+    ("uzb", "Latn", &["uz", "uz-latn"]),
+    ("uzb_cyrl", "Cyrl", &["uz-cyrl"]),
+    ("vie", "Latn", &["vi"]),
+    ("yid", "Hebr", &["yi", "ydd"]),
+    ("yor", "Latn", &["yo"]),
+];
+
+/// The Tesseract language of a language tag, e.g. `deu` for `de-DE` or `chi_tra` for `zh-TW`.
+///
+/// Tags are matched case-insensitively, and subtags are removed from the end of the tag until
+/// it matches, as in the lookup of [RFC 4647](https://www.rfc-editor.org/rfc/rfc4647#section-3.4).
+#[must_use]
+pub fn tesseract_language(tag: &str) -> Option<&'static str> {
+    let mut tag = tag.trim().to_lowercase().replace('_', "-");
+    loop {
+        let found = LANGUAGE_TAGS.iter().find(|(language, _, aliases)| {
+            language.replace('_', "-") == tag || aliases.contains(&tag.as_str())
+        });
+        if let Some((language, _, _)) = found {
+            return Some(language);
+        }
+        let (prefix, _) = tag.rsplit_once('-')?;
+        tag = prefix.to_owned();
+    }
+}
+
+/// The tags that can be used instead of a Tesseract language.
+#[must_use]
+pub fn language_aliases(language: &str) -> &'static [&'static str] {
+    LANGUAGE_TAGS
+        .iter()
+        .find(|(tesseract_language, _, _)| *tesseract_language == language)
+        .map_or(&[], |(_, _, aliases)| aliases)
+}
+
+/// The ISO 15924 script of a Tesseract language, e.g. `Latn` for `eng` or `Hans` for `chi_sim`.
+///
+/// Variants of a language such as `deu_frak` or `chi_sim_vert` have the script of the language.
+#[must_use]
+pub fn language_script(language: &str) -> Option<&'static str> {
+    let mut language = language;
+    loop {
+        let found = LANGUAGE_TAGS
+            .iter()
+            .find(|(tesseract_language, _, _)| *tesseract_language == language);
+        if let Some((_, script, _)) = found {
+            return Some(script);
+        }
+        (language, _) = language.rsplit_once('_')?;
+    }
+}
+
+/// The aliases of the given languages, for the languages that have some.
+#[must_use]
+pub fn available_aliases(languages: &[TesseractModel]) -> BTreeMap<String, Vec<String>> {
+    languages
+        .iter()
+        .map(|tesseract_model| tesseract_model.language.as_str())
+        .filter_map(|language| {
+            let aliases = language_aliases(language);
+            (!aliases.is_empty()).then(|| {
+                let aliases = aliases.iter().map(|alias| (*alias).to_owned()).collect();
+                (language.to_owned(), aliases)
+            })
+        })
+        .collect()
+}
+
+/// Resolve a requested language to an available Tesseract language.
+///
+/// Available languages are used as is, so models named like a tag are never shadowed by an
+/// alias. Otherwise the language is looked up as a tag.
+#[must_use]
+pub fn resolve_language(
+    language: &str,
+    available_languages: &HashSet<TesseractModel>,
+) -> Option<String> {
+    let is_available = |language: &str| {
+        available_languages
+            .iter()
+            .any(|tesseract_model| tesseract_model.language == language)
+    };
+    if is_available(language) {
+        return Some(language.to_owned());
+    }
+    tesseract_language(language)
+        .filter(|language| is_available(language))
+        .map(str::to_owned)
+}
+
+/// The available language preferred by the `Accept-Language` header of a request, if any.
+///
+/// Languages are tried by decreasing quality, the first one that is available wins.
+#[must_use]
+pub fn accepted_language(
+    headers: &HeaderMap,
+    available_languages: &HashSet<TesseractModel>,
+) -> Option<String> {
+    let mut ranges: Vec<(&str, f32)> = headers
+        .get_all(ACCEPT_LANGUAGE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let tag = parts.next().filter(|tag| !tag.is_empty() && *tag != "*")?;
+            let quality = parts
+                .find_map(|parameter| parameter.strip_prefix("q="))
+                .map_or(Some(1.0), |quality| quality.parse::<f32>().ok())?;
+            (quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    // The sort is stable, so ranges of the same quality keep the order of the header.
+    ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    ranges
+        .into_iter()
+        .find_map(|(tag, _)| resolve_language(tag, available_languages))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use axum::http::{HeaderMap, HeaderValue, header::ACCEPT_LANGUAGE};
+
+    use crate::models::languages::TesseractModel;
+    use crate::utils::language_tags::{
+        accepted_language, language_aliases, language_script, resolve_language, tesseract_language,
+    };
+
+    fn create_available_languages(languages: &[&str]) -> HashSet<TesseractModel> {
+        languages
+            .iter()
+            .map(|language| TesseractModel {
+                language: (*language).to_owned(),
+                model: None,
+                full_path: None,
+                relative_path: None,
+                metadata: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tesseract_language() {
+        assert_eq!(tesseract_language("en"), Some("eng"));
+        assert_eq!(tesseract_language("de-DE"), Some("deu"));
+        assert_eq!(tesseract_language("ger"), Some("deu"));
+        assert_eq!(tesseract_language("zh-Hans"), Some("chi_sim"));
+        assert_eq!(tesseract_language("zh-Hant-TW"), Some("chi_tra"));
+        assert_eq!(tesseract_language("zh_TW"), Some("chi_tra"));
+        assert_eq!(tesseract_language("sr-Latn-RS"), Some("srp_latn"));
+        assert_eq!(tesseract_language("ENG"), Some("eng"));
+        assert_eq!(tesseract_language("chi_sim"), Some("chi_sim"));
+        assert_eq!(tesseract_language("xx-YY"), None);
+        assert_eq!(tesseract_language(""), None);
+    }
+
+    #[test]
+    fn test_language_aliases() {
+        assert_eq!(language_aliases("deu"), ["de", "ger"]);
+        assert!(language_aliases("unknown").is_empty());
+    }
+
+    #[test]
+    fn test_language_script() {
+        assert_eq!(language_script("eng"), Some("Latn"));
+        assert_eq!(language_script("srp_latn"), Some("Latn"));
+        assert_eq!(language_script("chi_tra_vert"), Some("Hant"));
+        assert_eq!(language_script("deu_frak"), Some("Latn"));
+        assert_eq!(language_script("unknown"), None);
+    }
+
+    #[test]
+    fn test_resolve_language() {
+        let available_languages = create_available_languages(&["eng", "deu", "chi_sim"]);
+        assert_eq!(
+            resolve_language("eng", &available_languages).as_deref(),
+            Some("eng")
+        );
+        assert_eq!(
+            resolve_language("en-US", &available_languages).as_deref(),
+            Some("eng")
+        );
+        assert_eq!(
+            resolve_language("zh-CN", &available_languages).as_deref(),
+            Some("chi_sim")
+        );
+        // Known tags of languages that are not installed are not available.
+        assert_eq!(resolve_language("zh-TW", &available_languages), None);
+        assert_eq!(resolve_language("fr", &available_languages), None);
+    }
+
+    #[test]
+    fn test_accepted_language() {
+        let available_languages = create_available_languages(&["eng", "deu"]);
+        let accepted = |header: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_str(header).unwrap());
+            accepted_language(&headers, &available_languages)
+        };
+
+        assert_eq!(accepted("de-DE").as_deref(), Some("deu"));
+        assert_eq!(
+            accepted("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5").as_deref(),
+            Some("eng")
+        );
+        assert_eq!(accepted("en;q=0.5, de").as_deref(), Some("deu"));
+        assert_eq!(accepted("de;q=0, en;q=0.1").as_deref(), Some("eng"));
+        assert_eq!(accepted("fr, *"), None);
+        assert_eq!(accepted("de;q=invalid"), None);
+        assert_eq!(
+            accepted_language(&HeaderMap::new(), &available_languages),
+            None
+        );
+    }
+}
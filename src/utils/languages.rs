@@ -1,10 +1,59 @@
 use crate::config::app_config::AppConfig;
-use crate::models::languages::TesseractModel;
+use crate::models::languages::{LanguagesQueryParams, TesseractModel};
+use crate::utils::language_tags::language_script;
+use crate::utils::traineddata::{cached_model_metadata, retain_cached_metadata};
+use crate::utils::validations::model_matches;
+use arc_swap::ArcSwap;
 use std::collections::HashSet;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use walkdir::{DirEntry, WalkDir};
 
+/// The languages available for OCR, shared by the REST and gRPC APIs.
+///
+/// Rescanning the tesseract data path replaces the whole set at once, so requests see either
+/// the previous or the new languages, never a partial scan.
+#[derive(Clone, Debug)]
+pub struct AvailableLanguages(Arc<ArcSwap<HashSet<TesseractModel>>>);
+
+impl AvailableLanguages {
+    #[must_use]
+    pub fn new(languages: HashSet<TesseractModel>) -> Self {
+        Self(Arc::new(ArcSwap::from_pointee(languages)))
+    }
+
+    /// The current languages.
+    #[must_use]
+    pub fn load(&self) -> Arc<HashSet<TesseractModel>> {
+        self.0.load_full()
+    }
+
+    pub fn store(&self, languages: HashSet<TesseractModel>) {
+        self.0.store(Arc::new(languages));
+    }
+
+    /// Scan the tesseract data path again and replace the languages with the ones found.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data path cannot be read, in which case the languages are kept.
+    pub fn rescan(&self, app_config: &AppConfig) -> io::Result<Arc<HashSet<TesseractModel>>> {
+        let languages = Arc::new(get_available_languages_with_models(app_config)?);
+        let previous = self.0.swap(languages.clone());
+        if previous != languages {
+            tracing::info!(
+                added = languages.difference(&previous).count(),
+                removed = previous.difference(&languages).count(),
+                "Reloaded {} Tesseract models from '{}'",
+                languages.len(),
+                app_config.tesseract.data_path
+            );
+        }
+        Ok(languages)
+    }
+}
+
 /// Get all available languages with their models from the tesseract data path.
 pub fn get_available_languages_with_models(
     app_config: &AppConfig,
@@ -12,6 +61,7 @@ pub fn get_available_languages_with_models(
     let tesseract_data_path = Path::new(&app_config.tesseract.data_path);
     let mut languages: HashSet<TesseractModel> = HashSet::new();
     let mut languages_visited: HashSet<String> = HashSet::new();
+    let mut model_paths: HashSet<PathBuf> = HashSet::new();
 
     for entry_result in WalkDir::new(tesseract_data_path)
         .min_depth(1)
@@ -39,17 +89,24 @@ pub fn get_available_languages_with_models(
             .strip_suffix(".traineddata")
             .unwrap_or_default()
             .to_string();
+        // Only read when the model is listed, as checksums are computed over the whole file.
+        // Files that did not change since the last scan are not read again.
+        let mut metadata = || {
+            model_paths.insert(entry_result.path().to_owned());
+            cached_model_metadata(entry_result.path())
+                .inspect_err(|error| tracing::warn!("Failed to read '{full_path}': {error}"))
+                .ok()
+        };
         match entry_result.depth() {
-            1 => {
-                // Files directly in tesseract data path
-                if languages_visited.insert(language_model_name.to_string()) {
-                    languages.insert(TesseractModel {
-                        language: language_model_name.to_string(),
-                        model: None,
-                        full_path: Some(full_path),
-                        relative_path: Some(relative_path),
-                    });
-                }
+            // Files directly in tesseract data path
+            1 if languages_visited.insert(language_model_name.to_string()) => {
+                languages.insert(TesseractModel {
+                    language: language_model_name.to_string(),
+                    model: None,
+                    metadata: metadata(),
+                    full_path: Some(full_path),
+                    relative_path: Some(relative_path),
+                });
             }
             2 => {
                 // Files in subdirectories (language directories)
@@ -65,6 +122,7 @@ pub fn get_available_languages_with_models(
                         languages.insert(TesseractModel {
                             language: language_dir_name.to_string(),
                             model: Some(language_model_name.to_string()),
+                            metadata: metadata(),
                             full_path: Some(full_path),
                             relative_path: Some(relative_path),
                         });
@@ -76,9 +134,55 @@ pub fn get_available_languages_with_models(
             }
         }
     }
+    retain_cached_metadata(tesseract_data_path, &model_paths);
     Ok(languages)
 }
 
+/// Sort the languages by language name then by model name.
+pub fn sorted_languages(available_languages: &HashSet<TesseractModel>) -> Vec<TesseractModel> {
+    let mut sorted_languages: Vec<TesseractModel> = available_languages.iter().cloned().collect();
+    sorted_languages.sort_by(|a, b| a.language.cmp(&b.language).then(a.model.cmp(&b.model)));
+    sorted_languages
+}
+
+/// Whether a model is trained for vertical text, like `jpn_vert` or `chi_sim/chi_sim_vert`.
+#[must_use]
+pub fn is_vertical(tesseract_model: &TesseractModel) -> bool {
+    tesseract_model
+        .model
+        .as_deref()
+        .unwrap_or(&tesseract_model.language)
+        .ends_with("_vert")
+}
+
+/// Keep the models that match all the filters of the query.
+#[must_use]
+pub fn filter_languages(
+    languages: Vec<TesseractModel>,
+    params: &LanguagesQueryParams,
+) -> Vec<TesseractModel> {
+    languages
+        .into_iter()
+        .filter(|tesseract_model| {
+            params.script.as_ref().is_none_or(|script| {
+                language_script(&tesseract_model.language)
+                    .is_some_and(|language_script| language_script.eq_ignore_ascii_case(script))
+            })
+        })
+        .filter(|tesseract_model| {
+            params
+                .model
+                .as_ref()
+                .is_none_or(|model| model_matches(tesseract_model, model))
+        })
+        .filter(|tesseract_model| {
+            params
+                .vertical
+                .is_none_or(|vertical| is_vertical(tesseract_model) == vertical)
+        })
+        .collect()
+}
+
 fn is_non_hidden_file(entry: &DirEntry) -> bool {
     entry
         .file_name()
@@ -120,8 +224,11 @@ mod tests {
                 port: 8080,
                 file_upload_max_size: 1024 * 1024 * 10,
                 file_upload_max_size_enabled: true,
+                model_upload_max_size: 1024 * 1024 * 200,
                 environment: "test".to_string(),
                 timeout: Duration::from_secs(15),
+                grpc_enabled: false,
+                grpc_port: 50051,
             },
             service: crate::config::app_config::ServiceConfig {
                 name: "test-service".to_string(),
@@ -129,6 +236,26 @@ mod tests {
             },
             security: crate::config::app_config::SecurityConfig {
                 max_access_control_age: Duration::from_secs(600),
+                admin_token: None,
+                expose_model_paths: false,
+                api_keys: Vec::new(),
+                api_keys_file: None,
+                jwt: crate::config::app_config::JwtConfig {
+                    jwks: None,
+                    jwks_refresh_interval: Duration::from_secs(300),
+                    issuer: None,
+                    audience: None,
+                    leeway: Duration::from_secs(60),
+                    tenant_claim: "tenant_id".to_string(),
+                    scopes_claim: "scope".to_string(),
+                },
+                cors: crate::config::app_config::CorsConfig {
+                    allowed_origins: vec!["*".to_string()],
+                    allowed_methods: vec!["*".to_string()],
+                    allowed_headers: vec!["*".to_string()],
+                    exposed_headers: vec!["x-request-id".to_string()],
+                    allow_credentials: false,
+                },
             },
             otel: crate::config::app_config::OtelConfig {
                 enabled: false,
@@ -137,14 +264,52 @@ mod tests {
                 logs_endpoint: None,
                 metrics_endpoint: None,
                 metric_export_interval: None,
+                metrics_exporter: crate::config::app_config::MetricsExporter::Otlp,
+                protocol: crate::config::app_config::OtlpProtocol::Grpc,
+                traces_sampler: crate::config::app_config::TracesSampler::TraceIdRatio,
+                traces_sampler_ratio: 1.0,
             },
             otel_provider: crate::config::app_config::OtelProviderConfig {
                 provider: None,
                 organization: None,
                 stream_name: None,
                 auth_token: None,
+                headers: std::collections::BTreeMap::new(),
+            },
+            log: crate::config::app_config::LogConfig {
+                format: crate::config::app_config::LogFormat::Pretty,
+            },
+            tesseract: crate::config::app_config::TesseractConfig {
+                data_path,
+                watch_enabled: false,
+                watch_debounce: Duration::from_millis(1000),
+                model_preferences: crate::config::app_config::ModelPreferences::default(),
+                install_sources: Vec::new(),
+            },
+            jobs: crate::config::app_config::JobsConfig {
+                result_retention: Duration::from_secs(300),
+                max_queued: 100,
+                workers: 2,
+            },
+            cache: crate::config::app_config::CacheConfig {
+                enabled: false,
+                backend: crate::config::app_config::CacheBackend::Memory,
+                max_entries: 1000,
+                ttl: Duration::from_secs(3600),
+                directory: "cache".to_string(),
+            },
+            health: crate::config::app_config::HealthConfig {
+                max_queue_depth: 100,
+                self_test_enabled: false,
+            },
+            rate_limit: crate::config::app_config::RateLimitConfig {
+                enabled: false,
+                requests_per_minute: 60,
+                burst: 10,
+                max_concurrent: 2,
+                key: crate::config::app_config::RateLimitKey::Client,
+                trust_forwarded_for: false,
             },
-            tesseract: crate::config::app_config::TesseractConfig { data_path },
         }
     }
 
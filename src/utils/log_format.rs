@@ -0,0 +1,188 @@
+use std::fmt;
+
+use opentelemetry::trace::{SpanId, TraceContextExt as _, TraceId};
+use serde_json::{Map, Value};
+use tracing::{Event, Subscriber, field::Field};
+use tracing_opentelemetry::OtelData;
+use tracing_subscriber::{
+    fmt::{
+        FmtContext, FormatEvent, FormattedFields,
+        format::{JsonFields, Writer},
+        time::{FormatTime as _, SystemTime},
+    },
+    registry::{LookupSpan, SpanRef},
+};
+
+/// Formats every event as a single-line JSON object for log shippers.
+///
+/// The fields of all the spans the event is in (e.g. the request id of the request span or the
+/// job id of an OCR job) are flattened into the record next to the fields of the event, and the
+/// OpenTelemetry `trace_id` and `span_id` are added when the event is in a traced span.
+#[derive(Debug, Default)]
+pub struct JsonLogFormat;
+
+impl<S> FormatEvent<S, JsonFields> for JsonLogFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, JsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+
+        let mut record = Map::new();
+        record.insert("timestamp".to_owned(), Value::String(timestamp));
+        record.insert(
+            "level".to_owned(),
+            Value::String(metadata.level().to_string()),
+        );
+        record.insert(
+            "target".to_owned(),
+            Value::String(metadata.target().to_owned()),
+        );
+
+        if let Some(scope) = ctx.event_scope() {
+            let mut span_names = Vec::new();
+            for span in scope.from_root() {
+                span_names.push(Value::String(span.name().to_owned()));
+                // Fields of inner spans take precedence over the fields of outer spans.
+                record.extend(span_fields(&span));
+            }
+            record.insert("spans".to_owned(), Value::Array(span_names));
+        }
+        if let Some((trace_id, span_id)) = ctx.lookup_current().and_then(|span| otel_ids(&span)) {
+            record.insert("trace_id".to_owned(), Value::String(trace_id.to_string()));
+            record.insert("span_id".to_owned(), Value::String(span_id.to_string()));
+        }
+
+        let mut visitor = JsonVisitor(Map::new());
+        event.record(&mut visitor);
+        record.extend(visitor.0);
+
+        writeln!(writer, "{}", Value::Object(record))
+    }
+}
+
+/// The fields recorded on a span, which `JsonFields` stores as a JSON object.
+fn span_fields<S>(span: &SpanRef<'_, S>) -> Map<String, Value>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    span.extensions()
+        .get::<FormattedFields<JsonFields>>()
+        .and_then(|fields| serde_json::from_str::<Map<String, Value>>(fields).ok())
+        .unwrap_or_default()
+}
+
+/// The OpenTelemetry trace and span ids of a span, if it is traced.
+fn otel_ids<S>(span: &SpanRef<'_, S>) -> Option<(TraceId, SpanId)>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let extensions = span.extensions();
+    let otel_data = extensions.get::<OtelData>()?;
+    // Only root spans have their own trace id, child spans inherit the one of their parent.
+    let trace_id = otel_data
+        .builder
+        .trace_id
+        .unwrap_or_else(|| otel_data.parent_cx.span().span_context().trace_id());
+    let span_id = otel_data.builder.span_id?;
+    (trace_id != TraceId::INVALID).then_some((trace_id, span_id))
+}
+
+struct JsonVisitor(Map<String, Value>);
+
+impl tracing::field::Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_owned(), Value::String(format!("{value:?}")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::Value;
+    use tracing_subscriber::{
+        fmt::{MakeWriter, format::JsonFields},
+        layer::SubscriberExt as _,
+    };
+
+    use crate::utils::log_format::JsonLogFormat;
+
+    #[derive(Clone, Default)]
+    struct TestWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for TestWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for TestWriter {
+        type Writer = TestWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_json_log_format_includes_span_fields() {
+        let writer = TestWriter::default();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .fmt_fields(JsonFields::new())
+                .event_format(JsonLogFormat)
+                .with_writer(writer.clone()),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            let _request = tracing::info_span!("request", request_id = "abc").entered();
+            let _job = tracing::info_span!("ocr.job", job_id = "42", pages = 2).entered();
+            tracing::info!(page = 1, "Recognized page");
+        });
+
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        let record: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(record["level"], "INFO");
+        assert_eq!(record["message"], "Recognized page");
+        assert_eq!(record["request_id"], "abc");
+        assert_eq!(record["job_id"], "42");
+        assert_eq!(record["pages"], 2);
+        assert_eq!(record["page"], 1);
+        assert_eq!(record["spans"], serde_json::json!(["request", "ocr.job"]));
+        assert!(record.get("trace_id").is_none());
+    }
+}
@@ -0,0 +1,234 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use opentelemetry::{
+    KeyValue, global,
+    metrics::{Histogram, Meter},
+};
+
+use crate::models::languages::TesseractModel;
+
+/// The instrumentation scope of the OCR metrics.
+const OCR_METER_NAME: &str = "ocr_service";
+
+/// Histogram boundaries (in seconds) for the duration of the OCR stages.
+const DURATION_BOUNDARIES: [f64; 14] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// Metrics describing the OCR work itself, on top of the HTTP metrics of `tower_otel_http_metrics`.
+///
+/// Every measurement is labelled with the `ocr.language` and `ocr.model` of the resolved
+/// `TesseractModel`.
+pub struct OcrMetrics {
+    pub decode_duration: Histogram<f64>,
+    pub preprocess_duration: Histogram<f64>,
+    pub engine_init_duration: Histogram<f64>,
+    pub recognition_duration: Histogram<f64>,
+    pub image_megapixels: Histogram<f64>,
+    pub output_characters: Histogram<u64>,
+    pub mean_confidence: Histogram<f64>,
+}
+
+/// The OCR metrics, created from the global meter provider on first use.
+///
+/// Must be called after `initialize_opentelemetry_providers` so the metrics are exported.
+pub fn ocr_metrics() -> &'static OcrMetrics {
+    static INSTANCE: OnceLock<OcrMetrics> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| OcrMetrics::new(&global::meter(OCR_METER_NAME)))
+}
+
+impl OcrMetrics {
+    fn new(meter: &Meter) -> Self {
+        let duration = |name: &'static str, description: &'static str| {
+            meter
+                .f64_histogram(name)
+                .with_description(description)
+                .with_unit("s")
+                .with_boundaries(DURATION_BOUNDARIES.to_vec())
+                .build()
+        };
+        Self {
+            decode_duration: duration("ocr.decode.duration", "Time spent decoding uploaded images"),
+            preprocess_duration: duration(
+                "ocr.preprocess.duration",
+                "Time spent converting decoded images for Tesseract",
+            ),
+            engine_init_duration: duration(
+                "ocr.engine_init.duration",
+                "Time spent initializing the Tesseract engine with a language model",
+            ),
+            recognition_duration: duration(
+                "ocr.recognition.duration",
+                "Time spent recognizing text in images",
+            ),
+            image_megapixels: meter
+                .f64_histogram("ocr.image.size")
+                .with_description("Size of the processed images")
+                .with_unit("Mpx")
+                .with_boundaries(vec![0.1, 0.5, 1.0, 2.0, 4.0, 8.0, 12.0, 16.0, 24.0, 50.0])
+                .build(),
+            output_characters: meter
+                .u64_histogram("ocr.output.characters")
+                .with_description("Number of characters extracted from an image")
+                .with_unit("{character}")
+                .with_boundaries(vec![
+                    0.0, 10.0, 50.0, 100.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+                ])
+                .build(),
+            mean_confidence: meter
+                .f64_histogram("ocr.confidence")
+                .with_description("Mean confidence of the recognized words of an image")
+                .with_unit("%")
+                .with_boundaries(vec![
+                    10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 95.0,
+                ])
+                .build(),
+        }
+    }
+}
+
+/// The attributes of the OCR metrics for a Tesseract model.
+#[must_use]
+pub fn model_attributes(tesseract_model: &TesseractModel) -> [KeyValue; 2] {
+    [
+        KeyValue::new("ocr.language", tesseract_model.language.clone()),
+        KeyValue::new(
+            "ocr.model",
+            tesseract_model
+                .model
+                .clone()
+                .unwrap_or_else(|| "none".to_owned()),
+        ),
+    ]
+}
+
+/// Record a duration in seconds on one of the duration histograms.
+pub fn record_duration(histogram: &Histogram<f64>, duration: Duration, attributes: &[KeyValue]) {
+    histogram.record(duration.as_secs_f64(), attributes);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Weak};
+    use std::time::Duration;
+
+    use opentelemetry::{KeyValue, metrics::MeterProvider as _};
+    use opentelemetry_sdk::{
+        Resource,
+        error::OTelSdkResult,
+        metrics::{
+            InstrumentKind, ManualReader, MetricResult, Pipeline, SdkMeterProvider, Temporality,
+            data::{self, ResourceMetrics},
+            reader::MetricReader,
+        },
+    };
+
+    use crate::models::languages::TesseractModel;
+    use crate::utils::metrics::{OcrMetrics, model_attributes, record_duration};
+
+    /// Shares a `ManualReader` with the meter provider, so the test can collect from it.
+    #[derive(Clone, Debug, Default)]
+    struct SharedReader(Arc<ManualReader>);
+
+    impl MetricReader for SharedReader {
+        fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+            self.0.register_pipeline(pipeline);
+        }
+
+        fn collect(&self, rm: &mut ResourceMetrics) -> MetricResult<()> {
+            self.0.collect(rm)
+        }
+
+        fn force_flush(&self) -> OTelSdkResult {
+            self.0.force_flush()
+        }
+
+        fn shutdown(&self) -> OTelSdkResult {
+            self.0.shutdown()
+        }
+
+        fn temporality(&self, kind: InstrumentKind) -> Temporality {
+            self.0.temporality(kind)
+        }
+    }
+
+    #[test]
+    fn test_ocr_metrics_names_and_attributes() {
+        let reader = SharedReader::default();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(reader.clone())
+            .build();
+        let metrics = OcrMetrics::new(&provider.meter("test"));
+        let attributes = model_attributes(&TesseractModel {
+            language: "eng".to_string(),
+            model: None,
+            full_path: None,
+            relative_path: None,
+            metadata: None,
+        });
+
+        for histogram in [
+            &metrics.decode_duration,
+            &metrics.preprocess_duration,
+            &metrics.engine_init_duration,
+            &metrics.recognition_duration,
+        ] {
+            record_duration(histogram, Duration::from_millis(20), &attributes);
+        }
+        metrics.image_megapixels.record(1.5, &attributes);
+        metrics.mean_confidence.record(90.0, &attributes);
+        metrics.output_characters.record(42, &attributes);
+
+        let mut resource_metrics = ResourceMetrics {
+            resource: Resource::builder_empty().build(),
+            scope_metrics: Vec::new(),
+        };
+        reader.collect(&mut resource_metrics).unwrap();
+        let mut recorded: Vec<(String, String, Vec<KeyValue>)> = resource_metrics.scope_metrics[0]
+            .metrics
+            .iter()
+            .map(|metric| {
+                let data = metric.data.as_any();
+                let attributes =
+                    if let Some(histogram) = data.downcast_ref::<data::Histogram<f64>>() {
+                        histogram.data_points[0].attributes.clone()
+                    } else {
+                        data.downcast_ref::<data::Histogram<u64>>()
+                            .expect("Every OCR metric is a histogram")
+                            .data_points[0]
+                            .attributes
+                            .clone()
+                    };
+                (metric.name.to_string(), metric.unit.to_string(), attributes)
+            })
+            .collect();
+        recorded.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let expected_attributes = vec![
+            KeyValue::new("ocr.language", "eng"),
+            KeyValue::new("ocr.model", "none"),
+        ];
+        assert_eq!(
+            recorded
+                .iter()
+                .map(|(name, unit, _)| (name.as_str(), unit.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("ocr.confidence", "%"),
+                ("ocr.decode.duration", "s"),
+                ("ocr.engine_init.duration", "s"),
+                ("ocr.image.size", "Mpx"),
+                ("ocr.output.characters", "{character}"),
+                ("ocr.preprocess.duration", "s"),
+                ("ocr.recognition.duration", "s"),
+            ]
+        );
+        for (name, _, attributes) in recorded {
+            let mut attributes = attributes;
+            attributes.sort_by(|a, b| a.key.cmp(&b.key));
+            assert_eq!(attributes, expected_attributes, "{name}");
+        }
+    }
+}
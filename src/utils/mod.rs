@@ -1,3 +1,16 @@
+pub mod cache;
+pub mod health;
+pub mod jobs;
+pub mod jwt;
+pub mod language_tags;
 pub mod languages;
+pub mod log_format;
+pub mod metrics;
+pub mod model_installer;
+pub mod model_store;
+pub mod ocr;
+pub mod rate_limit;
 pub mod telemetry;
+pub mod tessdata_watcher;
+pub mod traineddata;
 pub mod validations;
@@ -0,0 +1,723 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+
+use crate::models::{error::ErrorType, languages::TesseractModel};
+use crate::utils::language_tags::tesseract_language;
+use crate::utils::model_store::{StagedModel, commit_models, model_slot, stage_model};
+
+/// The manifest read from the root of a source when none is given, in the format of
+/// `sha256sum`.
+pub const DEFAULT_MANIFEST: &str = "SHA256SUMS";
+
+const TRAINEDDATA_SUFFIX: &str = ".traineddata";
+
+/// The largest file read from a source, well above the largest official models.
+const MAX_FILE_SIZE: u64 = 1024 * 1024 * 512;
+
+/// How long connecting to an HTTP source may take.
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long an HTTP source may take to answer, or to send more of a file.
+const HTTP_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where models are installed from. Every source is laid out like the data path, with a
+/// manifest of the checksums of its models.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelSource {
+    /// A local directory, e.g. a mirror on a mounted volume.
+    Directory(PathBuf),
+    /// The base URL of an HTTP mirror.
+    Http(String),
+    /// A `.tar`, `.tar.gz`, `.tgz` or `.zip` bundle. The manifest may be in a subdirectory of
+    /// the bundle, which is then the root of the models.
+    Archive(PathBuf),
+}
+
+impl FromStr for ModelSource {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let source = source.trim();
+        if source.is_empty() {
+            return Err("The model source is empty".to_owned());
+        }
+        let lowercase = source.to_lowercase();
+        if lowercase.starts_with("http://") || lowercase.starts_with("https://") {
+            return Ok(ModelSource::Http(source.trim_end_matches('/').to_owned()));
+        }
+        if [".tar", ".tar.gz", ".tgz", ".zip"]
+            .iter()
+            .any(|extension| lowercase.ends_with(extension))
+        {
+            return Ok(ModelSource::Archive(PathBuf::from(source)));
+        }
+        Ok(ModelSource::Directory(PathBuf::from(source)))
+    }
+}
+
+impl fmt::Display for ModelSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelSource::Directory(path) | ModelSource::Archive(path) => {
+                write!(f, "{}", path.display())
+            }
+            ModelSource::Http(url) => f.write_str(url),
+        }
+    }
+}
+
+/// A model listed in the manifest of a source.
+#[derive(Debug)]
+struct ManifestEntry {
+    /// The path of the model in the source, e.g. `chi_sim/chi_sim_vert.traineddata`.
+    path: String,
+    sha256: String,
+    tesseract_model: TesseractModel,
+}
+
+/// Install models from a source into the data path.
+///
+/// Only the models of `languages` are installed, or every model of the manifest if it is empty.
+/// Languages can be given as aliases, e.g. `de` for `deu`. Every model is checked against its
+/// checksum before any model is moved into place, and the models moved into place are put back
+/// if a later one cannot be, so a failed install leaves the data path as it was. The models are
+/// not loaded by Tesseract, the checksums vouch for them.
+///
+/// This blocks on file and network I/O.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If the manifest is not a file name, the source or its manifest cannot be
+///   read, a file of the source is larger than 512 MiB, a language is not in the manifest, or a
+///   model does not match its checksum.
+/// - `InternalError`: If the models cannot be written to the data path.
+pub fn install_models(
+    data_path: &str,
+    source: &ModelSource,
+    manifest: &str,
+    languages: &[String],
+) -> Result<Vec<TesseractModel>, ErrorType> {
+    let mut reader = SourceReader::open(source, manifest)?;
+    let manifest_content = reader.read_manifest()?;
+    let entries = select_entries(parse_manifest(data_path, &manifest_content)?, languages)?;
+
+    let mut staged_models: Vec<(StagedModel, TesseractModel)> = Vec::new();
+    let paths: Vec<&str> = entries.iter().map(|entry| entry.path.as_str()).collect();
+    reader.read_files(&paths, &mut |path, content| {
+        let Some(entry) = entries.iter().find(|entry| entry.path == path) else {
+            return Ok(());
+        };
+        let sha256 = format!("{:x}", Sha256::digest(&content));
+        if !sha256.eq_ignore_ascii_case(&entry.sha256) {
+            return Err(ErrorType::InvalidRequest(format!(
+                "The checksum of '{path}' does not match the manifest"
+            )));
+        }
+        let staged_model = stage_model(data_path, &entry.tesseract_model, &content)?;
+        staged_models.push((staged_model, entry.tesseract_model.clone()));
+        Ok(())
+    })?;
+
+    let (staged_models, installed): (Vec<StagedModel>, Vec<TesseractModel>) =
+        staged_models.into_iter().unzip();
+    commit_models(staged_models)?;
+    for tesseract_model in &installed {
+        tracing::info!(
+            "Installed Tesseract model {} from '{}'",
+            tesseract_model.relative_path.as_deref().unwrap_or_default(),
+            source
+        );
+    }
+    Ok(installed)
+}
+
+/// Parse a manifest in the format of `sha256sum`: a checksum and a path per line. Files that are
+/// not `.traineddata` files are ignored.
+///
+/// Invalid lines are reported by their number only, so a file that is not a manifest is never
+/// echoed back.
+fn parse_manifest(data_path: &str, manifest: &str) -> Result<Vec<ManifestEntry>, ErrorType> {
+    let invalid = |number: usize, message: &str| {
+        ErrorType::InvalidRequest(format!("Invalid manifest line {number}: {message}"))
+    };
+    let mut entries = Vec::new();
+    for (number, line) in manifest
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+    {
+        let (sha256, path) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| invalid(number, "expected a checksum and a path"))?;
+        if sha256.len() != 64 || !sha256.chars().all(|char| char.is_ascii_hexdigit()) {
+            return Err(invalid(number, "expected a SHA-256 checksum"));
+        }
+        // `sha256sum` marks files read in binary mode with a `*`.
+        let path = path.trim_start().trim_start_matches('*');
+        let path = path.strip_prefix("./").unwrap_or(path);
+        let Some(stem) = path.strip_suffix(TRAINEDDATA_SUFFIX) else {
+            continue;
+        };
+        let expected_path = "expected <language>.traineddata or <language>/<model>.traineddata";
+        let tesseract_model = match stem.split('/').collect::<Vec<_>>()[..] {
+            [language] => model_slot(data_path, language, None),
+            [language, model] => model_slot(data_path, language, Some(model)),
+            _ => return Err(invalid(number, expected_path)),
+        }
+        .map_err(|_| invalid(number, expected_path))?;
+        entries.push(ManifestEntry {
+            path: path.to_owned(),
+            sha256: sha256.to_owned(),
+            tesseract_model,
+        });
+    }
+    Ok(entries)
+}
+
+/// Keep the entries of the requested languages, or all of them if none is requested.
+fn select_entries(
+    entries: Vec<ManifestEntry>,
+    languages: &[String],
+) -> Result<Vec<ManifestEntry>, ErrorType> {
+    if languages.is_empty() {
+        return Ok(entries);
+    }
+    let mut selected = HashSet::new();
+    for language in languages {
+        let matching: Vec<&str> = entries
+            .iter()
+            .map(|entry| entry.tesseract_model.language.as_str())
+            .filter(|entry_language| {
+                entry_language == language || tesseract_language(language) == Some(entry_language)
+            })
+            .collect();
+        if matching.is_empty() {
+            return Err(ErrorType::InvalidRequest(format!(
+                "Language '{language}' is not in the manifest"
+            )));
+        }
+        selected.extend(matching.into_iter().map(str::to_owned));
+    }
+    Ok(entries
+        .into_iter()
+        .filter(|entry| selected.contains(&entry.tesseract_model.language))
+        .collect())
+}
+
+/// Called with the path and the content of each file read from a source.
+type OnFile<'a> = dyn FnMut(&str, Vec<u8>) -> Result<(), ErrorType> + 'a;
+
+/// Reads the manifest and the models of a source.
+enum SourceReader {
+    Directory(PathBuf, String),
+    Http(reqwest::blocking::Client, String, String),
+    Zip(zip::ZipArchive<File>, String, String),
+    /// Tar files can only be read in order, so they are read once for the manifest and once
+    /// for the models.
+    Tar(PathBuf, String, String),
+}
+
+impl SourceReader {
+    fn open(source: &ModelSource, manifest: &str) -> Result<Self, ErrorType> {
+        validate_manifest_name(manifest)?;
+        let invalid = |error: &dyn fmt::Display| {
+            ErrorType::InvalidRequest(format!("Failed to open '{source}': {error}"))
+        };
+        Ok(match source {
+            ModelSource::Directory(path) => {
+                SourceReader::Directory(path.clone(), manifest.to_owned())
+            }
+            ModelSource::Http(url) => {
+                let client = reqwest::blocking::Client::builder()
+                    .connect_timeout(HTTP_CONNECT_TIMEOUT)
+                    // The blocking client applies this to each read of the response.
+                    .timeout(HTTP_READ_TIMEOUT)
+                    .build()
+                    .map_err(|error| invalid(&error))?;
+                SourceReader::Http(client, url.clone(), manifest.to_owned())
+            }
+            ModelSource::Archive(path)
+                if path.to_string_lossy().to_lowercase().ends_with(".zip") =>
+            {
+                let file = File::open(path).map_err(|error| invalid(&error))?;
+                let archive = zip::ZipArchive::new(file).map_err(|error| invalid(&error))?;
+                let names: Vec<&str> = archive.file_names().collect();
+                let root = archive_root(names, manifest).ok_or_else(|| {
+                    ErrorType::InvalidRequest(format!("'{source}' has no {manifest} manifest"))
+                })?;
+                SourceReader::Zip(archive, root, manifest.to_owned())
+            }
+            ModelSource::Archive(path) => {
+                let names = tar_entries(path)
+                    .and_then(|mut archive| {
+                        archive
+                            .entries()?
+                            .map(|entry| Ok(entry?.path()?.to_string_lossy().into_owned()))
+                            .collect::<std::io::Result<Vec<String>>>()
+                    })
+                    .map_err(|error| invalid(&error))?;
+                let root =
+                    archive_root(names.iter().map(String::as_str), manifest).ok_or_else(|| {
+                        ErrorType::InvalidRequest(format!("'{source}' has no {manifest} manifest"))
+                    })?;
+                SourceReader::Tar(path.clone(), root, manifest.to_owned())
+            }
+        })
+    }
+
+    fn read_manifest(&mut self) -> Result<String, ErrorType> {
+        let manifest = match self {
+            SourceReader::Directory(_, manifest)
+            | SourceReader::Http(_, _, manifest)
+            | SourceReader::Zip(_, _, manifest)
+            | SourceReader::Tar(_, _, manifest) => manifest.clone(),
+        };
+        let mut content = None;
+        self.read_files(&[&manifest], &mut |_, file_content| {
+            content = Some(file_content);
+            Ok(())
+        })?;
+        String::from_utf8(content.unwrap_or_default()).map_err(|_| {
+            ErrorType::InvalidRequest(format!("The manifest {manifest} is not valid UTF-8"))
+        })
+    }
+
+    /// Read the files at `paths`, relative to the root of the source, in any order.
+    fn read_files(&mut self, paths: &[&str], on_file: &mut OnFile<'_>) -> Result<(), ErrorType> {
+        let not_found =
+            |path: &str| ErrorType::InvalidRequest(format!("'{path}' not found in the source"));
+        match self {
+            SourceReader::Directory(root, _) => {
+                for path in paths {
+                    let content = File::open(root.join(path))
+                        .and_then(|file| read_limited(file, path))
+                        .map_err(|error| {
+                            ErrorType::InvalidRequest(format!("Failed to read '{path}': {error}"))
+                        })?;
+                    on_file(path, content)?;
+                }
+            }
+            SourceReader::Http(client, url, _) => {
+                for path in paths {
+                    let file_url = format!("{url}/{path}");
+                    let download_error = |error: &dyn fmt::Display| {
+                        ErrorType::InvalidRequest(format!(
+                            "Failed to download '{file_url}': {error}"
+                        ))
+                    };
+                    let response = client
+                        .get(&file_url)
+                        .send()
+                        .and_then(|response| response.error_for_status())
+                        .map_err(|error| download_error(&error))?;
+                    let content =
+                        read_limited(response, path).map_err(|error| download_error(&error))?;
+                    on_file(path, content)?;
+                }
+            }
+            SourceReader::Zip(archive, root, _) => {
+                for path in paths {
+                    let file = archive
+                        .by_name(&format!("{root}{path}"))
+                        .map_err(|_| not_found(path))?;
+                    let content = read_limited(file, path).map_err(|error| {
+                        ErrorType::InvalidRequest(format!("Failed to read '{path}': {error}"))
+                    })?;
+                    on_file(path, content)?;
+                }
+            }
+            SourceReader::Tar(archive_path, root, _) => {
+                let mut remaining: HashSet<&str> = paths.iter().copied().collect();
+                let read_error = |error: std::io::Error| {
+                    ErrorType::InvalidRequest(format!(
+                        "Failed to read '{}': {error}",
+                        archive_path.display()
+                    ))
+                };
+                let mut archive = tar_entries(archive_path).map_err(read_error)?;
+                for entry in archive.entries().map_err(read_error)? {
+                    let entry = entry.map_err(read_error)?;
+                    let name = entry
+                        .path()
+                        .map_err(read_error)?
+                        .to_string_lossy()
+                        .into_owned();
+                    let Some(path) = name
+                        .strip_prefix("./")
+                        .unwrap_or(&name)
+                        .strip_prefix(root.as_str())
+                        .and_then(|path| remaining.take(path))
+                    else {
+                        continue;
+                    };
+                    let content = read_limited(entry, path).map_err(read_error)?;
+                    on_file(path, content)?;
+                }
+                if let Some(path) = remaining.into_iter().next() {
+                    return Err(not_found(path));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The manifest is read from the root of the source, so it must be a plain file name.
+fn validate_manifest_name(manifest: &str) -> Result<(), ErrorType> {
+    let valid = !manifest.is_empty()
+        && manifest != "."
+        && manifest != ".."
+        && !manifest.contains(['/', '\\']);
+    if valid {
+        Ok(())
+    } else {
+        Err(ErrorType::InvalidRequest(format!(
+            "Invalid manifest '{manifest}', expected a file name"
+        )))
+    }
+}
+
+/// Read a file of a source, failing if it is larger than `MAX_FILE_SIZE`.
+fn read_limited(reader: impl Read, path: &str) -> std::io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    reader.take(MAX_FILE_SIZE + 1).read_to_end(&mut content)?;
+    if content.len() as u64 > MAX_FILE_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "'{path}' is larger than {} MiB",
+                MAX_FILE_SIZE / 1024 / 1024
+            ),
+        ));
+    }
+    Ok(content)
+}
+
+/// Open a tar file, decompressing it if it is gzipped.
+fn tar_entries(path: &Path) -> std::io::Result<tar::Archive<Box<dyn Read>>> {
+    let file = File::open(path)?;
+    let name = path.to_string_lossy().to_lowercase();
+    let reader: Box<dyn Read> = if name.ends_with(".gz") || name.ends_with(".tgz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// The directory of the archive that holds the manifest, as a prefix of the archive paths
+/// (e.g. `tessdata/`), preferring the shallowest one.
+fn archive_root<'a>(names: impl IntoIterator<Item = &'a str>, manifest: &str) -> Option<String> {
+    names
+        .into_iter()
+        .map(|name| name.strip_prefix("./").unwrap_or(name))
+        .filter_map(|name| {
+            if name == manifest {
+                Some(String::new())
+            } else {
+                name.strip_suffix(manifest)
+                    .filter(|prefix| prefix.ends_with('/'))
+                    .map(str::to_owned)
+            }
+        })
+        .min_by_key(|root| root.matches('/').count())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::Write as _;
+    use std::path::Path;
+
+    use sha2::{Digest, Sha256};
+
+    use crate::models::error::ErrorType;
+    use crate::utils::model_installer::{
+        DEFAULT_MANIFEST, ModelSource, install_models, parse_manifest,
+    };
+
+    const MODELS: [(&str, &[u8]); 3] = [
+        ("eng.traineddata", b"eng model"),
+        ("deu/deu_best.traineddata", b"deu best model"),
+        ("deu/deu_fast.traineddata", b"deu fast model"),
+    ];
+
+    fn create_manifest() -> String {
+        MODELS
+            .iter()
+            .map(|(path, content)| format!("{:x}  {path}\n", Sha256::digest(content)))
+            .collect::<String>()
+            + "0000000000000000000000000000000000000000000000000000000000000000  README.md\n"
+    }
+
+    fn create_mirror(root: &Path) {
+        fs::create_dir_all(root.join("deu")).unwrap();
+        for (path, content) in MODELS {
+            fs::write(root.join(path), content).unwrap();
+        }
+        fs::write(root.join(DEFAULT_MANIFEST), create_manifest()).unwrap();
+    }
+
+    fn installed_paths(installed: &[crate::models::languages::TesseractModel]) -> Vec<&str> {
+        let mut paths: Vec<&str> = installed
+            .iter()
+            .filter_map(|tesseract_model| tesseract_model.relative_path.as_deref())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_model_source_from_str() {
+        assert_eq!(
+            "https://mirror.example.com/tessdata/".parse(),
+            Ok(ModelSource::Http(
+                "https://mirror.example.com/tessdata".to_owned()
+            ))
+        );
+        assert_eq!(
+            "/srv/bundle.tar.gz".parse(),
+            Ok(ModelSource::Archive("/srv/bundle.tar.gz".into()))
+        );
+        assert_eq!(
+            "/srv/tessdata".parse(),
+            Ok(ModelSource::Directory("/srv/tessdata".into()))
+        );
+        assert!("".parse::<ModelSource>().is_err());
+    }
+
+    #[test]
+    fn test_install_models_from_directory() {
+        let mirror = tempfile::tempdir().unwrap();
+        create_mirror(mirror.path());
+        let data_path = tempfile::tempdir().unwrap();
+        let data_path = data_path.path().to_str().unwrap();
+        let source = ModelSource::Directory(mirror.path().to_owned());
+
+        let installed =
+            install_models(data_path, &source, DEFAULT_MANIFEST, &["de".to_owned()]).unwrap();
+        assert_eq!(
+            installed_paths(&installed),
+            ["deu/deu_best", "deu/deu_fast"]
+        );
+        assert_eq!(
+            fs::read(format!("{data_path}/deu/deu_best.traineddata")).unwrap(),
+            b"deu best model"
+        );
+        assert!(!fs::exists(format!("{data_path}/eng.traineddata")).unwrap());
+
+        let installed = install_models(data_path, &source, DEFAULT_MANIFEST, &[]).unwrap();
+        assert_eq!(installed.len(), 3);
+
+        let error =
+            install_models(data_path, &source, DEFAULT_MANIFEST, &["fra".to_owned()]).unwrap_err();
+        assert!(
+            matches!(error, ErrorType::InvalidRequest(message) if message == "Language 'fra' is not in the manifest")
+        );
+    }
+
+    #[test]
+    fn test_install_models_checksum_mismatch() {
+        let mirror = tempfile::tempdir().unwrap();
+        create_mirror(mirror.path());
+        fs::write(mirror.path().join("deu/deu_fast.traineddata"), b"tampered").unwrap();
+        let data_path = tempfile::tempdir().unwrap();
+
+        let error = install_models(
+            data_path.path().to_str().unwrap(),
+            &ModelSource::Directory(mirror.path().to_owned()),
+            DEFAULT_MANIFEST,
+            &[],
+        )
+        .unwrap_err();
+        assert!(
+            matches!(error, ErrorType::InvalidRequest(message) if message.contains("deu/deu_fast.traineddata"))
+        );
+        // Nothing is installed, not even the models that matched their checksum.
+        let mut files = Vec::new();
+        for entry in walkdir::WalkDir::new(data_path.path()) {
+            let entry = entry.unwrap();
+            if entry.file_type().is_file() {
+                files.push(entry.path().to_owned());
+            }
+        }
+        assert!(files.is_empty(), "{files:?}");
+    }
+
+    #[test]
+    fn test_install_models_rolls_back_on_failure() {
+        let mirror = tempfile::tempdir().unwrap();
+        create_mirror(mirror.path());
+        let data_path = tempfile::tempdir().unwrap();
+        fs::write(
+            data_path.path().join("eng.traineddata"),
+            b"previous eng model",
+        )
+        .unwrap();
+        // A directory in the slot of a model cannot be replaced by the model.
+        fs::create_dir_all(data_path.path().join("deu/deu_fast.traineddata")).unwrap();
+
+        let error = install_models(
+            data_path.path().to_str().unwrap(),
+            &ModelSource::Directory(mirror.path().to_owned()),
+            DEFAULT_MANIFEST,
+            &[],
+        )
+        .unwrap_err();
+        assert!(matches!(error, ErrorType::InternalError(_)));
+
+        // The models moved into place before the failure are put back.
+        assert_eq!(
+            fs::read(data_path.path().join("eng.traineddata")).unwrap(),
+            b"previous eng model"
+        );
+        let mut files = Vec::new();
+        for entry in walkdir::WalkDir::new(data_path.path()) {
+            let entry = entry.unwrap();
+            if entry.file_type().is_file() {
+                files.push(
+                    entry
+                        .path()
+                        .strip_prefix(data_path.path())
+                        .unwrap()
+                        .to_owned(),
+                );
+            }
+        }
+        assert_eq!(files, [Path::new("eng.traineddata")]);
+    }
+
+    #[test]
+    fn test_parse_manifest_errors_name_the_line_only() {
+        let manifest = "# checksums\n\nroot:x:0:0:root:/root:/bin/bash\n";
+        let error = parse_manifest("tessdata", manifest).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid request: Invalid manifest line 3: expected a checksum and a path"
+        );
+
+        let manifest = format!("{}  secret!/eng.traineddata\n", "0".repeat(64));
+        let error = parse_manifest("tessdata", &manifest).unwrap_err();
+        assert!(!error.to_string().contains("secret"), "{error}");
+    }
+
+    #[test]
+    fn test_install_models_manifest_is_a_file_name() {
+        let mirror = tempfile::tempdir().unwrap();
+        create_mirror(mirror.path());
+        let data_path = tempfile::tempdir().unwrap();
+        let source = ModelSource::Directory(mirror.path().join("deu"));
+
+        for manifest in ["../SHA256SUMS", "/etc/passwd", "..", ""] {
+            let error = install_models(data_path.path().to_str().unwrap(), &source, manifest, &[])
+                .unwrap_err();
+            assert!(
+                matches!(&error, ErrorType::InvalidRequest(message) if message.starts_with("Invalid manifest")),
+                "{manifest}: {error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_install_models_from_tar_gz() {
+        let mirror = tempfile::tempdir().unwrap();
+        create_mirror(mirror.path());
+        let bundle = tempfile::tempdir().unwrap();
+        let bundle_path = bundle.path().join("tessdata.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(&bundle_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        // Models before the manifest, in a subdirectory of the bundle.
+        for (path, _) in MODELS {
+            builder
+                .append_path_with_name(mirror.path().join(path), format!("tessdata/{path}"))
+                .unwrap();
+        }
+        builder
+            .append_path_with_name(
+                mirror.path().join(DEFAULT_MANIFEST),
+                format!("tessdata/{DEFAULT_MANIFEST}"),
+            )
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let data_path = tempfile::tempdir().unwrap();
+        let installed = install_models(
+            data_path.path().to_str().unwrap(),
+            &ModelSource::Archive(bundle_path),
+            DEFAULT_MANIFEST,
+            &["eng".to_owned()],
+        )
+        .unwrap();
+        assert_eq!(installed_paths(&installed), ["eng"]);
+        assert_eq!(
+            fs::read(data_path.path().join("eng.traineddata")).unwrap(),
+            b"eng model"
+        );
+    }
+
+    #[test]
+    fn test_install_models_from_zip() {
+        let bundle = tempfile::tempdir().unwrap();
+        let bundle_path = bundle.path().join("tessdata.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&bundle_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file(DEFAULT_MANIFEST, options).unwrap();
+        writer.write_all(create_manifest().as_bytes()).unwrap();
+        for (path, content) in MODELS {
+            writer.start_file(path, options).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let data_path = tempfile::tempdir().unwrap();
+        let installed = install_models(
+            data_path.path().to_str().unwrap(),
+            &ModelSource::Archive(bundle_path),
+            DEFAULT_MANIFEST,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            installed_paths(&installed),
+            ["deu/deu_best", "deu/deu_fast", "eng"]
+        );
+    }
+
+    #[test]
+    fn test_install_models_invalid_manifest() {
+        let mirror = tempfile::tempdir().unwrap();
+        let data_path = tempfile::tempdir().unwrap();
+        let data_path = data_path.path().to_str().unwrap();
+        let source = ModelSource::Directory(mirror.path().to_owned());
+
+        // No manifest.
+        assert!(install_models(data_path, &source, DEFAULT_MANIFEST, &[]).is_err());
+
+        for manifest in [
+            "not-a-checksum  eng.traineddata",
+            &format!("{}  ../eng.traineddata", "0".repeat(64)),
+            &format!("{}  a/b/c.traineddata", "0".repeat(64)),
+        ] {
+            fs::write(mirror.path().join(DEFAULT_MANIFEST), manifest).unwrap();
+            assert!(
+                matches!(
+                    install_models(data_path, &source, DEFAULT_MANIFEST, &[]),
+                    Err(ErrorType::InvalidRequest(_))
+                ),
+                "{manifest}"
+            );
+        }
+    }
+}
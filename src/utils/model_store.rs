@@ -0,0 +1,387 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+use crate::models::{error::ErrorType, languages::TesseractModel};
+use crate::utils::ocr::load_model;
+
+const TRAINEDDATA_EXTENSION: &str = "traineddata";
+
+/// The longest language or model name accepted, well above the names of the official models.
+const MAX_NAME_LENGTH: usize = 64;
+
+/// The model stored in a slot of the data path, following the layout read by
+/// `get_available_languages_with_models`: `<language>.traineddata` without a model, or
+/// `<language>/<model>.traineddata` with one.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If the language or model is not a valid file name.
+pub fn model_slot(
+    data_path: &str,
+    language: &str,
+    model: Option<&str>,
+) -> Result<TesseractModel, ErrorType> {
+    validate_name("language", language)?;
+    let relative_path = match model {
+        Some(model) => {
+            validate_name("model", model)?;
+            format!("{language}/{model}")
+        }
+        None => language.to_owned(),
+    };
+    Ok(TesseractModel {
+        language: language.to_owned(),
+        model: model.map(str::to_owned),
+        metadata: None,
+        full_path: Some(
+            traineddata_path(data_path, &relative_path)
+                .to_string_lossy()
+                .to_string(),
+        ),
+        relative_path: Some(relative_path),
+    })
+}
+
+/// Store a model in its slot, replacing the previous one. The model is written to a hidden file
+/// next to its slot and only moved into place once Tesseract has loaded it, so an invalid upload
+/// never replaces a working model.
+///
+/// Returns whether a previous model was replaced.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If Tesseract cannot load the model.
+/// - `InternalError`: If the model cannot be written to the data path.
+pub fn install_model(
+    data_path: &str,
+    tesseract_model: &TesseractModel,
+    content: &[u8],
+) -> Result<bool, ErrorType> {
+    let staged_model = stage_model(data_path, tesseract_model, content)?;
+    if let Err(error) = load_model(data_path, &staged_model.upload_model) {
+        tracing::warn!(
+            "Rejected uploaded model {}: {error:?}",
+            staged_model.target.display()
+        );
+        return Err(ErrorType::InvalidRequest(
+            "The file is not a Tesseract model that can be loaded".to_owned(),
+        ));
+    }
+    staged_model.commit()
+}
+
+/// A model written next to its slot under a hidden name, until it is moved into place by
+/// `commit`. The hidden file is removed if the model is dropped without being committed.
+#[derive(Debug)]
+pub struct StagedModel {
+    upload: PathBuf,
+    target: PathBuf,
+    upload_model: TesseractModel,
+    committed: bool,
+}
+
+impl StagedModel {
+    /// Move the model into its slot, replacing the previous one.
+    ///
+    /// Returns whether a previous model was replaced.
+    ///
+    /// # Errors
+    ///
+    /// - `InternalError`: If the model cannot be moved into its slot.
+    pub fn commit(mut self) -> Result<bool, ErrorType> {
+        let replaced = self.target.is_file();
+        fs::rename(&self.upload, &self.target).map_err(|error| {
+            ErrorType::InternalError(anyhow::Error::new(error).context(format!(
+                "Failed to move the model to '{}'",
+                self.target.display()
+            )))
+        })?;
+        self.committed = true;
+        Ok(replaced)
+    }
+
+    /// Move the model into its slot, keeping the previous model under a hidden name.
+    fn replace(mut self) -> Result<ReplacedModel, ErrorType> {
+        let move_error = |error: io::Error, path: &Path| {
+            ErrorType::InternalError(
+                anyhow::Error::new(error)
+                    .context(format!("Failed to move the model to '{}'", path.display())),
+            )
+        };
+        let backup = if self.target.is_file() {
+            let backup = self.target.with_file_name(format!(
+                ".backup-{}.{TRAINEDDATA_EXTENSION}",
+                uuid::Uuid::now_v7()
+            ));
+            fs::rename(&self.target, &backup).map_err(|error| move_error(error, &backup))?;
+            Some(backup)
+        } else {
+            None
+        };
+        if let Err(error) = fs::rename(&self.upload, &self.target) {
+            if let Some(backup) = &backup {
+                let _ = fs::rename(backup, &self.target);
+            }
+            return Err(move_error(error, &self.target));
+        }
+        self.committed = true;
+        Ok(ReplacedModel {
+            target: self.target.clone(),
+            backup,
+        })
+    }
+}
+
+impl Drop for StagedModel {
+    fn drop(&mut self) {
+        if !self.committed {
+            remove_file(&self.upload);
+        }
+    }
+}
+
+/// A model moved into its slot by `commit_models`, with the model it replaced moved aside until
+/// every model is in place.
+#[derive(Debug)]
+struct ReplacedModel {
+    target: PathBuf,
+    backup: Option<PathBuf>,
+}
+
+impl ReplacedModel {
+    /// Put the previous model (or no model) back into the slot.
+    fn roll_back(self) {
+        match &self.backup {
+            Some(backup) => {
+                if let Err(error) = fs::rename(backup, &self.target) {
+                    tracing::warn!(
+                        "Failed to restore '{}' from '{}': {error}",
+                        self.target.display(),
+                        backup.display()
+                    );
+                }
+            }
+            None => remove_file(&self.target),
+        }
+    }
+
+    fn finish(self) {
+        if let Some(backup) = &self.backup {
+            remove_file(backup);
+        }
+    }
+}
+
+/// Move staged models into their slots, all or none: when a model cannot be moved, the models
+/// moved before it are put back, so the data path is left as it was.
+///
+/// # Errors
+///
+/// - `InternalError`: If a model cannot be moved into its slot.
+pub fn commit_models(staged_models: Vec<StagedModel>) -> Result<(), ErrorType> {
+    let mut replaced_models = Vec::new();
+    for staged_model in staged_models {
+        match staged_model.replace() {
+            Ok(replaced_model) => replaced_models.push(replaced_model),
+            Err(error) => {
+                replaced_models
+                    .into_iter()
+                    .rev()
+                    .for_each(ReplacedModel::roll_back);
+                return Err(error);
+            }
+        }
+    }
+    replaced_models.into_iter().for_each(ReplacedModel::finish);
+    Ok(())
+}
+
+/// Write a model next to its slot under a hidden name, without replacing the model in the slot.
+///
+/// # Errors
+///
+/// - `InternalError`: If the model cannot be written to the data path.
+pub fn stage_model(
+    data_path: &str,
+    tesseract_model: &TesseractModel,
+    content: &[u8],
+) -> Result<StagedModel, ErrorType> {
+    let relative_path = tesseract_model.relative_path.clone().unwrap_or_default();
+    let target = traineddata_path(data_path, &relative_path);
+    let directory = target.parent().unwrap_or(Path::new(data_path));
+    fs::create_dir_all(directory)
+        .with_context(|| format!("Failed to create '{}'", directory.display()))?;
+
+    // Hidden files are ignored when scanning the data path, so the upload is never listed.
+    let upload_name = format!(".upload-{}", uuid::Uuid::now_v7());
+    let upload_relative_path = match relative_path.rsplit_once('/') {
+        Some((language, _)) => format!("{language}/{upload_name}"),
+        None => upload_name,
+    };
+    let upload = traineddata_path(data_path, &upload_relative_path);
+    let staged_model = StagedModel {
+        upload,
+        target,
+        upload_model: TesseractModel {
+            relative_path: Some(upload_relative_path),
+            ..tesseract_model.clone()
+        },
+        committed: false,
+    };
+    fs::write(&staged_model.upload, content)
+        .with_context(|| format!("Failed to write '{}'", staged_model.upload.display()))?;
+    Ok(staged_model)
+}
+
+/// Delete the model in a slot, and its language directory once it has no models left.
+///
+/// # Errors
+///
+/// - `NotFound`: If there is no model in the slot.
+/// - `InternalError`: If the model cannot be deleted.
+pub fn delete_model(data_path: &str, tesseract_model: &TesseractModel) -> Result<(), ErrorType> {
+    let relative_path = tesseract_model.relative_path.clone().unwrap_or_default();
+    let target = traineddata_path(data_path, &relative_path);
+    match fs::remove_file(&target) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Err(ErrorType::NotFound(format!(
+                "Model '{relative_path}' not found"
+            )));
+        }
+        Err(error) => {
+            return Err(ErrorType::InternalError(
+                anyhow::Error::new(error)
+                    .context(format!("Failed to delete '{}'", target.display())),
+            ));
+        }
+    }
+    if tesseract_model.model.is_some() {
+        if let Some(directory) = target.parent() {
+            // Only succeeds if the directory is empty.
+            let _ = fs::remove_dir(directory);
+        }
+    }
+    Ok(())
+}
+
+fn traineddata_path(data_path: &str, relative_path: &str) -> PathBuf {
+    Path::new(data_path).join(format!("{relative_path}.{TRAINEDDATA_EXTENSION}"))
+}
+
+/// Names become file and directory names, so they are restricted to the characters used by the
+/// official models to rule out path traversal and hidden files.
+fn validate_name(kind: &str, name: &str) -> Result<(), ErrorType> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(ErrorType::InvalidRequest(format!(
+            "Invalid {kind} '{name}', only letters, digits, '_' and '-' are allowed"
+        )))
+    }
+}
+
+fn remove_file(path: &Path) {
+    if let Err(error) = fs::remove_file(path) {
+        tracing::warn!("Failed to remove '{}': {error}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::models::error::ErrorType;
+    use crate::utils::model_store::{delete_model, model_slot, stage_model};
+
+    #[test]
+    fn test_model_slot_layout() {
+        let slot = model_slot("tessdata", "deu", None).unwrap();
+        assert_eq!(slot.relative_path.as_deref(), Some("deu"));
+        assert_eq!(slot.full_path.as_deref(), Some("tessdata/deu.traineddata"));
+
+        let slot = model_slot("tessdata", "chi_sim", Some("chi_sim_vert")).unwrap();
+        assert_eq!(slot.relative_path.as_deref(), Some("chi_sim/chi_sim_vert"));
+        assert_eq!(
+            slot.full_path.as_deref(),
+            Some("tessdata/chi_sim/chi_sim_vert.traineddata")
+        );
+    }
+
+    #[test]
+    fn test_model_slot_rejects_paths() {
+        for (language, model) in [
+            ("../etc", None),
+            ("deu", Some("../../passwd")),
+            (".hidden", None),
+            ("", None),
+            ("deu", Some("deu/best")),
+        ] {
+            assert!(
+                matches!(
+                    model_slot("tessdata", language, model),
+                    Err(ErrorType::InvalidRequest(_))
+                ),
+                "{language} {model:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_delete_model_removes_empty_language_directory() {
+        let tessdata = tempfile::tempdir().unwrap();
+        let data_path = tessdata.path().to_string_lossy().to_string();
+        fs::create_dir(tessdata.path().join("deu")).unwrap();
+        fs::write(tessdata.path().join("deu/deu_best.traineddata"), b"").unwrap();
+        fs::write(tessdata.path().join("deu/deu_fast.traineddata"), b"").unwrap();
+
+        delete_model(
+            &data_path,
+            &model_slot(&data_path, "deu", Some("deu_best")).unwrap(),
+        )
+        .unwrap();
+        assert!(tessdata.path().join("deu").is_dir());
+        delete_model(
+            &data_path,
+            &model_slot(&data_path, "deu", Some("deu_fast")).unwrap(),
+        )
+        .unwrap();
+        assert!(!tessdata.path().join("deu").exists());
+
+        assert!(matches!(
+            delete_model(&data_path, &model_slot(&data_path, "deu", None).unwrap()),
+            Err(ErrorType::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_staged_model_is_only_listed_once_committed() {
+        let data_path = tempfile::tempdir().unwrap();
+        let data_path = data_path.path().to_str().unwrap();
+        let slot = model_slot(data_path, "deu", Some("deu_best")).unwrap();
+        let target = slot.full_path.clone().unwrap();
+
+        let staged_model = stage_model(data_path, &slot, b"model").unwrap();
+        assert!(!fs::exists(&target).unwrap());
+        drop(staged_model);
+        // The hidden file is removed when the model is not committed.
+        assert_eq!(fs::read_dir(format!("{data_path}/deu")).unwrap().count(), 0);
+
+        let staged_model = stage_model(data_path, &slot, b"model").unwrap();
+        assert!(!staged_model.commit().unwrap());
+        assert_eq!(fs::read(&target).unwrap(), b"model");
+        assert_eq!(fs::read_dir(format!("{data_path}/deu")).unwrap().count(), 1);
+
+        let staged_model = stage_model(data_path, &slot, b"new model").unwrap();
+        assert!(staged_model.commit().unwrap());
+        assert_eq!(fs::read(&target).unwrap(), b"new model");
+    }
+}
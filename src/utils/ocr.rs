@@ -0,0 +1,248 @@
+use std::io::Cursor;
+use std::os::raw::{c_int, c_void};
+use std::path::Path;
+use std::time::Instant;
+
+use image::{DynamicImage, ImageReader};
+use opentelemetry::KeyValue;
+use tesseract_rs::TesseractAPI;
+
+use crate::models::{error::ErrorType, languages::TesseractModel};
+use crate::utils::metrics::{model_attributes, ocr_metrics, record_duration};
+
+const BYTES_PER_PIXEL: u32 = 3;
+
+/// Decode the raw bytes of an uploaded file into an image.
+///
+/// The `attributes` label the decode metrics, see `model_attributes`.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If the format cannot be guessed or the image cannot be decoded.
+#[tracing::instrument(name = "ocr.decode", skip_all, fields(bytes = file_content.len()))]
+pub fn decode_image(
+    file_content: &[u8],
+    attributes: &[KeyValue],
+) -> Result<DynamicImage, ErrorType> {
+    let started_at = Instant::now();
+    let img = ImageReader::new(Cursor::new(file_content))
+        .with_guessed_format()
+        .map_err(|error| ErrorType::InvalidRequest(error.to_string()))?
+        .decode()
+        .map_err(|image_error| ErrorType::InvalidRequest(image_error.to_string()))?;
+    record_duration(
+        &ocr_metrics().decode_duration,
+        started_at.elapsed(),
+        attributes,
+    );
+    Ok(img)
+}
+
+/// Instantiate the Tesseract API and load the given Tesseract model into it.
+///
+/// # Errors
+///
+/// - `InternalError`: If the model cannot be loaded from the data path.
+pub fn load_model(
+    data_path: &str,
+    tesseract_model: &TesseractModel,
+) -> Result<TesseractAPI, ErrorType> {
+    let language_model_path = tesseract_model.relative_path.clone().unwrap_or_default();
+    let tesseract_api = TesseractAPI::new();
+    tesseract_api
+        .init(data_path, language_model_path.as_str())
+        .map_err(|tess_error| {
+            ErrorType::InternalError(anyhow::anyhow!(
+                "Something went wrong while performing OCR: {tess_error}"
+            ))
+        })?;
+    Ok(tesseract_api)
+}
+
+/// Perform OCR on an image with the given Tesseract model.
+///
+/// When a `ProgressMonitor` is given, Tesseract reports its recognition progress to it so that
+/// another thread can poll it while this (blocking) call is running.
+///
+/// # Errors
+///
+/// - `InvalidRequest`: If the image dimensions are too large or the text cannot be extracted.
+/// - `InternalError`: If something goes wrong while creating or using the OCR Engine.
+#[tracing::instrument(
+    name = "ocr.recognize_image",
+    skip_all,
+    fields(language = %tesseract_model.language, model = ?tesseract_model.model)
+)]
+pub fn recognize_image(
+    data_path: &str,
+    tesseract_model: &TesseractModel,
+    img: &DynamicImage,
+    monitor: Option<&ProgressMonitor>,
+) -> Result<String, ErrorType> {
+    let metrics = ocr_metrics();
+    let attributes = model_attributes(tesseract_model);
+    let resource_path = Path::new(data_path);
+
+    // Convert the image to RGB8 and gather image dimensions for Tesseract
+    let preprocess_span = tracing::info_span!("ocr.preprocess").entered();
+    let started_at = Instant::now();
+    let rgb_image = img.to_rgb8();
+    let (width, height) = rgb_image.dimensions();
+    let bytes_per_line = (width * BYTES_PER_PIXEL).try_into().map_err(|error| {
+        ErrorType::InvalidRequest(format!("Image dimensions are too large: {error}"))
+    })?;
+    let raw_image_data = rgb_image.into_raw();
+    record_duration(
+        &metrics.preprocess_duration,
+        started_at.elapsed(),
+        &attributes,
+    );
+    metrics.image_megapixels.record(
+        f64::from(width) * f64::from(height) / 1_000_000.0,
+        &attributes,
+    );
+    preprocess_span.exit();
+
+    let language_model_path = tesseract_model.relative_path.clone().unwrap_or_default();
+    tracing::debug!(
+        "Initializing Tesseract API with path: {} and language: {}",
+        resource_path.to_str().unwrap_or_default(),
+        language_model_path
+    );
+    let engine_init_span = tracing::info_span!("ocr.engine_init").entered();
+    let started_at = Instant::now();
+    let tesseract_api = load_model(data_path, tesseract_model)?;
+    record_duration(
+        &metrics.engine_init_duration,
+        started_at.elapsed(),
+        &attributes,
+    );
+    engine_init_span.exit();
+
+    let recognition_span = tracing::info_span!("ocr.recognition").entered();
+    let started_at = Instant::now();
+    tesseract_api
+        .set_image(
+            &raw_image_data,
+            width.try_into().map_err(|error| {
+                ErrorType::InvalidRequest(format!("Image width is too large: {error}"))
+            })?,
+            height.try_into().map_err(|error| {
+                ErrorType::InvalidRequest(format!("Image height is too large: {error}"))
+            })?,
+            BYTES_PER_PIXEL.try_into().unwrap(),
+            bytes_per_line,
+        )
+        .map_err(|tess_error| {
+            ErrorType::InternalError(anyhow::anyhow!(
+                "Something went wrong while processing the image: {tess_error}"
+            ))
+        })?;
+
+    if let Some(monitor) = monitor {
+        monitor.recognize(&tesseract_api)?;
+    }
+
+    let text = tesseract_api.get_utf8_text().map_err(|tess_error| {
+        ErrorType::InvalidRequest(format!(
+            "Something went wrong while extracting the text: {tess_error}"
+        ))
+    })?;
+    record_duration(
+        &metrics.recognition_duration,
+        started_at.elapsed(),
+        &attributes,
+    );
+    recognition_span.exit();
+
+    metrics
+        .output_characters
+        .record(text.chars().count() as u64, &attributes);
+    if let Ok(confidence) = tesseract_api.mean_text_conf() {
+        if confidence >= 0 {
+            metrics
+                .mean_confidence
+                .record(f64::from(confidence), &attributes);
+        }
+    }
+
+    Ok(text)
+}
+
+/// Tesseract's progress monitor (`ETEXT_DESC`).
+///
+/// `tesseract_rs::TesseractAPI::recognize` always passes a null monitor, so recognition with
+/// progress reporting goes through the C API directly using the handle the bindings expose.
+pub struct ProgressMonitor {
+    /// Never null, owned by the monitor and deleted when it is dropped.
+    handle: *mut c_void,
+}
+
+// SAFETY: The `ETEXT_DESC` behind the handle is a plain heap allocation that is not tied to the
+// thread that created it. Tesseract only writes to it inside `TessBaseAPIRecognize`, which is
+// called through `recognize` while the monitor is borrowed, and the other threads only read its
+// progress through `progress` (see there). It is deleted once, in `drop`, when no borrow is left.
+unsafe impl Send for ProgressMonitor {}
+// SAFETY: See `Send`; `&ProgressMonitor` only allows `recognize` and `progress`.
+unsafe impl Sync for ProgressMonitor {}
+
+impl ProgressMonitor {
+    /// Create a monitor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InternalError` if Tesseract fails to allocate the monitor.
+    pub fn new() -> Result<Self, ErrorType> {
+        // SAFETY: `TessMonitorCreate` has no preconditions and returns an owned monitor.
+        let handle = unsafe { TessMonitorCreate() };
+        if handle.is_null() {
+            return Err(ErrorType::InternalError(anyhow::anyhow!(
+                "Failed to create the Tesseract progress monitor"
+            )));
+        }
+        Ok(Self { handle })
+    }
+
+    /// The recognition progress of the current image in percent (0-100).
+    ///
+    /// Called from other threads while Tesseract recognizes the image. The progress is a single
+    /// aligned integer that Tesseract only ever stores whole values in, so a concurrent read
+    /// returns either the previous or the current progress, and a stale value is only reported
+    /// until the next poll.
+    #[must_use]
+    pub fn progress(&self) -> u8 {
+        // SAFETY: The handle is a valid monitor until `drop`, see `new`.
+        let progress = unsafe { TessMonitorGetProgress(self.handle) };
+        progress.clamp(0, 100) as u8
+    }
+
+    fn recognize(&self, tesseract_api: &TesseractAPI) -> Result<(), ErrorType> {
+        let handle = tesseract_api.handle.lock().map_err(|_| {
+            ErrorType::InternalError(anyhow::anyhow!("Failed to lock the Tesseract API handle"))
+        })?;
+        // SAFETY: The API handle is valid and locked for as long as `handle` is held, and the
+        // monitor is valid until `drop`, which cannot run while it is borrowed here.
+        let result = unsafe { TessBaseAPIRecognize(*handle, self.handle) };
+        if result != 0 {
+            return Err(ErrorType::InternalError(anyhow::anyhow!(
+                "Something went wrong while recognizing the image"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ProgressMonitor {
+    fn drop(&mut self) {
+        // SAFETY: The handle was created by `TessMonitorCreate`, is not null and is only
+        // deleted here.
+        unsafe { TessMonitorDelete(self.handle) };
+    }
+}
+
+extern "C" {
+    fn TessMonitorCreate() -> *mut c_void;
+    fn TessMonitorDelete(monitor: *mut c_void);
+    fn TessMonitorGetProgress(monitor: *mut c_void) -> c_int;
+    fn TessBaseAPIRecognize(handle: *mut c_void, monitor: *mut c_void) -> c_int;
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::app_config::RateLimitConfig;
+
+/// The number of clients tracked before the idle ones are forgotten.
+const MAX_IDLE_CLIENTS: usize = 10_000;
+
+/// Token-bucket rate limits and concurrent recognition limits of each client.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    clients: Arc<Mutex<HashMap<String, ClientQuota>>>,
+}
+
+#[derive(Debug)]
+struct ClientQuota {
+    tokens: f64,
+    refilled_at: Instant,
+    running: usize,
+}
+
+/// A request rejected by the limits of its client, with the values of the `Retry-After` and
+/// `RateLimit-*` headers.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct RateLimited {
+    pub message: String,
+    /// The value of `RateLimit-Limit`: the size of the bucket, or the concurrent recognitions.
+    pub limit: u64,
+    /// The value of `RateLimit-Remaining`.
+    pub remaining: u64,
+    /// When the request can be retried, the value of `Retry-After` and `RateLimit-Reset`.
+    pub retry_after: Duration,
+}
+
+impl RateLimited {
+    /// `retry_after` in whole seconds, rounded up so clients do not retry before the limit is
+    /// reset.
+    #[must_use]
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after.as_secs() + u64::from(self.retry_after.subsec_nanos() > 0)
+    }
+}
+
+/// A running recognition of a client, counted against its concurrent recognitions until it is
+/// dropped.
+#[derive(Debug)]
+pub struct RecognitionPermit {
+    quota: Option<(RateLimiter, String)>,
+    /// Whether the token taken with the permit still has to pay for a frame.
+    prepaid: bool,
+}
+
+impl RecognitionPermit {
+    /// A permit that is not counted, when rate limiting is disabled.
+    #[must_use]
+    pub fn unlimited() -> Self {
+        Self {
+            quota: None,
+            prepaid: false,
+        }
+    }
+
+    /// Take a token from the bucket of the client for every frame of a stream the permit is
+    /// held for. The first frame is paid for by the token taken with the permit.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RateLimited` if the bucket is empty.
+    pub fn take_token(&mut self) -> Result<(), RateLimited> {
+        if std::mem::take(&mut self.prepaid) {
+            return Ok(());
+        }
+        match &self.quota {
+            Some((rate_limiter, client)) => rate_limiter.take_token(client),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for RecognitionPermit {
+    fn drop(&mut self) {
+        if let Some((rate_limiter, client)) = &self.quota {
+            let mut clients = rate_limiter.clients.lock().unwrap();
+            if let Some(quota) = clients.get_mut(client) {
+                quota.running = quota.running.saturating_sub(1);
+            }
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Create the rate limiter, or `None` when rate limiting is disabled.
+    #[must_use]
+    pub fn new(config: &RateLimitConfig) -> Option<Self> {
+        config.enabled.then(|| Self {
+            config: config.clone(),
+            clients: Arc::default(),
+        })
+    }
+
+    /// Take a token from the bucket of `client` and start a recognition.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RateLimited` if the bucket is empty or the client runs the maximum number of
+    /// recognitions. The token is not taken then.
+    pub fn acquire(&self, client: &str) -> Result<RecognitionPermit, RateLimited> {
+        self.acquire_at(client, Instant::now())
+    }
+
+    /// Take a token from the bucket of `client` for a frame of a stream, which already counts
+    /// as one recognition.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RateLimited` if the bucket is empty.
+    pub fn take_token(&self, client: &str) -> Result<(), RateLimited> {
+        self.take_token_at(client, Instant::now())
+    }
+
+    fn acquire_at(&self, client: &str, now: Instant) -> Result<RecognitionPermit, RateLimited> {
+        let mut clients = self.clients.lock().unwrap();
+        let quota = self.refill(&mut clients, client, now);
+        if quota.running >= self.config.max_concurrent {
+            return Err(RateLimited {
+                message: format!(
+                    "Too many concurrent recognitions, at most {} at a time",
+                    self.config.max_concurrent
+                ),
+                limit: self.config.max_concurrent as u64,
+                remaining: 0,
+                retry_after: Duration::from_secs(1),
+            });
+        }
+        self.take(quota)?;
+        quota.running += 1;
+        Ok(RecognitionPermit {
+            quota: Some((self.clone(), client.to_owned())),
+            prepaid: true,
+        })
+    }
+
+    fn take_token_at(&self, client: &str, now: Instant) -> Result<(), RateLimited> {
+        let mut clients = self.clients.lock().unwrap();
+        let quota = self.refill(&mut clients, client, now);
+        self.take(quota)
+    }
+
+    /// The quota of `client`, with the tokens added back since it was last used.
+    fn refill<'a>(
+        &self,
+        clients: &'a mut HashMap<String, ClientQuota>,
+        client: &str,
+        now: Instant,
+    ) -> &'a mut ClientQuota {
+        let burst = f64::from(self.config.burst);
+        let tokens_per_second = self.tokens_per_second();
+        if clients.len() >= MAX_IDLE_CLIENTS && !clients.contains_key(client) {
+            // Clients with a full bucket and no recognitions are the same as new clients.
+            clients.retain(|_, quota| {
+                quota.running > 0
+                    || quota.tokens
+                        + now.duration_since(quota.refilled_at).as_secs_f64() * tokens_per_second
+                        < burst
+            });
+        }
+        let quota = clients.entry(client.to_owned()).or_insert(ClientQuota {
+            tokens: burst,
+            refilled_at: now,
+            running: 0,
+        });
+        quota.tokens = (quota.tokens
+            + now.duration_since(quota.refilled_at).as_secs_f64() * tokens_per_second)
+            .min(burst);
+        quota.refilled_at = now;
+        quota
+    }
+
+    /// Take a token from the bucket of a quota, if it is not empty.
+    fn take(&self, quota: &mut ClientQuota) -> Result<(), RateLimited> {
+        if quota.tokens < 1.0 {
+            return Err(RateLimited {
+                message: format!(
+                    "Too many requests, at most {} per minute",
+                    self.config.requests_per_minute
+                ),
+                limit: u64::from(self.config.burst),
+                remaining: 0,
+                retry_after: Duration::from_secs_f64(
+                    (1.0 - quota.tokens) / self.tokens_per_second(),
+                ),
+            });
+        }
+        quota.tokens -= 1.0;
+        Ok(())
+    }
+
+    fn tokens_per_second(&self) -> f64 {
+        f64::from(self.config.requests_per_minute) / 60.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::config::app_config::{RateLimitConfig, RateLimitKey};
+    use crate::utils::rate_limit::RateLimiter;
+
+    fn create_test_rate_limiter() -> RateLimiter {
+        RateLimiter::new(&RateLimitConfig {
+            enabled: true,
+            requests_per_minute: 60,
+            burst: 2,
+            max_concurrent: 2,
+            key: RateLimitKey::Client,
+            trust_forwarded_for: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let rate_limiter = create_test_rate_limiter();
+        let now = Instant::now();
+
+        drop(rate_limiter.acquire_at("client", now).unwrap());
+        drop(rate_limiter.acquire_at("client", now).unwrap());
+        let rate_limited = rate_limiter.acquire_at("client", now).unwrap_err();
+        assert_eq!(rate_limited.limit, 2);
+        assert_eq!(rate_limited.retry_after, Duration::from_secs(1));
+        // Other clients have their own bucket.
+        assert!(rate_limiter.acquire_at("other", now).is_ok());
+
+        // One token per second is added back.
+        let later = now + Duration::from_millis(1500);
+        drop(rate_limiter.acquire_at("client", later).unwrap());
+        let rate_limited = rate_limiter.acquire_at("client", later).unwrap_err();
+        assert_eq!(rate_limited.retry_after, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_concurrent_recognitions() {
+        let rate_limiter = create_test_rate_limiter();
+        let now = Instant::now();
+
+        let first = rate_limiter.acquire_at("client", now).unwrap();
+        let _second = rate_limiter.acquire_at("client", now).unwrap();
+        let rate_limited = rate_limiter
+            .acquire_at("client", now + Duration::from_secs(60))
+            .unwrap_err();
+        assert!(rate_limited.message.contains("concurrent"));
+
+        drop(first);
+        assert!(
+            rate_limiter
+                .acquire_at("client", now + Duration::from_secs(60))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_stream_tokens() {
+        let rate_limiter = create_test_rate_limiter();
+        let now = Instant::now();
+
+        // The stream holds a permit, whose token pays for the first frame, and each of the other
+        // frames takes a token of the same bucket.
+        let mut permit = rate_limiter.acquire_at("client", now).unwrap();
+        permit.take_token().unwrap();
+        rate_limiter.take_token_at("client", now).unwrap();
+        let rate_limited = rate_limiter.take_token_at("client", now).unwrap_err();
+        assert_eq!(rate_limited.retry_after, Duration::from_secs(1));
+        assert_eq!(rate_limited.retry_after_secs(), 1);
+        assert!(
+            rate_limiter
+                .take_token_at("client", now + Duration::from_secs(1))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_disabled() {
+        let config = RateLimitConfig {
+            enabled: false,
+            ..create_test_rate_limiter().config
+        };
+        assert!(RateLimiter::new(&config).is_none());
+    }
+}
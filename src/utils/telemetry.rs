@@ -1,10 +1,16 @@
+use std::collections::HashMap;
 use std::time::Duration;
 
-use crate::config::app_config::AppConfig;
+use crate::config::app_config::{
+    AppConfig, LogFormat, MetricsExporter, OtlpProtocol, TracesSampler,
+};
+use crate::utils::log_format::JsonLogFormat;
 use anyhow::Error;
 use opentelemetry::{self, KeyValue, global, trace::TracerProvider};
 use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
-use opentelemetry_otlp::{LogExporter, SpanExporter, WithExportConfig, WithTonicConfig};
+use opentelemetry_otlp::{
+    LogExporter, Protocol, SpanExporter, WithExportConfig, WithHttpConfig, WithTonicConfig,
+};
 use opentelemetry_sdk::{
     Resource,
     metrics::Temporality,
@@ -15,18 +21,27 @@ use opentelemetry_semantic_conventions::{
     resource::{DEPLOYMENT_ENVIRONMENT_NAME, SERVICE_NAME},
     trace::{SERVER_ADDRESS, SERVER_PORT},
 };
-use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
 use tracing_subscriber::{
-    EnvFilter, Layer, Registry, fmt::format::FmtSpan, layer::SubscriberExt as _,
+    EnvFilter, Layer, Registry,
+    fmt::format::{FmtSpan, JsonFields},
+    layer::SubscriberExt as _,
 };
 
-const OTEL_PROVIDER_OPENOBSERVE: &str = "openobserve";
-
 #[must_use = "Recommend holding with 'let _guard = ' pattern to ensure the final telemetry data is sent to the server"]
 pub struct OtelGuard {
     tracer_provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
     logging_provider: Option<opentelemetry_sdk::logs::SdkLoggerProvider>,
     meter_provider: Option<opentelemetry_sdk::metrics::SdkMeterProvider>,
+    prometheus_registry: Option<prometheus::Registry>,
+}
+
+impl OtelGuard {
+    /// The registry to serve on `/system/metrics`, when metrics are exported to Prometheus.
+    #[must_use]
+    pub fn prometheus_registry(&self) -> Option<prometheus::Registry> {
+        self.prometheus_registry.clone()
+    }
 }
 
 impl Drop for OtelGuard {
@@ -74,15 +89,26 @@ pub async fn initialize_opentelemetry_providers(
         tracing::subscriber::set_global_default(subscriber)
             .expect("Could not set up global logger");
 
+        // Prometheus is scraped, so it does not need a collector and works without OTLP.
+        let (meter_provider, prometheus_registry) =
+            if app_config.otel.metrics_exporter == MetricsExporter::Prometheus {
+                let (meter_provider, registry) = init_meter_provider(app_config)?;
+                (Some(meter_provider), registry)
+            } else {
+                (None, None)
+            };
+
         return Ok(OtelGuard {
             tracer_provider: None,
             logging_provider: None,
-            meter_provider: None,
+            meter_provider,
+            prometheus_registry,
         });
     }
+    app_config.otel.validate()?;
     tracing::info!(
         "Initializing OpenTelemetry with traces endpoint: {}",
-        app_config.otel.traces_endpoint.as_ref().unwrap()
+        otlp_endpoint(&app_config.otel.traces_endpoint)
     );
 
     global::set_text_map_propagator(TraceContextPropagator::new());
@@ -103,7 +129,7 @@ pub async fn initialize_opentelemetry_providers(
     let stdout_fmt_layer = stdout_layer(app_config);
 
     // Initialize OpenTelemetry Metrics provider
-    let meter_provider = init_meter_provider(app_config)?;
+    let (meter_provider, prometheus_registry) = init_meter_provider(app_config)?;
 
     let subscriber = Registry::default()
         .with(stdout_fmt_layer)
@@ -116,6 +142,7 @@ pub async fn initialize_opentelemetry_providers(
         tracer_provider: Some(tracer_provider),
         logging_provider: Some(logging_provider),
         meter_provider: Some(meter_provider),
+        prometheus_registry,
     })
 }
 
@@ -141,22 +168,42 @@ fn otel_env_filter() -> EnvFilter {
         .add_directive("reqwest=off".parse().unwrap())
 }
 
-fn stdout_layer(app_config: &AppConfig) -> impl Layer<tracing_subscriber::Registry> {
-    tracing_subscriber::fmt::layer()
-        .pretty()
-        .with_writer(std::io::stdout)
-        .with_ansi(true)
-        .with_thread_ids(true)
-        .with_level(true)
-        .with_file(true)
-        .with_line_number(true)
-        // Only log spans when otel is enabled
-        .with_span_events(if app_config.otel.enabled {
-            FmtSpan::NEW | FmtSpan::CLOSE
-        } else {
-            FmtSpan::NONE
-        })
-        .with_filter(stdout_env_filter())
+fn stdout_layer(app_config: &AppConfig) -> Box<dyn Layer<Registry> + Send + Sync> {
+    // Only log spans when otel is enabled
+    let span_events = if app_config.otel.enabled {
+        FmtSpan::NEW | FmtSpan::CLOSE
+    } else {
+        FmtSpan::NONE
+    };
+    match app_config.log.format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_writer(std::io::stdout)
+            .with_ansi(true)
+            .with_thread_ids(true)
+            .with_level(true)
+            .with_file(true)
+            .with_line_number(true)
+            .with_span_events(span_events)
+            .with_filter(stdout_env_filter())
+            .boxed(),
+        LogFormat::Compact => tracing_subscriber::fmt::layer()
+            .compact()
+            .with_writer(std::io::stdout)
+            .with_ansi(true)
+            .with_thread_ids(true)
+            .with_level(true)
+            .with_span_events(span_events)
+            .with_filter(stdout_env_filter())
+            .boxed(),
+        // JSON records carry the span fields and ids instead of separate span events.
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .fmt_fields(JsonFields::new())
+            .event_format(JsonLogFormat)
+            .with_writer(std::io::stdout)
+            .with_filter(stdout_env_filter())
+            .boxed(),
+    }
 }
 
 // Create a new tracing::Fmt layer to print the logs to stdout. It has a
@@ -188,9 +235,7 @@ fn init_tracer_provider(
 ) -> Result<opentelemetry_sdk::trace::SdkTracerProvider, Error> {
     let span_exporter = init_span_exporter(app_config)?;
     let tracer_provider = SdkTracerProvider::builder()
-        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
-            1.0,
-        ))))
+        .with_sampler(init_sampler(app_config))
         .with_id_generator(RandomIdGenerator::default())
         .with_resource(init_otel_resources(app_config))
         .with_batch_exporter(span_exporter)
@@ -199,28 +244,57 @@ fn init_tracer_provider(
     Ok(tracer_provider)
 }
 
-fn init_span_exporter(app_config: &AppConfig) -> Result<SpanExporter, Error> {
-    let mut builder = SpanExporter::builder()
-        .with_tonic()
-        .with_endpoint(app_config.otel.traces_endpoint.clone().unwrap());
-    if let Some(metadata) = get_metadata_map(app_config) {
-        builder = builder.with_metadata(metadata);
+fn init_sampler(app_config: &AppConfig) -> Sampler {
+    let ratio = app_config.otel.traces_sampler_ratio;
+    match app_config.otel.traces_sampler {
+        TracesSampler::AlwaysOn => Sampler::AlwaysOn,
+        TracesSampler::AlwaysOff => Sampler::AlwaysOff,
+        TracesSampler::TraceIdRatio => Sampler::TraceIdRatioBased(ratio),
+        TracesSampler::ParentBasedAlwaysOn => Sampler::ParentBased(Box::new(Sampler::AlwaysOn)),
+        TracesSampler::ParentBasedAlwaysOff => Sampler::ParentBased(Box::new(Sampler::AlwaysOff)),
+        TracesSampler::ParentBasedTraceIdRatio => {
+            Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio)))
+        }
     }
-    let span_exporter = builder.build()?;
+}
+
+fn init_span_exporter(app_config: &AppConfig) -> Result<SpanExporter, Error> {
+    let endpoint = otlp_endpoint(&app_config.otel.traces_endpoint);
+    let headers = otlp_headers(app_config);
+    let span_exporter = match app_config.otel.protocol {
+        OtlpProtocol::Grpc => SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .with_metadata(metadata_map(headers)?)
+            .build()?,
+        OtlpProtocol::HttpProtobuf => SpanExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpBinary)
+            .with_endpoint(endpoint)
+            .with_headers(headers)
+            .build()?,
+    };
     Ok(span_exporter)
 }
 
 fn init_logging_provider(
     app_config: &AppConfig,
 ) -> Result<opentelemetry_sdk::logs::SdkLoggerProvider, Error> {
-    let mut builder = LogExporter::builder()
-        .with_tonic()
-        .with_endpoint(app_config.otel.logs_endpoint.clone().unwrap());
-
-    if let Some(metadata) = get_metadata_map(app_config) {
-        builder = builder.with_metadata(metadata);
-    }
-    let logs_exporter = builder.build()?;
+    let endpoint = otlp_endpoint(&app_config.otel.logs_endpoint);
+    let headers = otlp_headers(app_config);
+    let logs_exporter = match app_config.otel.protocol {
+        OtlpProtocol::Grpc => LogExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .with_metadata(metadata_map(headers)?)
+            .build()?,
+        OtlpProtocol::HttpProtobuf => LogExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpBinary)
+            .with_endpoint(endpoint)
+            .with_headers(headers)
+            .build()?,
+    };
 
     let logger_provider = opentelemetry_sdk::logs::SdkLoggerProvider::builder()
         .with_resource(init_otel_resources(app_config))
@@ -229,57 +303,85 @@ fn init_logging_provider(
     Ok(logger_provider)
 }
 
-fn get_metadata_map(app_config: &AppConfig) -> Option<MetadataMap> {
-    if app_config.otel_provider.provider == Some(OTEL_PROVIDER_OPENOBSERVE.to_string()) {
-        return Some(openobserve_metadata(app_config));
-    }
-    None
+/// The endpoint of an OTLP exporter. Endpoints are checked by `OtelConfig::validate` at startup.
+fn otlp_endpoint(endpoint: &Option<String>) -> String {
+    endpoint.clone().unwrap_or_default()
 }
 
-fn openobserve_metadata(app_config: &AppConfig) -> MetadataMap {
-    let mut map = MetadataMap::with_capacity(3);
-    if let Some(auth_token) = &app_config.otel_provider.auth_token {
-        map.insert(
-            "authorization",
-            MetadataValue::try_from(auth_token).unwrap(),
-        );
-    }
-    if let Some(organization) = &app_config.otel_provider.organization {
+/// The headers sent with every OTLP export, as gRPC metadata or HTTP headers.
+fn otlp_headers(app_config: &AppConfig) -> HashMap<String, String> {
+    app_config.otel_provider.headers().into_iter().collect()
+}
+
+fn metadata_map(headers: HashMap<String, String>) -> Result<MetadataMap, Error> {
+    let mut map = MetadataMap::with_capacity(headers.len());
+    for (key, value) in headers {
         map.insert(
-            "organization",
-            MetadataValue::try_from(organization).unwrap(),
+            MetadataKey::from_bytes(key.as_bytes())?,
+            MetadataValue::try_from(value.as_str())?,
         );
     }
-    if let Some(stream_name) = &app_config.otel_provider.stream_name {
-        map.insert("stream-name", MetadataValue::try_from(stream_name).unwrap());
-    }
-    map
+    Ok(map)
 }
 
 fn init_meter_provider(
     app_config: &AppConfig,
-) -> Result<opentelemetry_sdk::metrics::SdkMeterProvider, Error> {
-    let mut builder = opentelemetry_otlp::MetricExporter::builder()
-        .with_tonic()
-        .with_temporality(Temporality::Cumulative)
-        .with_endpoint(app_config.otel.metrics_endpoint.clone().unwrap())
-        .with_timeout(Duration::from_secs(3));
-
-    if let Some(metadata) = get_metadata_map(app_config) {
-        builder = builder.with_metadata(metadata);
-    }
-    let metric_exporter = builder.build()?;
-
-    let periodic_reader = opentelemetry_sdk::metrics::PeriodicReader::builder(metric_exporter)
-        .with_interval(app_config.otel.metric_export_interval.unwrap())
-        .build();
+) -> Result<
+    (
+        opentelemetry_sdk::metrics::SdkMeterProvider,
+        Option<prometheus::Registry>,
+    ),
+    Error,
+> {
+    let builder = opentelemetry_sdk::metrics::SdkMeterProvider::builder()
+        .with_resource(init_otel_resources(app_config));
 
-    let meter_provider = opentelemetry_sdk::metrics::SdkMeterProvider::builder()
-        .with_resource(init_otel_resources(app_config))
-        .with_reader(periodic_reader)
-        .build();
+    let (builder, prometheus_registry) = match app_config.otel.metrics_exporter {
+        MetricsExporter::Otlp => (
+            builder.with_reader(init_otlp_metric_reader(app_config)?),
+            None,
+        ),
+        MetricsExporter::Prometheus => {
+            let registry = prometheus::Registry::new();
+            let prometheus_exporter = opentelemetry_prometheus::exporter()
+                .with_registry(registry.clone())
+                .build()?;
+            (builder.with_reader(prometheus_exporter), Some(registry))
+        }
+    };
+    let meter_provider = builder.build();
 
     global::set_meter_provider(meter_provider.clone());
 
-    Ok(meter_provider)
+    Ok((meter_provider, prometheus_registry))
+}
+
+fn init_otlp_metric_reader(
+    app_config: &AppConfig,
+) -> Result<opentelemetry_sdk::metrics::PeriodicReader<opentelemetry_otlp::MetricExporter>, Error> {
+    let endpoint = otlp_endpoint(&app_config.otel.metrics_endpoint);
+    let headers = otlp_headers(app_config);
+    let metric_exporter = match app_config.otel.protocol {
+        OtlpProtocol::Grpc => opentelemetry_otlp::MetricExporter::builder()
+            .with_tonic()
+            .with_temporality(Temporality::Cumulative)
+            .with_endpoint(endpoint)
+            .with_timeout(Duration::from_secs(3))
+            .with_metadata(metadata_map(headers)?)
+            .build()?,
+        OtlpProtocol::HttpProtobuf => opentelemetry_otlp::MetricExporter::builder()
+            .with_http()
+            .with_temporality(Temporality::Cumulative)
+            .with_protocol(Protocol::HttpBinary)
+            .with_endpoint(endpoint)
+            .with_timeout(Duration::from_secs(3))
+            .with_headers(headers)
+            .build()?,
+    };
+
+    let mut builder = opentelemetry_sdk::metrics::PeriodicReader::builder(metric_exporter);
+    if let Some(interval) = app_config.otel.metric_export_interval {
+        builder = builder.with_interval(interval);
+    }
+    Ok(builder.build())
 }
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{DebounceEventResult, Debouncer, new_debouncer};
+
+use crate::config::app_config::AppConfig;
+use crate::utils::languages::AvailableLanguages;
+
+/// Watch the tesseract data path and rescan the available languages when it changes.
+///
+/// Events are debounced by `TesseractConfig::watch_debounce`, so copying a large model or a
+/// whole language directory triggers a single rescan. The watcher stops when the returned
+/// debouncer is dropped.
+///
+/// # Errors
+///
+/// Returns an error if the data path cannot be watched (e.g. it does not exist).
+pub fn watch_tessdata(
+    app_config: AppConfig,
+    available_languages: AvailableLanguages,
+) -> notify::Result<Debouncer<RecommendedWatcher>> {
+    let debounce = app_config.tesseract.watch_debounce;
+    let data_path = app_config.tesseract.data_path.clone();
+    let mut debouncer = new_debouncer(debounce, move |result: DebounceEventResult| match result {
+        Ok(events) if !events.is_empty() => {
+            if let Err(error) = available_languages.rescan(&app_config) {
+                tracing::warn!("Failed to reload the Tesseract languages: {error}");
+            }
+        }
+        Ok(_) => {}
+        Err(error) => tracing::warn!("Failed to watch the Tesseract data path: {error}"),
+    })?;
+    debouncer
+        .watcher()
+        .watch(Path::new(&data_path), RecursiveMode::Recursive)?;
+    tracing::info!("Watching '{data_path}' for Tesseract model changes");
+    Ok(debouncer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::config::app_config::app_config;
+    use crate::utils::languages::AvailableLanguages;
+    use crate::utils::tessdata_watcher::watch_tessdata;
+
+    #[test]
+    fn test_watch_tessdata_reloads_languages() {
+        let tessdata = tempfile::tempdir().unwrap();
+        let mut app_config = app_config().to_owned();
+        app_config.tesseract.data_path = tessdata.path().to_string_lossy().to_string();
+        app_config.tesseract.watch_debounce = Duration::from_millis(50);
+        let available_languages = AvailableLanguages::new(HashSet::new());
+        let _watcher = watch_tessdata(app_config, available_languages.clone()).unwrap();
+
+        fs::create_dir(tessdata.path().join("deu")).unwrap();
+        fs::write(tessdata.path().join("deu/deu_best.traineddata"), b"").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while available_languages.load().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        let languages = available_languages.load();
+        assert_eq!(languages.len(), 1);
+        let model = languages.iter().next().unwrap();
+        assert_eq!(model.language, "deu");
+        assert_eq!(model.model.as_deref(), Some("deu_best"));
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+
+use crate::models::languages::{EngineMode, ModelMetadata};
+
+/// The number of components of a `.traineddata` file written by Tesseract 4 and later. Older
+/// files have fewer components.
+const TESSDATA_NUM_ENTRIES: usize = 24;
+
+/// The templates of the legacy engine (`TESSDATA_INTTEMP`).
+const INTTEMP_COMPONENT: usize = 3;
+/// The network of the LSTM engine (`TESSDATA_LSTM`).
+const LSTM_COMPONENT: usize = 17;
+/// The version string of the model (`TESSDATA_VERSION`).
+const VERSION_COMPONENT: usize = 23;
+
+/// Version strings are short, anything longer is not a version string.
+const MAX_VERSION_LENGTH: u64 = 1024;
+
+/// The components of a `.traineddata` file, as `(offset, size)` of each present component.
+///
+/// The file starts with the number of components as an `i32`, followed by the offset of each
+/// component as an `i64` (`-1` for missing components), then the components themselves. Files
+/// are written little-endian, but old files may be big-endian.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ComponentTable(Vec<Option<(u64, u64)>>);
+
+impl ComponentTable {
+    /// Read the component table at the start of a `.traineddata` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidData` if the table is not a valid component table.
+    pub fn read(reader: &mut impl Read, file_size: u64) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut num_entries = [0; 4];
+        reader.read_exact(&mut num_entries)?;
+        let (num_entries, big_endian) = match (
+            i32::from_le_bytes(num_entries),
+            i32::from_be_bytes(num_entries),
+        ) {
+            (entries, _) if (1..=TESSDATA_NUM_ENTRIES as i32).contains(&entries) => {
+                (entries as usize, false)
+            }
+            (_, entries) if (1..=TESSDATA_NUM_ENTRIES as i32).contains(&entries) => {
+                (entries as usize, true)
+            }
+            _ => return Err(invalid("Invalid number of components")),
+        };
+
+        let header_size = 4 + 8 * num_entries as u64;
+        let mut offsets = Vec::with_capacity(num_entries);
+        for _ in 0..num_entries {
+            let mut offset = [0; 8];
+            reader.read_exact(&mut offset)?;
+            let offset = if big_endian {
+                i64::from_be_bytes(offset)
+            } else {
+                i64::from_le_bytes(offset)
+            };
+            offsets.push(match u64::try_from(offset) {
+                Ok(offset) if (header_size..=file_size).contains(&offset) => Some(offset),
+                Ok(_) => return Err(invalid("Component offset out of the file")),
+                Err(_) => None,
+            });
+        }
+
+        // A component ends where the next present component starts, the last one at the end of
+        // the file.
+        let mut components = vec![None; num_entries];
+        let mut end = file_size;
+        for (index, offset) in offsets.iter().enumerate().rev() {
+            if let Some(offset) = *offset {
+                if offset > end {
+                    return Err(invalid("Components are not in order"));
+                }
+                components[index] = (offset < end).then_some((offset, end - offset));
+                end = offset;
+            }
+        }
+        Ok(Self(components))
+    }
+
+    /// The offset and size of a component, if the file has it.
+    #[must_use]
+    pub fn component(&self, index: usize) -> Option<(u64, u64)> {
+        self.0.get(index).copied().flatten()
+    }
+
+    /// The OCR engine modes that can be used with the model.
+    #[must_use]
+    pub fn engine_modes(&self) -> Vec<EngineMode> {
+        let legacy = self.component(INTTEMP_COMPONENT).is_some();
+        let lstm = self.component(LSTM_COMPONENT).is_some();
+        let mut engine_modes = Vec::new();
+        if legacy {
+            engine_modes.push(EngineMode::TesseractOnly);
+        }
+        if lstm {
+            engine_modes.push(EngineMode::LstmOnly);
+        }
+        if legacy && lstm {
+            engine_modes.push(EngineMode::TesseractLstmCombined);
+        }
+        engine_modes
+    }
+}
+
+/// Read the size, checksum and components of a `.traineddata` file.
+///
+/// Files whose component table cannot be parsed still get a size and checksum, without engine
+/// modes or version.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read.
+pub fn read_model_metadata(path: &Path) -> io::Result<ModelMetadata> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    let sha256 = format!("{:x}", hasher.finalize());
+
+    file.seek(SeekFrom::Start(0))?;
+    let (engine_modes, version) = match ComponentTable::read(&mut file, size) {
+        Ok(components) => {
+            let version = read_version(&mut file, &components)?;
+            (components.engine_modes(), version)
+        }
+        Err(error) if error.kind() == io::ErrorKind::InvalidData => (Vec::new(), None),
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => (Vec::new(), None),
+        Err(error) => return Err(error),
+    };
+
+    Ok(ModelMetadata {
+        size,
+        sha256,
+        engine_modes,
+        version,
+    })
+}
+
+/// Metadata read before, with the size and modification time of the file it was read from.
+struct CachedMetadata {
+    size: u64,
+    modified: SystemTime,
+    metadata: ModelMetadata,
+}
+
+fn metadata_cache() -> &'static Mutex<HashMap<PathBuf, CachedMetadata>> {
+    static INSTANCE: OnceLock<Mutex<HashMap<PathBuf, CachedMetadata>>> = OnceLock::new();
+
+    INSTANCE.get_or_init(Mutex::default)
+}
+
+/// Like `read_model_metadata`, but only reads files that are new or whose size or modification
+/// time changed since they were last read, so rescans do not hash every model again.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read.
+pub fn cached_model_metadata(path: &Path) -> io::Result<ModelMetadata> {
+    let file_metadata = fs::metadata(path)?;
+    let size = file_metadata.len();
+    // Without a modification time, changes cannot be detected, so the file is always read.
+    let Ok(modified) = file_metadata.modified() else {
+        return read_model_metadata(path);
+    };
+
+    if let Some(cached) = metadata_cache()
+        .lock()
+        .expect("Model metadata cache lock poisoned")
+        .get(path)
+        .filter(|cached| cached.size == size && cached.modified == modified)
+    {
+        return Ok(cached.metadata.clone());
+    }
+
+    let metadata = read_model_metadata(path)?;
+    metadata_cache()
+        .lock()
+        .expect("Model metadata cache lock poisoned")
+        .insert(
+            path.to_owned(),
+            CachedMetadata {
+                size,
+                modified,
+                metadata: metadata.clone(),
+            },
+        );
+    Ok(metadata)
+}
+
+/// Forget the cached metadata of the files in `directory` that are not in `paths` (anymore).
+pub fn retain_cached_metadata(directory: &Path, paths: &HashSet<PathBuf>) {
+    metadata_cache()
+        .lock()
+        .expect("Model metadata cache lock poisoned")
+        .retain(|path, _| !path.starts_with(directory) || paths.contains(path));
+}
+
+fn read_version(
+    file: &mut (impl Read + Seek),
+    components: &ComponentTable,
+) -> io::Result<Option<String>> {
+    let Some((offset, size)) = components.component(VERSION_COMPONENT) else {
+        return Ok(None);
+    };
+    if size > MAX_VERSION_LENGTH {
+        return Ok(None);
+    }
+    file.seek(SeekFrom::Start(offset))?;
+    let mut version = Vec::new();
+    file.take(size).read_to_end(&mut version)?;
+    let version = String::from_utf8_lossy(&version)
+        .trim_end_matches('\0')
+        .trim()
+        .to_owned();
+    Ok((!version.is_empty()).then_some(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::io::{self, Cursor};
+
+    use crate::models::languages::EngineMode;
+    use crate::utils::traineddata::{
+        ComponentTable, cached_model_metadata, read_model_metadata, retain_cached_metadata,
+    };
+
+    /// Build a `.traineddata` file with the given components, indexed like Tesseract's.
+    fn create_traineddata(components: &[(usize, &[u8])], big_endian: bool) -> Vec<u8> {
+        const NUM_ENTRIES: usize = 24;
+        let mut offsets = [-1_i64; NUM_ENTRIES];
+        let mut data = Vec::new();
+        let mut offset = 4 + 8 * NUM_ENTRIES as i64;
+        for (index, content) in components {
+            offsets[*index] = offset;
+            data.extend_from_slice(content);
+            offset += content.len() as i64;
+        }
+        let mut file = Vec::new();
+        if big_endian {
+            file.extend_from_slice(&(NUM_ENTRIES as i32).to_be_bytes());
+            offsets
+                .iter()
+                .for_each(|offset| file.extend_from_slice(&offset.to_be_bytes()));
+        } else {
+            file.extend_from_slice(&(NUM_ENTRIES as i32).to_le_bytes());
+            offsets
+                .iter()
+                .for_each(|offset| file.extend_from_slice(&offset.to_le_bytes()));
+        }
+        file.extend_from_slice(&data);
+        file
+    }
+
+    #[test]
+    fn test_component_table() {
+        let file = create_traineddata(&[(0, b"config"), (17, b"lstm"), (23, b"5.0")], false);
+        let table = ComponentTable::read(&mut Cursor::new(&file), file.len() as u64).unwrap();
+
+        assert_eq!(table.component(0), Some((196, 6)));
+        assert_eq!(table.component(17), Some((202, 4)));
+        assert_eq!(table.component(23), Some((206, 3)));
+        assert_eq!(table.component(3), None);
+        assert_eq!(table.engine_modes(), [EngineMode::LstmOnly]);
+    }
+
+    #[test]
+    fn test_component_table_big_endian_legacy() {
+        let file = create_traineddata(&[(3, b"inttemp"), (17, b"lstm")], true);
+        let table = ComponentTable::read(&mut Cursor::new(&file), file.len() as u64).unwrap();
+
+        assert_eq!(
+            table.engine_modes(),
+            [
+                EngineMode::TesseractOnly,
+                EngineMode::LstmOnly,
+                EngineMode::TesseractLstmCombined
+            ]
+        );
+    }
+
+    #[test]
+    fn test_component_table_invalid() {
+        let file = b"not a traineddata file";
+        let error = ComponentTable::read(&mut Cursor::new(file), file.len() as u64).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_read_model_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("eng.traineddata");
+        let file = create_traineddata(
+            &[(17, b"lstm"), (23, b"4.00.00alpha:eng:synth20170629")],
+            false,
+        );
+        std::fs::write(&path, &file).unwrap();
+
+        let metadata = read_model_metadata(&path).unwrap();
+        assert_eq!(metadata.size, file.len() as u64);
+        assert_eq!(metadata.sha256.len(), 64);
+        assert_eq!(metadata.engine_modes, [EngineMode::LstmOnly]);
+        assert_eq!(
+            metadata.version.as_deref(),
+            Some("4.00.00alpha:eng:synth20170629")
+        );
+
+        // Files that are not models still get a size and checksum.
+        std::fs::write(&path, b"test data").unwrap();
+        let metadata = read_model_metadata(&path).unwrap();
+        assert_eq!(metadata.size, 9);
+        assert!(metadata.engine_modes.is_empty());
+        assert_eq!(metadata.version, None);
+    }
+
+    #[test]
+    fn test_cached_model_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("eng.traineddata");
+        std::fs::write(&path, b"first").unwrap();
+        let first = cached_model_metadata(&path).unwrap();
+
+        // Same size and modification time: the file is not read again.
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        std::fs::write(&path, b"other").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified).unwrap();
+        assert_eq!(cached_model_metadata(&path).unwrap(), first);
+
+        // A changed size (or modification time) is read again.
+        std::fs::write(&path, b"changed").unwrap();
+        let changed = cached_model_metadata(&path).unwrap();
+        assert_eq!(changed, read_model_metadata(&path).unwrap());
+        assert_ne!(changed.sha256, first.sha256);
+
+        // Forgotten files are read again.
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+        std::fs::write(&path, b"forget!").unwrap();
+        file.set_modified(modified).unwrap();
+        assert_eq!(cached_model_metadata(&path).unwrap(), changed);
+        retain_cached_metadata(dir.path(), &HashSet::new());
+        assert_ne!(cached_model_metadata(&path).unwrap(), changed);
+    }
+}
@@ -1,6 +1,13 @@
 use std::collections::HashSet;
 
+use axum::http::HeaderMap;
+
+use crate::config::app_config::ModelPreferences;
 use crate::models::{error::ErrorType, images::ImagesQueryParams, languages::TesseractModel};
+use crate::utils::language_tags::{accepted_language, resolve_language};
+
+/// The name of the model without a name (`<language>.traineddata`) in the model preferences.
+const DEFAULT_MODEL_NAME: &str = "default";
 
 /// Allowed file types
 const ALLOWED_FILE_TYPES: [&str; 5] = [
@@ -15,6 +22,7 @@ pub fn validate_language_params(
     language_params: &ImagesQueryParams,
     available_languages: &HashSet<TesseractModel>,
     default_language: &str,
+    preferences: &ModelPreferences,
 ) -> Result<TesseractModel, ErrorType> {
     // If model is provided, language must also be provided
     if language_params.model.is_some() && language_params.language.is_none() {
@@ -24,22 +32,26 @@ pub fn validate_language_params(
     }
 
     // Use the provided language or default to the configured default language
-    let language = language_params
+    let requested_language = language_params
         .language
         .as_deref()
         .unwrap_or(default_language);
+    // Accept ISO 639 and BCP-47 tags such as "en" or "zh-Hans" for the Tesseract languages
+    let language = resolve_language(requested_language, available_languages)
+        .unwrap_or_else(|| requested_language.to_owned());
 
-    // Filter models that match the requested language
-    let matching_language_models: Vec<&TesseractModel> = available_languages
+    // Filter models that match the requested language, sorted so the choice is stable
+    let mut matching_language_models: Vec<&TesseractModel> = available_languages
         .iter()
         .filter(|model| model.language == language)
         .collect();
+    matching_language_models.sort_by(|a, b| a.model.cmp(&b.model));
 
     // If no models match the requested language
     if matching_language_models.is_empty() {
         return Err(ErrorType::InvalidRequest(format!(
             "Language '{}' is not available",
-            language
+            requested_language
         )));
     }
 
@@ -60,7 +72,11 @@ pub fn validate_language_params(
         )));
     }
 
-    // If only language is provided (no specific model)
+    // If only language is provided (no specific model), use the preferred model first
+    if let Some(model) = preferred_model(&language, &matching_language_models, preferences) {
+        return Ok(model.clone());
+    }
+
     if matching_language_models.len() == 1 {
         // If there's only one model for this language, use it
         Ok(matching_language_models[0].clone())
@@ -79,6 +95,63 @@ pub fn validate_language_params(
     }
 }
 
+/// The model chosen by the preferences among the models of a language: the default model of
+/// the language, then the first model of the preference order.
+fn preferred_model<'a>(
+    language: &str,
+    models: &[&'a TesseractModel],
+    preferences: &ModelPreferences,
+) -> Option<&'a TesseractModel> {
+    let find = |name: &str| {
+        models
+            .iter()
+            .find(|tesseract_model| model_matches(tesseract_model, name))
+            .copied()
+    };
+    if let Some(default_model) = preferences.default_models.get(language) {
+        match find(default_model) {
+            Some(tesseract_model) => return Some(tesseract_model),
+            None => tracing::warn!(
+                "Default model '{}' of language '{}' is not available",
+                default_model,
+                language
+            ),
+        }
+    }
+    preferences.order.iter().find_map(|name| find(name))
+}
+
+/// Whether a model is named by `name`: the model of that name, a model ending with `_<name>`
+/// (e.g. `eng_best` for `best`), or the model without a name for `default`.
+#[must_use]
+pub fn model_matches(tesseract_model: &TesseractModel, name: &str) -> bool {
+    match tesseract_model.model.as_deref() {
+        None => name == DEFAULT_MODEL_NAME,
+        Some(model) => {
+            model == name
+                || model
+                    .strip_suffix(name)
+                    .is_some_and(|prefix| prefix.ends_with('_'))
+        }
+    }
+}
+
+/// Use the language preferred by the `Accept-Language` header when no language is requested.
+///
+/// Languages that are not available are ignored, so the configured default language is used
+/// when none of them is.
+#[must_use]
+pub fn with_accepted_language(
+    mut language_params: ImagesQueryParams,
+    headers: &HeaderMap,
+    available_languages: &HashSet<TesseractModel>,
+) -> ImagesQueryParams {
+    if language_params.language.is_none() {
+        language_params.language = accepted_language(headers, available_languages);
+    }
+    language_params
+}
+
 /// Validate the file type
 ///
 /// # Errors
@@ -98,6 +171,7 @@ pub fn validate_file_type(file_type: &str) -> Result<(), ErrorType> {
 #[cfg(test)]
 mod tests {
     use crate::{
+        config::app_config::ModelPreferences,
         models::{error::ErrorType, images::ImagesQueryParams, languages::TesseractModel},
         utils::validations::{validate_file_type, validate_language_params},
     };
@@ -150,7 +224,12 @@ mod tests {
         };
         let available_languages = HashSet::new();
 
-        let result = validate_language_params(&params, &available_languages, "eng");
+        let result = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        );
         assert!(result.is_err());
         match result {
             Err(ErrorType::InvalidRequest(msg)) => {
@@ -168,7 +247,12 @@ mod tests {
         };
         let available_languages = HashSet::new();
 
-        let result = validate_language_params(&params, &available_languages, "eng");
+        let result = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        );
         assert!(result.is_err());
         match result {
             Err(ErrorType::InvalidRequest(msg)) => {
@@ -186,12 +270,14 @@ mod tests {
             model: Some("fast".to_string()),
             full_path: Some("spa/spa_fast.traineddata".to_string()),
             relative_path: Some("spa/spa_fast".to_string()),
+            metadata: None,
         });
         available_languages.insert(TesseractModel {
             language: "spa".to_string(),
             model: Some("default".to_string()),
             full_path: Some("spa/spa_default.traineddata".to_string()),
             relative_path: Some("spa/spa_default".to_string()),
+            metadata: None,
         });
 
         let params = ImagesQueryParams {
@@ -199,13 +285,45 @@ mod tests {
             model: Some("fast".to_string()),
         };
 
-        let result = validate_language_params(&params, &available_languages, "eng");
+        let result = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        );
         assert!(result.is_ok());
         let model = result.unwrap();
         assert_eq!(model.language, "spa");
         assert_eq!(model.model, Some("fast".to_string()));
     }
 
+    #[test]
+    fn test_validate_language_params_language_alias() {
+        let mut available_languages = HashSet::new();
+        available_languages.insert(TesseractModel {
+            language: "spa".to_string(),
+            model: Some("fast".to_string()),
+            full_path: Some("spa/spa_fast.traineddata".to_string()),
+            relative_path: Some("spa/spa_fast".to_string()),
+            metadata: None,
+        });
+
+        let params = ImagesQueryParams {
+            language: Some("es-MX".to_string()),
+            model: Some("fast".to_string()),
+        };
+
+        let model = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        )
+        .unwrap();
+        assert_eq!(model.language, "spa");
+        assert_eq!(model.model, Some("fast".to_string()));
+    }
+
     #[test]
     fn test_validate_language_params_model_not_found_for_language() {
         let mut available_languages = HashSet::new();
@@ -214,6 +332,7 @@ mod tests {
             model: Some("fast".to_string()),
             full_path: Some("spa/spa_fast.traineddata".to_string()),
             relative_path: Some("spa/spa_fast".to_string()),
+            metadata: None,
         });
 
         let params = ImagesQueryParams {
@@ -221,7 +340,12 @@ mod tests {
             model: Some("slow".to_string()),
         };
 
-        let result = validate_language_params(&params, &available_languages, "eng");
+        let result = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        );
         assert!(result.is_err());
         match result {
             Err(ErrorType::InvalidRequest(msg)) => {
@@ -239,6 +363,7 @@ mod tests {
             model: Some("fast".to_string()),
             full_path: Some("spa/spa_fast.traineddata".to_string()),
             relative_path: Some("spa/spa_fast".to_string()),
+            metadata: None,
         });
 
         let params = ImagesQueryParams {
@@ -246,7 +371,12 @@ mod tests {
             model: None,
         };
 
-        let result = validate_language_params(&params, &available_languages, "eng");
+        let result = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        );
         assert!(result.is_ok());
         let model = result.unwrap();
         assert_eq!(model.language, "spa");
@@ -261,12 +391,14 @@ mod tests {
             model: None,
             full_path: Some("eng.traineddata".to_string()),
             relative_path: Some("eng".to_string()),
+            metadata: None,
         });
         available_languages.insert(TesseractModel {
             language: "eng".to_string(),
             model: Some("fast".to_string()),
             full_path: Some("eng/eng_fast.traineddata".to_string()),
             relative_path: Some("eng/eng_fast".to_string()),
+            metadata: None,
         });
 
         let params = ImagesQueryParams {
@@ -274,7 +406,12 @@ mod tests {
             model: None,
         };
 
-        let result = validate_language_params(&params, &available_languages, "eng");
+        let result = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        );
         assert!(result.is_ok());
         let model = result.unwrap();
         assert_eq!(model.language, "eng");
@@ -289,12 +426,14 @@ mod tests {
             model: Some("fast".to_string()),
             full_path: Some("eng/eng_fast.traineddata".to_string()),
             relative_path: Some("eng/eng_fast".to_string()),
+            metadata: None,
         });
         available_languages.insert(TesseractModel {
             language: "eng".to_string(),
             model: Some("best".to_string()),
             full_path: Some("eng/eng_best.traineddata".to_string()),
             relative_path: Some("eng/eng_best".to_string()),
+            metadata: None,
         });
 
         let params = ImagesQueryParams {
@@ -302,7 +441,12 @@ mod tests {
             model: None,
         };
 
-        let result = validate_language_params(&params, &available_languages, "eng");
+        let result = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        );
         assert!(result.is_err());
         match result {
             Err(ErrorType::InvalidRequest(msg)) => {
@@ -323,6 +467,7 @@ mod tests {
             model: None,
             full_path: Some("eng.traineddata".to_string()),
             relative_path: Some("eng".to_string()),
+            metadata: None,
         });
 
         let params = ImagesQueryParams {
@@ -330,7 +475,12 @@ mod tests {
             model: None,
         };
 
-        let result = validate_language_params(&params, &available_languages, "eng");
+        let result = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        );
         assert!(result.is_ok());
         let model = result.unwrap();
         assert_eq!(model.language, "eng");
@@ -345,6 +495,7 @@ mod tests {
             model: None,
             full_path: Some("spa.traineddata".to_string()),
             relative_path: Some("spa".to_string()),
+            metadata: None,
         });
 
         let params = ImagesQueryParams {
@@ -352,7 +503,12 @@ mod tests {
             model: None,
         };
 
-        let result = validate_language_params(&params, &available_languages, "eng");
+        let result = validate_language_params(
+            &params,
+            &available_languages,
+            "eng",
+            &ModelPreferences::default(),
+        );
         assert!(result.is_err());
         match result {
             Err(ErrorType::InvalidRequest(msg)) => {
@@ -361,4 +517,80 @@ mod tests {
             _ => panic!("Expected InvalidRequest error"),
         }
     }
+
+    fn create_eng_models(models: &[Option<&str>]) -> HashSet<TesseractModel> {
+        models
+            .iter()
+            .map(|model| TesseractModel {
+                language: "eng".to_string(),
+                model: model.map(str::to_string),
+                full_path: None,
+                relative_path: None,
+                metadata: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_language_params_default_model() {
+        let available_languages = create_eng_models(&[None, Some("eng_best"), Some("eng_fast")]);
+        let params = ImagesQueryParams {
+            language: Some("eng".to_string()),
+            model: None,
+        };
+        let preferences = ModelPreferences {
+            default_models: [("eng".to_string(), "fast".to_string())].into(),
+            order: vec!["best".to_string()],
+        };
+
+        let model =
+            validate_language_params(&params, &available_languages, "eng", &preferences).unwrap();
+        assert_eq!(model.model.as_deref(), Some("eng_fast"));
+
+        // A requested model always wins over the preferences
+        let params = ImagesQueryParams {
+            language: Some("eng".to_string()),
+            model: Some("eng_best".to_string()),
+        };
+        let model =
+            validate_language_params(&params, &available_languages, "eng", &preferences).unwrap();
+        assert_eq!(model.model.as_deref(), Some("eng_best"));
+    }
+
+    #[test]
+    fn test_validate_language_params_model_preference_order() {
+        let params = ImagesQueryParams {
+            language: None,
+            model: None,
+        };
+        let preferences = ModelPreferences {
+            // Unavailable default models fall back to the preference order
+            default_models: [("eng".to_string(), "legacy".to_string())].into(),
+            order: vec![
+                "best".to_string(),
+                "default".to_string(),
+                "fast".to_string(),
+            ],
+        };
+        let preferred = |models: &[Option<&str>]| {
+            validate_language_params(&params, &create_eng_models(models), "eng", &preferences)
+                .map(|model| model.model)
+        };
+
+        assert_eq!(
+            preferred(&[None, Some("best"), Some("fast")])
+                .unwrap()
+                .as_deref(),
+            Some("best")
+        );
+        assert_eq!(preferred(&[None, Some("eng_fast")]).unwrap(), None);
+        assert_eq!(
+            preferred(&[Some("eng_fast"), Some("other")])
+                .unwrap()
+                .as_deref(),
+            Some("eng_fast")
+        );
+        // Names only match whole words of the model name
+        assert!(preferred(&[Some("breakfast"), Some("other")]).is_err());
+    }
 }
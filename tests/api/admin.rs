@@ -0,0 +1,271 @@
+use std::fs;
+
+use axum::{
+    body::Body,
+    http::{
+        Request, StatusCode,
+        header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE},
+    },
+};
+use http_body_util::BodyExt as _;
+use ocr_service::{AppState, router_with_state};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+
+use crate::helpers::*;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// A test app whose data path is a temporary copy with an `eng` model, so tests can change it.
+fn admin_app() -> (TestApp, TempDir) {
+    let mut app = TestApp::new();
+    let tessdata = tempfile::tempdir().unwrap();
+    fs::copy(
+        format!(
+            "{}/eng.traineddata",
+            app.app_state.app_config.tesseract.data_path
+        ),
+        tessdata.path().join("eng.traineddata"),
+    )
+    .unwrap();
+    let mut app_config = app.app_state.app_config.clone();
+    app_config.tesseract.data_path = tessdata.path().to_string_lossy().to_string();
+    app_config.security.admin_token = Some(ADMIN_TOKEN.to_owned());
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+    (app, tessdata)
+}
+
+async fn languages(app: &TestApp) -> Vec<(String, Option<String>)> {
+    let req = Request::get("/api/v1/languages")
+        .body(Body::empty())
+        .unwrap();
+    let body = app.request(req).await.into_body().collect().await.unwrap();
+    let body: Value = serde_json::from_slice(&body.to_bytes()).unwrap();
+    body["languages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|language| {
+            (
+                language["language"].as_str().unwrap().to_owned(),
+                language["model"].as_str().map(str::to_owned),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_admin_disabled_without_token() {
+    let app = TestApp::new();
+
+    let req = Request::post("/api/v1/admin/languages/rescan")
+        .header(AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(req).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_admin_requires_valid_token() {
+    let (app, _tessdata) = admin_app();
+
+    for authorization in [None, Some("Bearer wrong-token"), Some(ADMIN_TOKEN)] {
+        let mut req = Request::post("/api/v1/admin/languages/rescan");
+        if let Some(authorization) = authorization {
+            req = req.header(AUTHORIZATION, authorization);
+        }
+        let response = app.request(req.body(Body::empty()).unwrap()).await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers().get(WWW_AUTHENTICATE).unwrap(), "Bearer");
+    }
+}
+
+#[tokio::test]
+async fn test_rescan_languages_reloads_models() {
+    let (app, tessdata) = admin_app();
+
+    fs::create_dir(tessdata.path().join("deu")).unwrap();
+    fs::write(tessdata.path().join("deu/deu_fast.traineddata"), b"").unwrap();
+    let req = Request::post("/api/v1/admin/languages/rescan")
+        .header(AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(req).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(body["languages"].as_array().unwrap().len(), 2);
+    // The other routes see the reloaded languages.
+    assert_eq!(
+        languages(&app).await,
+        [
+            ("deu".to_owned(), Some("deu_fast".to_owned())),
+            ("eng".to_owned(), None)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_delete_model() {
+    let (app, tessdata) = admin_app();
+    fs::create_dir(tessdata.path().join("deu")).unwrap();
+    fs::write(tessdata.path().join("deu/deu_fast.traineddata"), b"").unwrap();
+
+    let delete = || {
+        Request::delete("/api/v1/admin/models?language=deu&model=deu_fast")
+            .header(AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.request(delete()).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(!tessdata.path().join("deu").exists());
+    assert_eq!(languages(&app).await, [("eng".to_owned(), None)]);
+
+    let response = app.request(delete()).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_upload_model_invalid_slot() {
+    let (app, _tessdata) = admin_app();
+
+    let req = Request::put("/api/v1/admin/models?language=..&model=eng")
+        .header(AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+        .header(
+            CONTENT_TYPE,
+            format!("multipart/form-data; boundary={BOUNDARY}"),
+        )
+        .body(create_multipart_body(&[(
+            "file",
+            "model.traineddata",
+            "application/octet-stream",
+            b"model",
+        )]))
+        .unwrap();
+    let response = app.request(req).await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_upload_model() {
+    let (app, tessdata) = admin_app();
+    let model = fs::read(tessdata.path().join("eng.traineddata")).unwrap();
+
+    let upload = |content: &[u8]| {
+        Request::put("/api/v1/admin/models?language=eng&model=eng_copy")
+            .header(AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(create_multipart_body(&[(
+                "file",
+                "model.traineddata",
+                "application/octet-stream",
+                content,
+            )]))
+            .unwrap()
+    };
+
+    // Files that Tesseract cannot load are rejected and not kept.
+    let response = app.request(upload(b"not a model")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(!tessdata.path().join("eng/eng_copy.traineddata").exists());
+
+    let response = app.request(upload(&model)).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: Value =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(body["relative_path"], "eng/eng_copy");
+    assert_eq!(
+        languages(&app).await,
+        [
+            ("eng".to_owned(), None),
+            ("eng".to_owned(), Some("eng_copy".to_owned()))
+        ]
+    );
+
+    let response = app.request(upload(&model)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_install_models() {
+    let (mut app, tessdata) = admin_app();
+    let mirror = tempfile::tempdir().unwrap();
+    let mut app_config = app.app_state.app_config.clone();
+    app_config.tesseract.install_sources = vec![mirror.path().to_string_lossy().to_string()];
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+    fs::create_dir(mirror.path().join("deu")).unwrap();
+    fs::write(mirror.path().join("deu/deu_fast.traineddata"), b"deu").unwrap();
+    fs::write(mirror.path().join("fra.traineddata"), b"fra").unwrap();
+
+    let install = |manifest: &str| {
+        fs::write(mirror.path().join("SHA256SUMS"), manifest).unwrap();
+        let body = serde_json::json!({
+            "source": mirror.path().to_string_lossy(),
+            "languages": ["de"],
+        });
+        Request::post("/api/v1/admin/models/install")
+            .header(AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // Models that do not match their checksum are not installed.
+    let response = app
+        .request(install(&format!(
+            "{}  deu/deu_fast.traineddata\n",
+            "0".repeat(64)
+        )))
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(!tessdata.path().join("deu").exists());
+
+    let manifest = [
+        ("deu/deu_fast.traineddata", b"deu"),
+        ("fra.traineddata", b"fra"),
+    ]
+    .iter()
+    .map(|(path, content)| format!("{:x}  {path}\n", Sha256::digest(content)))
+    .collect::<String>();
+    let response = app.request(install(&manifest)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(body["installed"][0]["relative_path"], "deu/deu_fast");
+    assert_eq!(body["installed"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        languages(&app).await,
+        [
+            ("deu".to_owned(), Some("deu_fast".to_owned())),
+            ("eng".to_owned(), None)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_install_models_only_from_install_sources() {
+    let (app, _tessdata) = admin_app();
+
+    let body = serde_json::json!({ "source": "/etc", "manifest": "passwd" });
+    let req = Request::post("/api/v1/admin/models/install")
+        .header(AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.request(req).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(!String::from_utf8_lossy(&body).contains("root"));
+}
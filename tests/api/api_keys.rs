@@ -0,0 +1,117 @@
+use axum::{
+    body::Body,
+    http::{
+        Request, StatusCode,
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    },
+};
+use ocr_service::config::app_config::parse_api_keys;
+use ocr_service::grpc::proto::{ListLanguagesRequest, ocr_service_client::OcrServiceClient};
+use ocr_service::{AppState, router_with_state};
+use sha2::{Digest, Sha256};
+use tonic::Code;
+
+use crate::helpers::*;
+
+const OCR_KEY: &str = "test-ocr-key";
+const LANGUAGES_KEY: &str = "test-languages-key";
+const ADMIN_KEY: &str = "test-admin-key";
+
+/// A test app that requires API keys, configured by the SHA-256 checksums of the keys.
+fn api_keys_app() -> TestApp {
+    let mut app = TestApp::new();
+    let hash = |key: &str| format!("{:x}", Sha256::digest(key));
+    let mut app_config = app.app_state.app_config.clone();
+    app_config.security.api_keys = parse_api_keys(&format!(
+        "ocr:{}:ocr,languages:{}:languages:read,admin:{}:admin",
+        hash(OCR_KEY),
+        hash(LANGUAGES_KEY),
+        hash(ADMIN_KEY)
+    ))
+    .unwrap();
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+    app
+}
+
+#[tokio::test]
+async fn test_api_key_scopes() {
+    let app = api_keys_app();
+    let languages = |header: Option<(&str, &str)>| {
+        let mut req = Request::get("/api/v1/languages");
+        if let Some((name, value)) = header {
+            req = req.header(name, value);
+        }
+        req.body(Body::empty()).unwrap()
+    };
+
+    for header in [None, Some(("x-api-key", "wrong-key"))] {
+        let response = app.request(languages(header)).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers().get(WWW_AUTHENTICATE).unwrap(), "Bearer");
+    }
+
+    let response = app.request(languages(Some(("x-api-key", OCR_KEY)))).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .request(languages(Some(("x-api-key", LANGUAGES_KEY))))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bearer = format!("Bearer {LANGUAGES_KEY}");
+    let response = app
+        .request(languages(Some((AUTHORIZATION.as_str(), &bearer))))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_api_keys_do_not_protect_health() {
+    let app = api_keys_app();
+
+    let req = Request::get("/system/health/live")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(req).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_admin_api_key() {
+    let app = api_keys_app();
+    let rescan = |key: &str| {
+        Request::post("/api/v1/admin/languages/rescan")
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // An API key with the admin scope enables the admin API without an admin token.
+    let response = app.request(rescan(ADMIN_KEY)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app.request(rescan(OCR_KEY)).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_grpc_api_key() {
+    let app = api_keys_app();
+    let address = app.spawn_grpc().await;
+    let mut client = OcrServiceClient::connect(format!("http://{address}"))
+        .await
+        .unwrap();
+
+    let status = client
+        .list_languages(ListLanguagesRequest {})
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    let mut request = tonic::Request::new(ListLanguagesRequest {});
+    request
+        .metadata_mut()
+        .insert("x-api-key", LANGUAGES_KEY.parse().unwrap());
+    assert!(client.list_languages(request).await.is_ok());
+}
@@ -0,0 +1,86 @@
+use axum::{
+    body::Body,
+    http::{
+        Request,
+        header::{
+            ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+            ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+            ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_REQUEST_HEADERS,
+            ACCESS_CONTROL_REQUEST_METHOD, ORIGIN,
+        },
+    },
+};
+use ocr_service::config::app_config::{parse_cors_headers, parse_cors_origins};
+use ocr_service::{AppState, router_with_state};
+
+use crate::helpers::*;
+
+/// A test app that allows credentialed requests from one origin and the subdomains of another.
+fn cors_app() -> TestApp {
+    let mut app = TestApp::new();
+    let mut app_config = app.app_state.app_config.clone();
+    let cors = &mut app_config.security.cors;
+    cors.allowed_origins =
+        parse_cors_origins("https://app.example.com,https://*.example.org").unwrap();
+    cors.allowed_methods = vec!["GET".to_owned(), "POST".to_owned()];
+    cors.allowed_headers = parse_cors_headers("x-api-key,content-type").unwrap();
+    cors.exposed_headers = parse_cors_headers("x-request-id").unwrap();
+    cors.allow_credentials = true;
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+    app
+}
+
+fn preflight(origin: &str) -> Request<Body> {
+    Request::options("/api/v1/languages")
+        .header(ORIGIN, origin)
+        .header(ACCESS_CONTROL_REQUEST_METHOD, "GET")
+        .header(ACCESS_CONTROL_REQUEST_HEADERS, "x-api-key")
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_cors_preflight() {
+    let app = cors_app();
+
+    for origin in ["https://app.example.com", "https://eu.example.org"] {
+        let response = app.request(preflight(origin)).await;
+        let headers = response.headers();
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], origin);
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_METHODS], "GET,POST");
+        assert_eq!(
+            headers[ACCESS_CONTROL_ALLOW_HEADERS],
+            "x-api-key,content-type"
+        );
+    }
+
+    for origin in ["https://other.example.com", "https://example.org"] {
+        let response = app.request(preflight(origin)).await;
+        assert!(
+            response
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_cors_exposed_headers() {
+    let app = cors_app();
+
+    let req = Request::get("/system/health/live")
+        .header(ORIGIN, "https://app.example.com")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(req).await;
+
+    let headers = response.headers();
+    assert_eq!(
+        headers[ACCESS_CONTROL_ALLOW_ORIGIN],
+        "https://app.example.com"
+    );
+    assert_eq!(headers[ACCESS_CONTROL_EXPOSE_HEADERS], "x-request-id");
+}
@@ -0,0 +1,104 @@
+use ocr_service::grpc::proto::{
+    HealthRequest, ListLanguagesRequest, RecognizeRequest, ocr_service_client::OcrServiceClient,
+};
+use tokio::fs::read;
+use tonic::Code;
+
+use crate::helpers::*;
+
+#[tokio::test]
+async fn test_grpc_health_and_languages() {
+    let app = TestApp::new();
+    let address = app.spawn_grpc().await;
+    let mut client = OcrServiceClient::connect(format!("http://{address}"))
+        .await
+        .unwrap();
+
+    let health = client.health(HealthRequest {}).await.unwrap().into_inner();
+    assert_eq!(health.status, "ok");
+
+    let languages = client
+        .list_languages(ListLanguagesRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(
+        languages
+            .languages
+            .iter()
+            .any(|model| model.language == "eng")
+    );
+}
+
+#[tokio::test]
+async fn test_grpc_health_unavailable_when_queue_is_full() {
+    let mut app = TestApp::new();
+    app.app_state.app_config.health.max_queue_depth = 1;
+    let _job = app.app_state.jobs.create(1, None).unwrap();
+    let address = app.spawn_grpc().await;
+    let mut client = OcrServiceClient::connect(format!("http://{address}"))
+        .await
+        .unwrap();
+
+    let status = client.health(HealthRequest {}).await.unwrap_err();
+
+    assert_eq!(status.code(), Code::Unavailable);
+    assert!(status.message().starts_with("queue: "));
+}
+
+#[tokio::test]
+async fn test_grpc_recognize() {
+    let app = TestApp::new();
+    let address = app.spawn_grpc().await;
+    let mut client = OcrServiceClient::connect(format!("http://{address}"))
+        .await
+        .unwrap();
+
+    let image = read("tests/images/tessdoc-introduction.png").await.unwrap();
+    let response = client
+        .recognize(RecognizeRequest {
+            image,
+            content_type: Some("image/png".to_owned()),
+            language: Some("eng".to_owned()),
+            model: None,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(response.text.starts_with("Introduction"));
+
+    let status = client
+        .recognize(RecognizeRequest {
+            image: Vec::new(),
+            content_type: None,
+            language: Some("xyz".to_owned()),
+            model: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_grpc_message_size_follows_upload_limit() {
+    let mut app = TestApp::new();
+    app.app_state.app_config.server.file_upload_max_size = 16;
+    app.app_state.app_config.server.file_upload_max_size_enabled = false;
+    let address = app.spawn_grpc().await;
+    let mut client = OcrServiceClient::connect(format!("http://{address}"))
+        .await
+        .unwrap();
+
+    // Not an image, but larger than the disabled upload limit, so it reaches the decoder.
+    let status = client
+        .recognize(RecognizeRequest {
+            image: vec![0; 1024],
+            content_type: Some("image/png".to_owned()),
+            language: Some("eng".to_owned()),
+            model: None,
+        })
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), Code::InvalidArgument, "{status:?}");
+}
@@ -2,14 +2,19 @@ use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
+use http_body_util::BodyExt as _;
+use ocr_service::{AppState, router_with_state};
+use serde_json::Value;
 
 use crate::helpers::*;
 
 #[tokio::test]
-async fn test_health_check_ok() {
+async fn test_health_live_ok() {
     let app = TestApp::new();
 
-    let req = Request::get("/system/health").body(Body::empty()).unwrap();
+    let req = Request::get("/system/health/live")
+        .body(Body::empty())
+        .unwrap();
     let resp = app.request(req).await;
     let headers = resp.headers().clone();
 
@@ -17,3 +22,83 @@ async fn test_health_check_ok() {
     assert_eq!(headers.get("access-control-allow-origin").unwrap(), "*");
     assert!(headers.get("vary").is_some());
 }
+
+#[tokio::test]
+async fn test_health_alias_of_live() {
+    let app = TestApp::new();
+
+    let req = Request::get("/system/health").body(Body::empty()).unwrap();
+    let resp = app.request(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body, serde_json::json!({"status": "ok"}));
+}
+
+#[tokio::test]
+async fn test_health_ready_ok() {
+    let app = TestApp::new();
+
+    let req = Request::get("/system/health/ready")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: Value =
+        serde_json::from_slice(&resp.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(body["status"], "ok");
+    let components: Vec<&str> = body["components"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|component| component["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(components, ["tessdata", "default_model", "queue"]);
+}
+
+#[tokio::test]
+async fn test_health_ready_unavailable_when_queue_is_full() {
+    let mut app = TestApp::new();
+    app.app_state.app_config.health.max_queue_depth = 1;
+    let _job = app.app_state.jobs.create(1, None).unwrap();
+    app.router = router_with_state(app.app_state.clone());
+
+    let req = Request::get("/system/health/ready")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value =
+        serde_json::from_slice(&resp.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(body["status"], "error");
+    assert_eq!(body["components"][2]["name"], "queue");
+    assert_eq!(body["components"][2]["status"], "error");
+}
+
+#[tokio::test]
+async fn test_health_ready_hides_data_path_and_skips_self_test_without_eng() {
+    let mut app = TestApp::new();
+    let tessdata = tempfile::tempdir().unwrap();
+    let data_path = tessdata.path().to_string_lossy().to_string();
+    let mut app_config = app.app_state.app_config.clone();
+    app_config.tesseract.data_path = data_path.clone();
+    app_config.health.self_test_enabled = true;
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+
+    let req = Request::get("/system/health/ready")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    assert!(!String::from_utf8_lossy(&body).contains(&data_path));
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["components"][0]["message"], "readable");
+    assert_eq!(body["components"][3]["name"], "self_test");
+    assert_eq!(body["components"][3]["status"], "ok");
+    assert_eq!(body["components"][3]["message"], "Skipped, no 'eng' model");
+}
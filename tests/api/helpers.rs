@@ -1,9 +1,15 @@
+use std::net::SocketAddr;
+
 use axum::{Router, body::Body, http::Request, http::Response};
+use tokio::net::TcpListener;
 use tower::ServiceExt as _;
 
-use ocr_service::{config::app_config::app_config, router};
+use ocr_service::{
+    AppState, config::app_config::app_config, grpc::grpc_service, router_with_state,
+};
 
 pub struct TestApp {
+    pub app_state: AppState,
     pub router: Router,
 }
 
@@ -20,11 +26,69 @@ impl TestApp {
         // This will exit with a help message if something is wrong.
         let app_config = app_config().to_owned();
 
-        let router = router(app_config);
-        Self { router }
+        let app_state = AppState::new(app_config);
+        let router = router_with_state(app_state.clone());
+        Self { app_state, router }
     }
 
     pub async fn request(&self, req: Request<Body>) -> Response<Body> {
         self.router.clone().oneshot(req).await.unwrap()
     }
+
+    /// Serve the app on an OS assigned port, for tests that need a real connection (e.g. WebSockets).
+    pub async fn spawn(&self) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let router = self.router.clone();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap()
+        });
+        address
+    }
+
+    /// Serve the gRPC API on an OS assigned port.
+    pub async fn spawn_grpc(&self) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let incoming =
+            tonic::transport::server::TcpIncoming::from_listener(listener, true, None).unwrap();
+        let service = grpc_service(self.app_state.clone());
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(incoming)
+                .await
+                .unwrap()
+        });
+        address
+    }
+}
+
+/// The boundary of the bodies created by `create_multipart_body`.
+pub const BOUNDARY: &str = "test_boundary";
+
+/// Create a multipart form body with one part per `(field name, file name, content type, data)`.
+pub fn create_multipart_body(files: &[(&str, &str, &str, &[u8])]) -> Body {
+    let mut body = Vec::new();
+
+    for (field_name, filename, content_type, data) in files {
+        body.extend_from_slice(format!("--{BOUNDARY}\r\n").as_bytes());
+        body.extend_from_slice(
+            format!(
+                "Content-Disposition: form-data; name=\"{field_name}\"; filename=\"{filename}\"\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(format!("Content-Type: {content_type}\r\n\r\n").as_bytes());
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+
+    Body::from(body)
 }
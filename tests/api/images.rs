@@ -1,9 +1,12 @@
-use axum::{
-    body::Body,
-    http::{Request, StatusCode, header::CONTENT_TYPE},
+use axum::http::{
+    Request, StatusCode,
+    header::{ACCEPT_LANGUAGE, CONTENT_TYPE},
 };
+use futures_util::{SinkExt as _, StreamExt as _};
 use http_body_util::BodyExt as _;
+use ocr_service::{AppState, router_with_state};
 use tokio::fs::read;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
 
 use crate::helpers::*;
 
@@ -15,7 +18,12 @@ async fn test_images_endpoint_tessdoc_introduction() {
     let image_data = read("tests/images/tessdoc-introduction.png").await.unwrap();
 
     // Create multipart form data
-    let body = create_multipart_body("image", "tessdoc-introduction.png", &image_data);
+    let body = create_multipart_body(&[(
+        "image",
+        "tessdoc-introduction.png",
+        "image/png",
+        &image_data,
+    )]);
 
     let req = Request::post("/api/v1/images")
         .header(
@@ -37,27 +45,111 @@ async fn test_images_endpoint_tessdoc_introduction() {
     });
 }
 
-// Helper constant for multipart boundary
-const BOUNDARY: &str = "test_boundary";
+#[tokio::test]
+async fn test_images_cache_hit() {
+    let mut app = TestApp::new();
+    let mut app_config = app.app_state.app_config.clone();
+    app_config.cache.enabled = true;
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
 
-// Helper function to create multipart form data
-fn create_multipart_body(field_name: &str, filename: &str, data: &[u8]) -> Body {
-    let mut body = Vec::new();
+    let image_data = read("tests/images/tessdoc-introduction.png").await.unwrap();
+    let mut texts = Vec::new();
+    for expected in ["miss", "hit"] {
+        let body = create_multipart_body(&[(
+            "image",
+            "tessdoc-introduction.png",
+            "image/png",
+            &image_data,
+        )]);
+        let req = Request::post("/api/v1/images")
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            )
+            .body(body)
+            .unwrap();
 
-    // Add multipart boundary and headers
-    body.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
-    body.extend_from_slice(
-        format!(
-            "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n",
-            field_name, filename
-        )
-        .as_bytes(),
+        let response = app.request(req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-cache"], expected);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        texts.push(body["text"].clone());
+    }
+    assert_eq!(texts[0], texts[1]);
+}
+
+#[tokio::test]
+async fn test_images_stream_frame_result() {
+    let app = TestApp::new();
+    let address = app.spawn().await;
+
+    let (mut socket, _) =
+        connect_async(format!("ws://{address}/api/v1/images/stream?language=eng"))
+            .await
+            .unwrap();
+
+    let image_data = read("tests/images/tessdoc-introduction.png").await.unwrap();
+    socket.send(Message::binary(image_data)).await.unwrap();
+
+    let message = socket.next().await.unwrap().unwrap();
+    let message: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+    assert_eq!(message["type"], "result");
+    assert_eq!(message["frame_id"], 1);
+    assert!(
+        message["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("Introduction")
     );
-    body.extend_from_slice("Content-Type: image/png\r\n\r\n".as_bytes());
+}
 
-    // Add file data
-    body.extend_from_slice(data);
-    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+/// Post an image that is never recognized, because the language resolves to `chi_sim`, which
+/// has several models and no default model.
+async fn post_image_language_error(uri: &str, accept_language: Option<&str>) -> String {
+    let app = TestApp::new();
+    let image_data = read("tests/images/tessdoc-introduction.png").await.unwrap();
+    let body = create_multipart_body(&[(
+        "image",
+        "tessdoc-introduction.png",
+        "image/png",
+        &image_data,
+    )]);
 
-    Body::from(body)
+    let mut req = Request::post(uri).header(
+        CONTENT_TYPE,
+        format!("multipart/form-data; boundary={}", BOUNDARY),
+    );
+    if let Some(accept_language) = accept_language {
+        req = req.header(ACCEPT_LANGUAGE, accept_language);
+    }
+    let response = app.request(req.body(body).unwrap()).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    body["message"].as_str().unwrap().to_owned()
+}
+
+#[tokio::test]
+async fn test_images_language_alias() {
+    let message = post_image_language_error("/api/v1/images?language=zh-Hans", None).await;
+    assert_eq!(
+        message,
+        "Multiple models available for language 'chi_sim', please specify a model"
+    );
+
+    let message = post_image_language_error("/api/v1/images?language=zh-TW", None).await;
+    assert_eq!(message, "Language 'zh-TW' is not available");
+}
+
+#[tokio::test]
+async fn test_images_accept_language() {
+    let message =
+        post_image_language_error("/api/v1/images", Some("fr-CH, fr;q=0.9, zh-CN;q=0.8")).await;
+    assert_eq!(
+        message,
+        "Multiple models available for language 'chi_sim', please specify a model"
+    );
 }
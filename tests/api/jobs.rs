@@ -0,0 +1,111 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header::CONTENT_TYPE},
+};
+use http_body_util::BodyExt as _;
+use ocr_service::config::app_config::parse_api_keys;
+use ocr_service::{AppState, router_with_state};
+use sha2::{Digest, Sha256};
+use tokio::fs::read;
+
+use crate::helpers::*;
+
+#[tokio::test]
+async fn test_jobs_events_stream_result() {
+    let app = TestApp::new();
+
+    let image_data = read("tests/images/tessdoc-introduction.png").await.unwrap();
+    let body = create_multipart_body(&[
+        ("page", "page-1.png", "image/png", &image_data),
+        ("page", "page-2.png", "image/png", &image_data),
+    ]);
+
+    let req = Request::post("/api/v1/jobs")
+        .header(
+            CONTENT_TYPE,
+            format!("multipart/form-data; boundary={}", BOUNDARY),
+        )
+        .body(body)
+        .unwrap();
+    let response = app.request(req).await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let job: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(job["pages"], 2);
+    let id = job["id"].as_str().unwrap();
+
+    let req = Request::get(format!("/api/v1/jobs/{id}/events"))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        "text/event-stream"
+    );
+
+    // The stream ends after the final event, so the whole body can be collected.
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let events = String::from_utf8(body.to_vec()).unwrap();
+    assert_eq!(events.matches("event: page").count(), 2);
+    assert!(
+        events
+            .trim_end()
+            .split("\n\n")
+            .last()
+            .unwrap()
+            .starts_with("event: result")
+    );
+}
+
+#[tokio::test]
+async fn test_jobs_events_not_found() {
+    let app = TestApp::new();
+
+    let req = Request::get("/api/v1/jobs/01890a5d-ac96-774b-bcce-b302099a8057/events")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.request(req).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_jobs_only_readable_by_owner() {
+    let mut app = TestApp::new();
+    let hash = |key: &str| format!("{:x}", Sha256::digest(key));
+    let mut app_config = app.app_state.app_config.clone();
+    app_config.security.api_keys = parse_api_keys(&format!(
+        "owner:{}:ocr,other:{}:ocr",
+        hash("owner-key"),
+        hash("other-key")
+    ))
+    .unwrap();
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+
+    let sender = app
+        .app_state
+        .jobs
+        .create(1, Some("subject:owner".to_owned()))
+        .unwrap();
+    let id = sender.borrow().id;
+
+    for (key, status) in [
+        ("owner-key", StatusCode::OK),
+        ("other-key", StatusCode::NOT_FOUND),
+    ] {
+        for uri in [
+            format!("/api/v1/jobs/{id}"),
+            format!("/api/v1/jobs/{id}/events"),
+        ] {
+            let req = Request::get(&uri)
+                .header("x-api-key", key)
+                .body(Body::empty())
+                .unwrap();
+            let response = app.request(req).await;
+            assert_eq!(response.status(), status, "{key} {uri}");
+        }
+    }
+}
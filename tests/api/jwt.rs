@@ -0,0 +1,218 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{
+    Router,
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode, header::AUTHORIZATION},
+    routing::get,
+};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use http_body_util::BodyExt as _;
+use jsonwebtoken::{Algorithm, EncodingKey, Header, encode, get_current_timestamp};
+use ocr_service::config::app_config::Scope;
+use ocr_service::middleware::auth::{Principal, require_scope};
+use ocr_service::{AppState, router_with_state};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair as _};
+use serde_json::{Value, json};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tower::ServiceExt as _;
+
+use crate::helpers::*;
+
+const ISSUER: &str = "https://issuer.example.com";
+const AUDIENCE: &str = "ocr-service";
+
+/// A locally generated Ed25519 signing key, published in the JWKS under `kid`.
+struct TestKey {
+    kid: &'static str,
+    pkcs8: Vec<u8>,
+}
+
+impl TestKey {
+    fn generate(kid: &'static str) -> Self {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Self {
+            kid,
+            pkcs8: pkcs8.as_ref().to_vec(),
+        }
+    }
+
+    fn jwk(&self) -> Value {
+        let key_pair = Ed25519KeyPair::from_pkcs8(&self.pkcs8).unwrap();
+        json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "alg": "EdDSA",
+            "use": "sig",
+            "kid": self.kid,
+            "x": URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+        })
+    }
+
+    /// A token with valid registered claims, overridden by `claims`.
+    fn token(&self, claims: Value) -> String {
+        let mut payload = json!({
+            "iss": ISSUER,
+            "aud": AUDIENCE,
+            "sub": "user-1",
+            "exp": get_current_timestamp() + 300,
+            "tenant_id": "acme",
+            "scope": "openid ocr languages:read",
+        });
+        payload
+            .as_object_mut()
+            .unwrap()
+            .extend(claims.as_object().unwrap().clone());
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(self.kid.to_owned());
+        encode(&header, &payload, &EncodingKey::from_ed_der(&self.pkcs8)).unwrap()
+    }
+}
+
+fn jwks(keys: &[&TestKey]) -> String {
+    json!({"keys": keys.iter().map(|key| key.jwk()).collect::<Vec<_>>()}).to_string()
+}
+
+/// A test app validating JWTs against `jwks`, a path or a URL.
+fn jwt_app(jwks: String, refresh_interval: Duration) -> TestApp {
+    let mut app = TestApp::new();
+    let mut app_config = app.app_state.app_config.clone();
+    app_config.security.jwt.jwks = Some(jwks);
+    app_config.security.jwt.jwks_refresh_interval = refresh_interval;
+    app_config.security.jwt.issuer = Some(ISSUER.to_owned());
+    app_config.security.jwt.audience = Some(AUDIENCE.to_owned());
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+    app
+}
+
+fn languages(token: &str) -> Request<Body> {
+    Request::get("/api/v1/languages")
+        .header(AUTHORIZATION, format!("Bearer {token}"))
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_jwt_validation() {
+    let key = TestKey::generate("key-1");
+    let unknown_key = TestKey::generate("key-2");
+    let directory = TempDir::new().unwrap();
+    let jwks_path = directory.path().join("jwks.json");
+    std::fs::write(&jwks_path, jwks(&[&key])).unwrap();
+    let app = jwt_app(
+        jwks_path.to_string_lossy().to_string(),
+        Duration::from_secs(300),
+    );
+
+    let response = app.request(languages(&key.token(json!({})))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    for (token, message) in [
+        (
+            key.token(json!({"exp": get_current_timestamp() - 600})),
+            "The bearer token has expired",
+        ),
+        (
+            key.token(json!({"aud": "other-service"})),
+            "The bearer token has an invalid audience",
+        ),
+        (
+            key.token(json!({"iss": "https://other.example.com"})),
+            "The bearer token has an invalid issuer",
+        ),
+        (
+            unknown_key.token(json!({})),
+            "The bearer token is signed with an unknown key",
+        ),
+        ("not.a.jwt".to_owned(), "Invalid bearer token"),
+    ] {
+        let response = app.request(languages(&token)).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{message}");
+        let body: Value =
+            serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes())
+                .unwrap();
+        assert_eq!(body["message"], message);
+    }
+
+    let response = app
+        .request(languages(&key.token(json!({"scope": "ocr"}))))
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_jwks_url_is_refreshed() {
+    let old_key = TestKey::generate("old");
+    let new_key = TestKey::generate("new");
+    let published = Arc::new(Mutex::new(jwks(&[&old_key])));
+    let jwks_router = Router::new()
+        .route(
+            "/jwks.json",
+            get(|State(published): State<Arc<Mutex<String>>>| async move {
+                published.lock().unwrap().clone()
+            }),
+        )
+        .with_state(published.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, jwks_router).await.unwrap() });
+    let app = jwt_app(format!("http://{address}/jwks.json"), Duration::ZERO);
+
+    let response = app.request(languages(&old_key.token(json!({})))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The keys are rotated.
+    *published.lock().unwrap() = jwks(&[&new_key]);
+    let response = app.request(languages(&new_key.token(json!({})))).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.request(languages(&old_key.token(json!({})))).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_principal_extractor() {
+    let key = TestKey::generate("key-1");
+    let directory = TempDir::new().unwrap();
+    let jwks_path = directory.path().join("jwks.json");
+    std::fs::write(&jwks_path, jwks(&[&key])).unwrap();
+    let app = jwt_app(
+        jwks_path.to_string_lossy().to_string(),
+        Duration::from_secs(300),
+    );
+    let router = Router::new()
+        .route(
+            "/tenant",
+            get(|principal: Principal| async move {
+                format!(
+                    "{}:{}",
+                    principal.subject,
+                    principal.tenant_id.unwrap_or_default()
+                )
+            }),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            (app.app_state.clone(), Scope::Ocr),
+            require_scope,
+        ));
+
+    let req = Request::get("/tenant")
+        .header(
+            AUTHORIZATION,
+            format!(
+                "Bearer {}",
+                key.token(json!({"sub": "user-2", "tenant_id": "globex"}))
+            ),
+        )
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, "user-2:globex");
+}
@@ -1,8 +1,36 @@
 use axum::{body::Body, http::Request, http::StatusCode};
 use http_body_util::BodyExt as _;
+use ocr_service::{AppState, router_with_state};
+use serde_json::Value;
 
 use crate::helpers::*;
 
+async fn get_json(app: &TestApp, uri: &str) -> (StatusCode, Value) {
+    let req = Request::get(uri).body(Body::empty()).unwrap();
+    let response = app.request(req).await;
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn get_languages(app: &TestApp) -> Vec<Value> {
+    let (status, body) = get_json(app, "/api/v1/languages").await;
+    assert_eq!(status, StatusCode::OK);
+    body["languages"].as_array().unwrap().clone()
+}
+
+/// The `language/model` of the listed models.
+async fn get_model_names(app: &TestApp, uri: &str) -> Vec<String> {
+    let (status, body) = get_json(app, uri).await;
+    assert_eq!(status, StatusCode::OK);
+    body["languages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|model| format!("{}/{}", model["language"], model["model"]).replace('"', ""))
+        .collect()
+}
+
 #[tokio::test]
 async fn test_languages_ok() {
     let app = TestApp::new();
@@ -17,6 +45,123 @@ async fn test_languages_ok() {
     assert_eq!(headers.get("access-control-allow-origin").unwrap(), "*");
     assert!(headers.get("vary").is_some());
     let body = response.into_body().collect().await.unwrap().to_bytes();
-    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let mut body: Value = serde_json::from_slice(&body).unwrap();
+
+    // The metadata depends on the model files, so it is checked here instead of in the snapshot.
+    for language in body["languages"].as_array_mut().unwrap() {
+        let language = language.as_object_mut().unwrap();
+        assert!(language.remove("size").unwrap().as_u64().unwrap() > 0);
+        let sha256 = language.remove("sha256").unwrap();
+        let sha256 = sha256.as_str().unwrap();
+        assert_eq!(sha256.len(), 64);
+        assert!(sha256.chars().all(|char| char.is_ascii_hexdigit()));
+        let engine_modes = language.remove("engine_modes").unwrap();
+        assert!(!engine_modes.as_array().unwrap().is_empty());
+        language.remove("version");
+    }
     insta::assert_yaml_snapshot!(body);
 }
+
+#[tokio::test]
+async fn test_languages_expose_model_paths() {
+    let mut app = TestApp::new();
+    let languages = get_languages(&app).await;
+    assert!(
+        languages
+            .iter()
+            .all(|language| language.get("full_path").is_none()
+                && language.get("relative_path").is_none())
+    );
+
+    let mut app_config = app.app_state.app_config.clone();
+    app_config.security.expose_model_paths = true;
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+
+    let languages = get_languages(&app).await;
+    let eng = languages
+        .iter()
+        .find(|language| language["language"] == "eng" && language["model"].is_null())
+        .unwrap();
+    assert_eq!(eng["relative_path"], "eng");
+    assert!(
+        eng["full_path"]
+            .as_str()
+            .unwrap()
+            .ends_with("eng.traineddata")
+    );
+}
+
+#[tokio::test]
+async fn test_languages_filters() {
+    let app = TestApp::new();
+
+    assert_eq!(
+        get_model_names(&app, "/api/v1/languages?vertical=true").await,
+        ["chi_sim/chi_sim_vert"]
+    );
+    assert_eq!(
+        get_model_names(&app, "/api/v1/languages?vertical=false&script=hans").await,
+        ["chi_sim/chi_sim"]
+    );
+    assert_eq!(
+        get_model_names(&app, "/api/v1/languages?script=Latn").await,
+        ["eng/null"]
+    );
+    assert_eq!(
+        get_model_names(&app, "/api/v1/languages?model=default").await,
+        ["eng/null"]
+    );
+    assert!(
+        get_model_names(&app, "/api/v1/languages?script=Cyrl")
+            .await
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_languages_pagination() {
+    let app = TestApp::new();
+
+    let (status, body) = get_json(&app, "/api/v1/languages?page=2&per_page=2").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["languages"].as_array().unwrap().len(), 1);
+    assert_eq!(body["languages"][0]["language"], "eng");
+    assert_eq!(
+        body["pagination"],
+        serde_json::json!({"page": 2, "per_page": 2, "total": 3})
+    );
+
+    let (status, body) = get_json(&app, "/api/v1/languages?page=3&per_page=2").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["languages"].as_array().unwrap().is_empty());
+
+    let (status, _) = get_json(&app, "/api/v1/languages?per_page=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get_json(&app, "/api/v1/languages?page=0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_language_detail() {
+    let app = TestApp::new();
+
+    let (status, body) = get_json(&app, "/api/v1/languages/zh-Hans").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["language"], "chi_sim");
+    assert_eq!(body["script"], "Hans");
+    assert!(body["aliases"].as_array().unwrap().contains(&"zh".into()));
+    let models: Vec<&Value> = body["models"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|model| &model["model"])
+        .collect();
+    assert_eq!(models, ["chi_sim", "chi_sim_vert"]);
+    assert!(body["models"][0]["sha256"].is_string());
+    assert!(body["models"][0].get("full_path").is_none());
+
+    let (status, body) = get_json(&app, "/api/v1/languages/fra").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Language 'fra' is not available");
+}
@@ -1,4 +1,13 @@
+mod admin;
+mod api_keys;
+mod cors;
+mod grpc;
 mod health;
 mod helpers;
 mod images;
+mod jobs;
+mod jwt;
 mod languages;
+mod metrics;
+mod rate_limit;
+mod request_id;
@@ -0,0 +1,125 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use std::sync::{Arc, Mutex};
+
+use http_body_util::BodyExt as _;
+use ocr_service::{
+    models::images::ImagesQueryParams,
+    router_with_state,
+    utils::{
+        metrics::model_attributes,
+        ocr::{decode_image, recognize_image},
+        validations::validate_language_params,
+    },
+};
+use tracing::{
+    Subscriber,
+    span::{Attributes, Id},
+};
+use tracing_subscriber::{
+    Layer,
+    layer::{Context, SubscriberExt as _},
+    registry::LookupSpan,
+};
+
+use crate::helpers::*;
+
+#[tokio::test]
+async fn test_metrics_not_found_without_prometheus() {
+    let app = TestApp::new();
+
+    let req = Request::get("/system/metrics").body(Body::empty()).unwrap();
+    let resp = app.request(req).await;
+
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_metrics_prometheus_text_format() {
+    let mut app = TestApp::new();
+    let registry = prometheus::Registry::new();
+    let counter = prometheus::IntCounter::new("ocr_test_total", "A test counter").unwrap();
+    registry.register(Box::new(counter.clone())).unwrap();
+    counter.inc();
+    app.app_state.prometheus_registry = Some(registry);
+    app.router = router_with_state(app.app_state.clone());
+
+    let req = Request::get("/system/metrics").body(Body::empty()).unwrap();
+    let resp = app.request(req).await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/plain; version=0.0.4"
+    );
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("ocr_test_total 1"));
+}
+
+/// The name of a created span and the name of its parent.
+#[derive(Clone)]
+struct RecordedSpan {
+    name: String,
+    parent: Option<String>,
+}
+
+/// Records every span that is created.
+#[derive(Clone, Default)]
+struct RecordedSpans(Arc<Mutex<Vec<RecordedSpan>>>);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RecordedSpans {
+    fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let parent = ctx
+            .span(id)
+            .and_then(|span| span.parent())
+            .map(|parent| parent.name().to_owned());
+        self.0.lock().unwrap().push(RecordedSpan {
+            name: attributes.metadata().name().to_owned(),
+            parent,
+        });
+    }
+}
+
+#[test]
+fn test_ocr_spans() {
+    let app = TestApp::new();
+    let app_config = &app.app_state.app_config;
+    let tesseract_model = validate_language_params(
+        &ImagesQueryParams::new(Some("eng".to_owned()), None),
+        &app.app_state.available_tesseract_languages.load(),
+        &app_config.service.default_language,
+        &app_config.tesseract.model_preferences,
+    )
+    .unwrap();
+    let image_data = std::fs::read("tests/images/tessdoc-introduction.png").unwrap();
+
+    let spans = RecordedSpans::default();
+    let subscriber = tracing_subscriber::registry().with(spans.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        let img = decode_image(&image_data, &model_attributes(&tesseract_model)).unwrap();
+        recognize_image(
+            &app_config.tesseract.data_path,
+            &tesseract_model,
+            &img,
+            None,
+        )
+        .unwrap();
+    });
+
+    let spans = spans.0.lock().unwrap().clone();
+    let span = |name: &str| {
+        spans
+            .iter()
+            .find(|span| span.name == name)
+            .map(|span| span.parent.as_deref())
+            .unwrap_or_else(|| panic!("No span named {name}"))
+    };
+    assert_eq!(span("ocr.decode"), None);
+    assert_eq!(span("ocr.recognize_image"), None);
+    for name in ["ocr.preprocess", "ocr.engine_init", "ocr.recognition"] {
+        assert_eq!(span(name), Some("ocr.recognize_image"), "{name}");
+    }
+}
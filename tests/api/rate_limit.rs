@@ -0,0 +1,99 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header::RETRY_AFTER},
+};
+use futures_util::{SinkExt as _, StreamExt as _};
+use ocr_service::{AppState, router_with_state};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+use crate::helpers::*;
+
+/// A test app that allows each client two recognitions, and one more per minute.
+fn rate_limited_app() -> TestApp {
+    let mut app = TestApp::new();
+    let mut app_config = app.app_state.app_config.clone();
+    app_config.rate_limit.enabled = true;
+    app_config.rate_limit.requests_per_minute = 1;
+    app_config.rate_limit.burst = 2;
+    app_config.rate_limit.trust_forwarded_for = true;
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+    app
+}
+
+/// A job request that is rejected after taking a token, so no recognition runs.
+fn create_job(forwarded_for: &str) -> Request<Body> {
+    Request::post("/api/v1/jobs?language=invalid")
+        .header("x-forwarded-for", forwarded_for)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let app = rate_limited_app();
+
+    for _ in 0..2 {
+        let response = app.request(create_job("10.0.0.1")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let response = app.request(create_job("10.0.0.1")).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let headers = response.headers();
+    let retry_after: u64 = headers[RETRY_AFTER].to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry_after));
+    assert_eq!(headers["ratelimit-limit"], "2");
+    assert_eq!(headers["ratelimit-remaining"], "0");
+    assert_eq!(headers["ratelimit-reset"], headers[RETRY_AFTER]);
+
+    // Other clients have their own quotas.
+    let response = app.request(create_job("10.0.0.2")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_rate_limit_does_not_apply_to_other_routes() {
+    let app = rate_limited_app();
+
+    for _ in 0..3 {
+        let req = Request::get("/api/v1/languages")
+            .header("x-forwarded-for", "10.0.0.1")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.request(req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limit_stream_frames() {
+    let mut app = rate_limited_app();
+    let mut app_config = app.app_state.app_config.clone();
+    // The only token is taken by the upgrade request and pays for the first frame.
+    app_config.rate_limit.burst = 1;
+    app.app_state = AppState::new(app_config);
+    app.router = router_with_state(app.app_state.clone());
+    let address = app.spawn().await;
+
+    let (mut socket, _) =
+        connect_async(format!("ws://{address}/api/v1/images/stream?language=eng"))
+            .await
+            .unwrap();
+    // Frames that are not images fail to decode, so no recognition runs.
+    socket.send(Message::binary(vec![0u8; 16])).await.unwrap();
+    socket.send(Message::binary(vec![0u8; 16])).await.unwrap();
+
+    let mut messages = Vec::new();
+    for _ in 0..2 {
+        let message = socket.next().await.unwrap().unwrap();
+        let message: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+        messages.push(message);
+    }
+    messages.sort_by_key(|message| message["frame_id"].as_u64());
+    assert_eq!(messages[0]["frame_id"], 1);
+    assert_eq!(messages[0]["type"], "error");
+    assert_eq!(messages[1]["frame_id"], 2);
+    assert_eq!(messages[1]["type"], "rate_limited");
+    assert!((1..=60).contains(&messages[1]["retry_after"].as_u64().unwrap()));
+}
@@ -0,0 +1,65 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use http_body_util::BodyExt as _;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::helpers::*;
+
+#[tokio::test]
+async fn test_request_id_generated() {
+    let app = TestApp::new();
+
+    let req = Request::get("/system/health/live")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let request_id = resp
+        .headers()
+        .get("x-request-id")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    let request_id = Uuid::parse_str(request_id).unwrap();
+    assert_eq!(request_id.get_version_num(), 7);
+}
+
+#[tokio::test]
+async fn test_request_id_propagated() {
+    let app = TestApp::new();
+
+    let req = Request::get("/system/health/live")
+        .header("x-request-id", "client-request-42")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+
+    assert_eq!(
+        resp.headers().get("x-request-id").unwrap(),
+        "client-request-42"
+    );
+}
+
+#[tokio::test]
+async fn test_request_id_in_error_response() {
+    let app = TestApp::new();
+
+    let req = Request::get("/api/v1/jobs/01890a5d-ac96-774b-bcce-b302099a8057")
+        .header("x-request-id", "client-request-43")
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.request(req).await;
+
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        resp.headers().get("x-request-id").unwrap(),
+        "client-request-43"
+    );
+    let body: Value =
+        serde_json::from_slice(&resp.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert_eq!(body["request_id"], "client-request-43");
+}